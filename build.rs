@@ -1,37 +1,74 @@
 // Adopted from https://github.com/rust-rse/reed-solomon-erasure/blob/master/build.rs
+use std::env;
 use std::fs::File;
 use std::io::Write;
+use std::path::Path;
 
-const PRIME_POLY: usize = 29;
+include!("src/backend/params.rs");
 
-const FIELD_SIZE: usize = 256;
-const EXP_TABLE_SIZE: usize = 2 * FIELD_SIZE - 2;
+/// Multiplies `a` and `b` in GF(2^bits) modulo `poly`.
+fn gf_mul(mut a: usize, mut b: usize, poly: usize, bits: usize) -> usize {
+    let mut result = 0;
 
-fn gen_log_exp_table(poly: usize) -> ([u8; FIELD_SIZE], [u8; EXP_TABLE_SIZE]) {
-    let mut log_table = [0u8; FIELD_SIZE];
-    let mut exp_table = [0u8; EXP_TABLE_SIZE];
+    while b > 0 {
+        if b & 1 == 1 {
+            result ^= a;
+        }
+        b >>= 1;
+
+        a <<= 1;
+        if a >> bits == 1 {
+            a ^= poly;
+        }
+    }
+
+    result
+}
+
+/// Generates the log and exp tables of GF(2^bits) from successive powers of `generator`.
+///
+/// The exp table is doubled so that `EXP_TABLE[LOG_TABLE[a] + LOG_TABLE[b]]` needs no
+/// reduction. Panics unless the powers of `generator` run through every non-zero element,
+/// i.e. unless `poly` is primitive and `generator` generates the multiplicative group.
+fn gen_log_exp_table(bits: usize, poly: usize, generator: usize) -> (Vec<usize>, Vec<usize>) {
+    let field_size = 1 << bits;
+    assert!(
+        poly >> bits == 1,
+        "polynomial {:#x} is not of degree {}",
+        poly,
+        bits
+    );
+
+    let mut log_table = vec![0; field_size];
+    let mut exp_table = vec![0; 2 * field_size - 2];
+    let mut seen = vec![false; field_size];
 
     let mut x: usize = 1;
 
-    for i in 0..FIELD_SIZE - 1 {
-        exp_table[i] = x as u8;
-        exp_table[i + FIELD_SIZE - 1] = x as u8;
-        log_table[x] = i as u8;
+    for i in 0..field_size - 1 {
+        assert!(
+            !seen[x],
+            "polynomial {:#x} with generator {} is not primitive: period {}",
+            poly, generator, i
+        );
+        seen[x] = true;
 
-        x <<= 1;
+        exp_table[i] = x;
+        exp_table[i + field_size - 1] = x;
+        log_table[x] = i;
 
-        if x >= FIELD_SIZE {
-            x = (x - FIELD_SIZE) ^ poly;
-        }
+        x = gf_mul(x, generator, poly, bits);
     }
 
+    assert_eq!(x, 1, "generator {} does not have full period", generator);
+
     (log_table, exp_table)
 }
 
 macro_rules! write_table {
     ($file:ident, $table:ident, $name:expr, $type:expr) => {
         let len = $table.len();
-        let mut table_str = String::from(format!("pub const {}: [{}; {}] = [", $name, $type, len));
+        let mut table_str = String::from(format!("pub static {}: [{}; {}] = [", $name, $type, len));
 
         for v in $table.iter() {
             let val_str = format!("{} ,", v);
@@ -45,14 +82,26 @@ macro_rules! write_table {
 }
 
 fn generate_tables() {
-    let (log_table, exp_table) = gen_log_exp_table(PRIME_POLY);
+    let out_dir = env::var("OUT_DIR").unwrap();
 
-    let mut f = File::create("src/backend/table.rs").unwrap();
+    let (log_table, exp_table) = gen_log_exp_table(8, GF2_8_PRIME_POLY, GF2_8_GENERATOR);
+
+    let mut f = File::create(Path::new(&out_dir).join("table8.rs")).unwrap();
 
     write_table!(f, log_table, "LOG_TABLE", "u8");
     write_table!(f, exp_table, "EXP_TABLE", "u8");
+
+    let (log_table, exp_table) = gen_log_exp_table(16, GF2_16_PRIME_POLY, GF2_16_GENERATOR);
+
+    let mut f = File::create(Path::new(&out_dir).join("table16.rs")).unwrap();
+
+    write_table!(f, log_table, "LOG_TABLE_16", "u16");
+    write_table!(f, exp_table, "EXP_TABLE_16", "u16");
 }
 
 fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/backend/params.rs");
+
     generate_tables();
 }
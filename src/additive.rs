@@ -0,0 +1,653 @@
+//! Additive FFT over binary fields in the novel polynomial basis of
+//! [Lin, Chung and Han](https://arxiv.org/abs/1404.3458).
+//!
+//! Binary fields have no multiplicative subgroups of power-of-two size, so evaluation
+//! domains are instead the subspaces spanned by the bits `1, 2, 4, ...` of the field
+//! elements. Point `ω_j` is the element whose bits are those of `j`, which makes
+//! `ω_i + ω_j = ω_(i^j)` and lets every shift of a subspace be named by an index.
+//!
+//! With `W_i` the vanishing polynomial of `span(ω_1, .., ω_(2^(i-1)))` and
+//! `Ŵ_i = W_i / W_i(ω_(2^i))`, the novel basis is `X_j = Π Ŵ_i` over the set bits `i` of
+//! `j`. Polynomials of degree `< 2^k` written in this basis are evaluated over any shift
+//! of a `2^k` subspace in `O(2^k k)`.
+
+use num_traits::{One, Zero};
+
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::ff::{BinaryField, Fp};
+use crate::polynomial::Polynomial;
+
+/// Values the transforms can run over: single symbols, or whole shards of symbols
+/// sharing the same evaluation point.
+trait Lane<F: Fp>: Clone {
+    /// `self += factor * other`
+    fn mul_add(&mut self, factor: &FE<F>, other: &Self);
+
+    /// `self += other`
+    fn add(&mut self, other: &Self);
+}
+
+impl<F: Fp> Lane<F> for FE<F> {
+    #[inline]
+    fn mul_add(&mut self, factor: &FE<F>, other: &Self) {
+        *self += *factor * *other;
+    }
+
+    #[inline]
+    fn add(&mut self, other: &Self) {
+        *self += *other;
+    }
+}
+
+/// Shards as bytes, multiplied with [`Fp::mul_add_slice`]
+impl<F: BinaryField> Lane<F> for Vec<u8> {
+    #[inline]
+    fn mul_add(&mut self, factor: &FE<F>, other: &Self) {
+        if factor.is_zero() {
+            return;
+        }
+
+        F::mul_add_slice(factor.value(), other, self);
+    }
+
+    #[inline]
+    fn add(&mut self, other: &Self) {
+        // addition in a binary field is XOR of the representations
+        for (a, b) in self.iter_mut().zip(other.iter()) {
+            *a ^= *b;
+        }
+    }
+}
+
+/// Precomputed subspace polynomials of the novel basis of `F`.
+#[derive(Debug, Clone)]
+pub struct NovelBasis<F: BinaryField> {
+    /// `subspace[i][b] = Ŵ_i(ω_(2^b))`, enough to evaluate the additive `Ŵ_i` anywhere
+    subspace: Vec<Vec<FE<F>>>,
+    /// `linearized[i][t]` is the coefficient of `x^(2^t)` in `Ŵ_i`
+    linearized: Vec<Vec<FE<F>>>,
+}
+
+impl<F: BinaryField> Default for NovelBasis<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: BinaryField> NovelBasis<F> {
+    pub fn new() -> Self {
+        let m = F::DEGREE;
+        let basis: Vec<FE<F>> = (0..m).map(|b| FE::from_u64(1 << b)).collect();
+
+        // W_0(x) = x and W_(i+1)(x) = W_i(x)^2 + W_i(v_i) W_i(x)
+        let mut values = basis.clone();
+        let mut coeffs = vec![FE::<F>::one()];
+        let mut subspace = Vec::with_capacity(m);
+        let mut linearized = Vec::with_capacity(m);
+
+        for i in 0..m {
+            let norm = FE::one() / values[i];
+            subspace.push(values.iter().map(|v| *v * norm).collect());
+            linearized.push(coeffs.iter().map(|c| *c * norm).collect());
+
+            let at_basis = values[i];
+            values = values.iter().map(|v| *v * *v + at_basis * *v).collect();
+
+            let mut next = vec![FE::<F>::zero(); coeffs.len() + 1];
+            for (t, c) in coeffs.iter().enumerate() {
+                next[t] += at_basis * *c;
+                next[t + 1] += *c * *c;
+            }
+            coeffs = next;
+        }
+
+        NovelBasis {
+            subspace,
+            linearized,
+        }
+    }
+
+    /// Returns `Ŵ_i(ω_point)`.
+    #[inline]
+    fn subspace_at(&self, i: usize, point: usize) -> FE<F> {
+        let mut result = FE::zero();
+        let mut bits = point >> i;
+        let mut b = i;
+
+        while bits > 0 {
+            if bits & 1 == 1 {
+                result += self.subspace[i][b];
+            }
+            bits >>= 1;
+            b += 1;
+        }
+
+        result
+    }
+
+    fn check_size(&self, len: usize, shift: usize) -> Result<(), Errors> {
+        if !len.is_power_of_two()
+            || len > 1 << F::DEGREE
+            || shift >= 1 << F::DEGREE
+            || !shift.is_multiple_of(len)
+        {
+            return Err(Errors::InvalidSubspace);
+        }
+
+        Ok(())
+    }
+
+    fn fft_lanes<L: Lane<F>>(&self, values: &mut [L], shift: usize) {
+        let n = values.len();
+        let mut half = n / 2;
+        let mut layer = n.trailing_zeros() as usize;
+
+        while half > 0 {
+            layer -= 1;
+            for offset in (0..n).step_by(2 * half) {
+                let factor = self.subspace_at(layer, offset ^ shift);
+                let (lo, hi) = values[offset..offset + 2 * half].split_at_mut(half);
+                for (a, b) in lo.iter_mut().zip(hi.iter_mut()) {
+                    a.mul_add(&factor, b);
+                    b.add(a);
+                }
+            }
+            half /= 2;
+        }
+    }
+
+    fn ifft_lanes<L: Lane<F>>(&self, values: &mut [L], shift: usize) {
+        let n = values.len();
+        let mut half = 1;
+        let mut layer = 0;
+
+        while half < n {
+            for offset in (0..n).step_by(2 * half) {
+                let factor = self.subspace_at(layer, offset ^ shift);
+                let (lo, hi) = values[offset..offset + 2 * half].split_at_mut(half);
+                for (a, b) in lo.iter_mut().zip(hi.iter_mut()) {
+                    b.add(a);
+                    a.mul_add(&factor, b);
+                }
+            }
+            half *= 2;
+            layer += 1;
+        }
+    }
+
+    /// Evaluates the polynomial with novel basis coefficients `values` at the points
+    /// `ω_(j ^ shift)` in place, where `shift` is a multiple of `values.len()`.
+    pub fn fft(&self, values: &mut [FE<F>], shift: usize) -> Result<(), Errors> {
+        self.check_size(values.len(), shift)?;
+        self.fft_lanes(values, shift);
+        Ok(())
+    }
+
+    /// Inverse of [`NovelBasis::fft`], interpolating novel basis coefficients.
+    pub fn ifft(&self, values: &mut [FE<F>], shift: usize) -> Result<(), Errors> {
+        self.check_size(values.len(), shift)?;
+        self.ifft_lanes(values, shift);
+        Ok(())
+    }
+
+    /// Formal derivative in the novel basis.
+    ///
+    /// Every `Ŵ_i` is linearized, so its derivative is the constant coefficient of `x`
+    /// and `X_j' = Σ Ŵ_i' X_(j - 2^i)` over the set bits `i` of `j`.
+    fn derivative_lanes<L: Lane<F>>(&self, values: &[L], zero: L) -> Vec<L> {
+        let mut result = vec![zero; values.len()];
+
+        for (j, v) in values.iter().enumerate() {
+            let mut bits = j;
+            while bits > 0 {
+                let i = bits.trailing_zeros() as usize;
+                result[j ^ (1 << i)].mul_add(&self.linearized[i][0], v);
+                bits &= bits - 1;
+            }
+        }
+
+        result
+    }
+
+    /// Converts coefficients (lowest degree first, length `2^k`) from the monomial basis
+    /// into the novel basis by recursively dividing by `Ŵ_(k-1)`.
+    pub fn to_novel(&self, coeffs: &mut [FE<F>]) -> Result<(), Errors> {
+        self.check_size(coeffs.len(), 0)?;
+
+        let n = coeffs.len();
+        let mut half = n / 2;
+        while half > 0 {
+            let layer = half.trailing_zeros() as usize;
+            let w = &self.linearized[layer];
+            let lead_inv = FE::one() / w[layer];
+
+            for block in coeffs.chunks_mut(2 * half) {
+                for d in (half..2 * half).rev() {
+                    let q = block[d] * lead_inv;
+                    for (t, c) in w.iter().enumerate().take(layer) {
+                        block[d - half + (1 << t)] -= q * *c;
+                    }
+                    block[d] = q;
+                }
+            }
+            half /= 2;
+        }
+
+        Ok(())
+    }
+
+    /// Inverse of [`NovelBasis::to_novel`].
+    pub fn to_monomial(&self, coeffs: &mut [FE<F>]) -> Result<(), Errors> {
+        self.check_size(coeffs.len(), 0)?;
+
+        let n = coeffs.len();
+        let mut half = 1;
+        while half < n {
+            let layer = half.trailing_zeros() as usize;
+            let w = &self.linearized[layer];
+
+            for block in coeffs.chunks_mut(2 * half) {
+                for d in half..2 * half {
+                    let q = block[d];
+                    block[d] = q * w[layer];
+                    for (t, c) in w.iter().enumerate().take(layer) {
+                        block[d - half + (1 << t)] += q * *c;
+                    }
+                }
+            }
+            half *= 2;
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates `poly` at `ω_(j ^ shift)` for `j` in `0..2^k`, where `shift` is a multiple
+    /// of `2^k`.
+    pub fn evaluate(
+        &self,
+        poly: &Polynomial<FE<F>>,
+        k: usize,
+        shift: usize,
+    ) -> Result<Vec<FE<F>>, Errors> {
+        let n = 1 << k;
+        if poly.coeffs.len() > n {
+            return Err(Errors::InvalidSubspace);
+        }
+
+        let mut coeffs: Vec<FE<F>> = poly.coeffs.iter().rev().cloned().collect();
+        coeffs.resize(n, FE::zero());
+
+        self.to_novel(&mut coeffs)?;
+        self.fft(&mut coeffs, shift)?;
+
+        Ok(coeffs)
+    }
+
+    /// Interpolates the polynomial of degree `< values.len()` taking `values[j]` at
+    /// `ω_(j ^ shift)`, where `shift` is a multiple of `values.len()`.
+    pub fn interpolate(&self, values: &[FE<F>], shift: usize) -> Result<Polynomial<FE<F>>, Errors> {
+        let mut coeffs = values.to_vec();
+
+        self.ifft(&mut coeffs, shift)?;
+        self.to_monomial(&mut coeffs)?;
+
+        Ok(Polynomial {
+            coeffs: coeffs.into_iter().rev().collect(),
+        })
+    }
+
+    /// Multiplies two polynomials given in the novel basis by pointwise products over a
+    /// large enough subspace.
+    fn mul_novel(&self, a: &[FE<F>], b: &[FE<F>]) -> Vec<FE<F>> {
+        let n = (a.len() + b.len() - 1).next_power_of_two();
+        let mut a = a.to_vec();
+        let mut b = b.to_vec();
+        a.resize(n, FE::zero());
+        b.resize(n, FE::zero());
+
+        self.fft_lanes(&mut a, 0);
+        self.fft_lanes(&mut b, 0);
+        for (x, y) in a.iter_mut().zip(b.iter()) {
+            *x *= *y;
+        }
+        self.ifft_lanes(&mut a, 0);
+
+        a
+    }
+
+    /// Returns the novel basis coefficients of `Π (x - ω_p)` over `points`, built with a
+    /// product tree in `O(n log^2 n)`.
+    fn vanishing(&self, points: &[usize]) -> Vec<FE<F>> {
+        // x + ω_p = ω_p X_0 + X_1, as X_1 = Ŵ_0(x) = x
+        let mut layer: Vec<Vec<FE<F>>> = points
+            .iter()
+            .map(|&p| vec![FE::from_u64(p as u64), FE::one()])
+            .collect();
+
+        if layer.is_empty() {
+            return vec![FE::one()];
+        }
+
+        while layer.len() > 1 {
+            layer = layer
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => {
+                        let mut product = self.mul_novel(a, b);
+                        product.truncate(a.len() + b.len() - 1);
+                        product
+                    }
+                    [a] => a.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+
+        layer.pop().unwrap()
+    }
+}
+
+/// Shard level erasure coder in the style of Leopard-RS, running in `O(n log n)` per
+/// symbol column with the additive FFT.
+///
+/// The `k` data shards (zero padded to a power of two `K`) are the evaluations of a
+/// polynomial of degree `< K` over the first subspace, and the `m` parity shards its
+/// evaluations over the following shifts of it.
+#[derive(Debug, Clone)]
+pub struct AdditiveEncoder<F: BinaryField> {
+    data_shards: usize,
+    parity_shards: usize,
+    /// Size of the data subspace
+    chunk: usize,
+    /// Size of the subspace covering every data and parity position
+    domain: usize,
+    basis: NovelBasis<F>,
+}
+
+impl<F: BinaryField> AdditiveEncoder<F> {
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self, Errors> {
+        if data_shards == 0 {
+            return Err(Errors::TooFewNumbers);
+        }
+
+        if parity_shards == 0 {
+            return Err(Errors::TooFewParity);
+        }
+
+        let chunk = data_shards.next_power_of_two();
+        let domain = (chunk + parity_shards).next_power_of_two();
+        if domain > 1 << F::DEGREE {
+            return Err(Errors::TooManyNumbers);
+        }
+
+        Ok(AdditiveEncoder {
+            data_shards,
+            parity_shards,
+            chunk,
+            domain,
+            basis: NovelBasis::new(),
+        })
+    }
+
+    pub fn data_shards(&self) -> usize {
+        self.data_shards
+    }
+
+    pub fn parity_shards(&self) -> usize {
+        self.parity_shards
+    }
+
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+
+    /// Position of shard `i` in the evaluation domain.
+    fn position(&self, i: usize) -> usize {
+        if i < self.data_shards {
+            i
+        } else {
+            self.chunk + i - self.data_shards
+        }
+    }
+
+    fn check_sizes<'a, I: Iterator<Item = &'a [u8]>>(mut shards: I) -> Result<usize, Errors> {
+        let len = match shards.next() {
+            Some(shard) => shard.len(),
+            None => return Err(Errors::InvalidShardCount),
+        };
+
+        if len == 0 || !len.is_multiple_of(F::SYMBOL_SIZE) || shards.any(|s| s.len() != len) {
+            return Err(Errors::InvalidShardSize);
+        }
+
+        Ok(len)
+    }
+
+    /// Computes the parity shards from the data shards.
+    fn parity(&self, data: &[&[u8]], len: usize) -> Vec<Vec<u8>> {
+        let mut coeffs: Vec<Vec<u8>> = data.iter().map(|shard| shard.to_vec()).collect();
+        coeffs.resize(self.chunk, vec![0; len]);
+        self.basis.ifft_lanes(&mut coeffs, 0);
+
+        let mut parity = Vec::with_capacity(self.parity_shards);
+        let mut shift = self.chunk;
+        while parity.len() < self.parity_shards {
+            let mut values = coeffs.clone();
+            self.basis.fft_lanes(&mut values, shift);
+
+            let take = (self.parity_shards - parity.len()).min(self.chunk);
+            parity.extend(values.into_iter().take(take));
+            shift += self.chunk;
+        }
+
+        parity
+    }
+
+    /// Fills the parity shards of `shards` from its first `k` data shards.
+    pub fn encode(&self, shards: &mut [Vec<u8>]) -> Result<(), Errors> {
+        if shards.len() != self.total_shards() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let (data, parity) = shards.split_at_mut(self.data_shards);
+        let data: Vec<&[u8]> = data.iter().map(|s| s.as_slice()).collect();
+        let len = Self::check_sizes(data.iter().cloned())?;
+
+        for (shard, computed) in parity.iter_mut().zip(self.parity(&data, len)) {
+            *shard = computed;
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the parity shards match the data shards.
+    pub fn verify(&self, shards: &[Vec<u8>]) -> Result<bool, Errors> {
+        if shards.len() != self.total_shards() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let len = Self::check_sizes(shards.iter().map(|s| s.as_slice()))?;
+        let data: Vec<&[u8]> = shards[..self.data_shards]
+            .iter()
+            .map(|s| s.as_slice())
+            .collect();
+
+        Ok(self.parity(&data, len).as_slice() == &shards[self.data_shards..])
+    }
+
+    /// Rebuilds every missing (`None`) shard from any `k` of the present ones.
+    ///
+    /// With `Λ` vanishing on the erased positions, the product `D·Λ` is known everywhere
+    /// and interpolated with one inverse FFT. Its derivative at an erased point `e` is
+    /// `D(ω_e) Λ'(ω_e)`, recovering every erasure with one more FFT.
+    pub fn reconstruct(&self, shards: &mut [Option<Vec<u8>>]) -> Result<(), Errors> {
+        if shards.len() != self.total_shards() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let present = shards.iter().filter(|s| s.is_some()).count();
+        if present < self.data_shards {
+            return Err(Errors::TooFewShards);
+        }
+
+        let len = Self::check_sizes(shards.iter().flatten().map(|s| s.as_slice()))?;
+
+        if present == shards.len() {
+            return Ok(());
+        }
+
+        // Missing shards and the unused tail of the domain are both erasures, while the
+        // zero padding of the data is known.
+        let mut erased: Vec<usize> = (0..shards.len())
+            .filter(|&i| shards[i].is_none())
+            .map(|i| self.position(i))
+            .collect();
+        erased.extend(self.chunk + self.parity_shards..self.domain);
+
+        let mut locator = self.basis.vanishing(&erased);
+        locator.resize(self.domain, FE::zero());
+
+        let mut locator_derivative = self.basis.derivative_lanes(&locator, FE::zero());
+        self.basis.fft_lanes(&mut locator, 0);
+        self.basis.fft_lanes(&mut locator_derivative, 0);
+
+        let mut values = vec![vec![0; len]; self.domain];
+        for (i, shard) in shards.iter().enumerate() {
+            if let Some(shard) = shard {
+                let position = self.position(i);
+                F::mul_slice(locator[position].value(), shard, &mut values[position]);
+            }
+        }
+
+        self.basis.ifft_lanes(&mut values, 0);
+        let mut values = self.basis.derivative_lanes(&values, vec![0; len]);
+        self.basis.fft_lanes(&mut values, 0);
+
+        for (i, shard) in shards.iter_mut().enumerate() {
+            if shard.is_none() {
+                let position = self.position(i);
+                let factor = FE::one() / locator_derivative[position];
+                let mut rebuilt = vec![0; len];
+                F::mul_slice(factor.value(), &values[position], &mut rebuilt);
+                *shard = Some(rebuilt);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{gf2_16::GF2_16, gf2_8::GF2k};
+
+    type Elem = FE<GF2k>;
+
+    #[test]
+    fn fft_matches_evaluation() {
+        let basis = NovelBasis::<GF2k>::new();
+        let poly = Polynomial {
+            coeffs: (1..=13).map(|x| Elem::new(x * 17)).collect(),
+        };
+
+        for shift in [0, 16, 48] {
+            let values = basis.evaluate(&poly, 4, shift).unwrap();
+            for (j, v) in values.iter().enumerate() {
+                assert_eq!(*v, poly.evaluate(&Elem::new((j ^ shift) as u8)));
+            }
+
+            let interpolated = basis.interpolate(&values, shift).unwrap();
+            assert_eq!(interpolated.coeffs[3..], poly.coeffs[..]);
+        }
+    }
+
+    #[test]
+    fn basis_roundtrip() {
+        let basis = NovelBasis::<GF2_16>::new();
+        let coeffs: Vec<FE<GF2_16>> = (0..64).map(|x| FE::from_u64(x * 1021 + 3)).collect();
+
+        let mut novel = coeffs.clone();
+        basis.to_novel(&mut novel).unwrap();
+        assert_ne!(novel, coeffs);
+        basis.to_monomial(&mut novel).unwrap();
+        assert_eq!(novel, coeffs);
+
+        assert_eq!(basis.fft(&mut novel[..3], 0), Err(Errors::InvalidSubspace));
+        assert_eq!(basis.fft(&mut novel[..16], 8), Err(Errors::InvalidSubspace));
+        assert_eq!(
+            basis.ifft(&mut novel[..16], 40),
+            Err(Errors::InvalidSubspace)
+        );
+        assert_eq!(basis.fft(&mut novel[..16], 48), Ok(()));
+    }
+
+    fn shards(data: usize, parity: usize, len: usize) -> Vec<Vec<u8>> {
+        let mut shards: Vec<Vec<u8>> = (0..data)
+            .map(|i| (0..len).map(|j| (i * 31 + j * 7 + i * j) as u8).collect())
+            .collect();
+        shards.resize(data + parity, vec![]);
+        shards
+    }
+
+    #[test]
+    fn encode_reconstruct() {
+        let encoder = AdditiveEncoder::<GF2k>::new(10, 6).unwrap();
+        let mut original = shards(10, 6, 32);
+        encoder.encode(&mut original).unwrap();
+        assert!(encoder.verify(&original).unwrap());
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.iter().cloned().map(Some).collect();
+        for i in [0, 4, 9, 10, 12, 15] {
+            damaged[i] = None;
+        }
+
+        encoder.reconstruct(&mut damaged).unwrap();
+        let rebuilt: Vec<Vec<u8>> = damaged.into_iter().map(Option::unwrap).collect();
+        assert_eq!(rebuilt, original);
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.into_iter().map(Some).collect();
+        for shard in damaged.iter_mut().take(7) {
+            *shard = None;
+        }
+        assert_eq!(encoder.reconstruct(&mut damaged), Err(Errors::TooFewShards));
+    }
+
+    #[test]
+    fn shards_use_slice_kernels() {
+        use crate::backend::slice::slice_bytes;
+
+        let encoder = AdditiveEncoder::<GF2k>::new(10, 6).unwrap();
+        let mut original = shards(10, 6, 64);
+
+        let before = slice_bytes();
+        encoder.encode(&mut original).unwrap();
+        let after = slice_bytes();
+        assert!(after > before);
+        assert!((after - before).is_multiple_of(64));
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.iter().cloned().map(Some).collect();
+        damaged[3] = None;
+        encoder.reconstruct(&mut damaged).unwrap();
+        assert!(slice_bytes() > after);
+        assert_eq!(damaged[3].as_ref(), Some(&original[3]));
+    }
+
+    #[test]
+    fn encode_reconstruct_gf2_16() {
+        let encoder = AdditiveEncoder::<GF2_16>::new(300, 200).unwrap();
+        let mut original = shards(300, 200, 8);
+        encoder.encode(&mut original).unwrap();
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.iter().cloned().map(Some).collect();
+        for i in (0..500).step_by(3).take(200) {
+            damaged[i] = None;
+        }
+
+        encoder.reconstruct(&mut damaged).unwrap();
+        let rebuilt: Vec<Vec<u8>> = damaged.into_iter().map(Option::unwrap).collect();
+        assert_eq!(rebuilt, original);
+        assert!(AdditiveEncoder::<GF2k>::new(200, 100).is_err());
+    }
+}
@@ -0,0 +1,503 @@
+//! GF(2^m) for `m <= 64` by carry-less multiplication, without log/exp tables.
+//!
+//! Products are computed with PCLMULQDQ where the CPU has it and reduced by the Barrett
+//! method, which needs two more carry-less products instead of a division. The AES field
+//! GF(2^8) modulo `0x11b` uses the GFNI `gf2p8mul` instruction instead, for single
+//! elements and 16 bytes at a time over slices. Every path has a bitwise fallback giving
+//! identical results.
+
+use super::mul_mod;
+use crate::ff::{BinaryField, Fp};
+
+/// Carry-less product of `a` and `b`, bit by bit.
+pub fn clmul_bitwise(a: u64, b: u64) -> u128 {
+    let mut result = 0;
+    for i in 0..64 {
+        if (b >> i) & 1 == 1 {
+            result ^= (a as u128) << i;
+        }
+    }
+
+    result
+}
+
+/// Carry-less product of `a` and `b`, with PCLMULQDQ where available.
+#[inline]
+pub fn clmul(a: u64, b: u64) -> u128 {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("pclmulqdq") {
+        // SAFETY: the feature was detected at runtime
+        return unsafe { x86::clmul(a, b) };
+    }
+
+    clmul_bitwise(a, b)
+}
+
+/// Low `m` bits of `x^(2m) / (x^m + poly)`, the Barrett constant of the modulus.
+const fn barrett_mu(m: usize, poly: u64) -> u64 {
+    // x^(2m) = x^m (x^m + poly) + x^m poly, so only `x^m poly` is left to divide, which
+    // unlike x^128 fits in a u128
+    let modulus = (1u128 << m) | poly as u128;
+    let mut rem = (poly as u128) << m;
+    let mut quotient = 0u64;
+
+    let mut i = m;
+    while i > 0 {
+        i -= 1;
+        if (rem >> (m + i)) & 1 == 1 {
+            rem ^= modulus << i;
+            quotient |= 1 << i;
+        }
+    }
+
+    quotient
+}
+
+/// Greatest common divisor of two polynomials over GF(2).
+const fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let degree = b.ilog2();
+        while a != 0 && a.ilog2() >= degree {
+            a ^= b << (a.ilog2() - degree);
+        }
+        (a, b) = (b, a);
+    }
+
+    a
+}
+
+/// Whether `x^m + poly` is irreducible, by Rabin's test: it divides `x^(2^m) - x`, and
+/// is coprime with `x^(2^(m/p)) - x` for every prime `p` dividing `m`.
+pub const fn is_irreducible(m: usize, poly: u64) -> bool {
+    // x^(2^i) mod f for i in 0..=m
+    let mut powers = [0u64; 65];
+    powers[0] = 2;
+    let mut i = 0;
+    while i < m {
+        powers[i + 1] = mul_mod(m, poly, powers[i], powers[i]);
+        i += 1;
+    }
+    if powers[m] != 2 {
+        return false;
+    }
+
+    let modulus = (1u128 << m) | poly as u128;
+    let mut p = 2;
+    while p <= m {
+        let mut prime = true;
+        let mut d = 2;
+        while d < p {
+            prime &= !p.is_multiple_of(d);
+            d += 1;
+        }
+
+        if prime && m.is_multiple_of(p) && gcd(modulus, (powers[m / p] ^ 2) as u128) != 1 {
+            return false;
+        }
+        p += 1;
+    }
+
+    true
+}
+
+/// GF(2^DEGREE) with prime polynomial `x^DEGREE + POLY`, elements stored in the low bits
+/// of a `u64`.
+///
+/// `DEGREE` must be a multiple of 8 from 8 to 64 and the polynomial irreducible, which
+/// is checked at compile time.
+#[derive(Debug, Clone, Copy)]
+pub struct ClmulField<const DEGREE: usize, const POLY: u64>;
+
+/// GF(2^8) of AES, the same field as [`AesField`](super::gf256::AesField)
+pub type AesClmulField = ClmulField<8, 0x1b>;
+
+/// GF(2^16) of [`GF2_16`](super::gf2_16::GF2_16), without its 384 KiB of tables
+pub type GF2_16Clmul = ClmulField<16, 0x100b>;
+
+/// GF(2^32) with prime polynomial `x^32 + x^7 + x^3 + x^2 + 1`
+pub type GF2_32 = ClmulField<32, 0x8d>;
+
+/// GF(2^64) with prime polynomial `x^64 + x^4 + x^3 + x + 1`
+pub type GF2_64 = ClmulField<64, 0x1b>;
+
+impl<const DEGREE: usize, const POLY: u64> ClmulField<DEGREE, POLY> {
+    /// `DEGREE`, failing compilation for parameters that do not give a field of whole
+    /// bytes
+    const CHECKED_DEGREE: usize = {
+        assert!(
+            DEGREE.is_multiple_of(8) && 8 <= DEGREE && DEGREE <= 64,
+            "degree must be a multiple of 8 from 8 to 64"
+        );
+        assert!(
+            DEGREE == 64 || POLY >> DEGREE == 0,
+            "polynomial must have a lower degree than the field"
+        );
+        assert!(
+            is_irreducible(DEGREE, POLY),
+            "polynomial must be irreducible"
+        );
+
+        DEGREE
+    };
+
+    const MASK: u64 = if Self::CHECKED_DEGREE == 64 {
+        u64::MAX
+    } else {
+        (1 << DEGREE) - 1
+    };
+
+    const MU: u64 = barrett_mu(Self::CHECKED_DEGREE, POLY);
+
+    /// Reduces a product of two elements with the carry-less multiply `clmul`.
+    #[inline]
+    fn reduce(c: u128, clmul: fn(u64, u64) -> u128) -> u64 {
+        let high = (c >> DEGREE) as u64;
+        let low = c as u64 & Self::MASK;
+
+        // floor(c / (x^m + poly)), exact as `high` has fewer than `m` bits
+        let quotient = high ^ (clmul(high, Self::MU) >> DEGREE) as u64;
+        (low ^ clmul(quotient, POLY) as u64) & Self::MASK
+    }
+
+    /// Multiplies without any of the hardware instructions, for checking them.
+    pub fn mul_bitwise(a: u64, b: u64) -> u64 {
+        Self::reduce(clmul_bitwise(a, b), clmul_bitwise)
+    }
+
+    /// Whether this is the field of `gf2p8mul` and the CPU supports it
+    #[cfg(target_arch = "x86_64")]
+    #[inline]
+    fn has_gfni() -> bool {
+        DEGREE == 8 && POLY == 0x1b && is_x86_feature_detected!("gfni")
+    }
+
+    fn pow(mut a: u64, mut power: u64) -> u64 {
+        let mut result = 1;
+        while power > 0 {
+            if power & 1 == 1 {
+                result = Self::mul(result, a);
+            }
+            a = Self::mul(a, a);
+            power >>= 1;
+        }
+
+        result
+    }
+
+    fn slice(c: u64, src: &[u8], dst: &mut [u8], add: bool) {
+        assert_eq!(src.len(), dst.len(), "slices differ in length");
+
+        #[cfg(target_arch = "x86_64")]
+        let done = if Self::has_gfni() {
+            // SAFETY: the feature was detected at runtime and the slices have equal length
+            unsafe { x86::gf2p8mul_slice(c as u8, src, dst, add) }
+        } else {
+            0
+        };
+        #[cfg(not(target_arch = "x86_64"))]
+        let done = 0;
+
+        for (s, d) in src[done..]
+            .chunks(Self::SYMBOL_SIZE)
+            .zip(dst[done..].chunks_mut(Self::SYMBOL_SIZE))
+        {
+            let mut product = Self::mul(c, Self::from_bytes(s));
+            if add {
+                product ^= Self::from_bytes(d);
+            }
+            Self::to_bytes(product, d);
+        }
+    }
+}
+
+impl<const DEGREE: usize, const POLY: u64> Fp for ClmulField<DEGREE, POLY> {
+    const ORDER: usize = if DEGREE < usize::BITS as usize {
+        1 << DEGREE
+    } else {
+        usize::MAX
+    };
+
+    const PRIME_POLY: usize = if DEGREE < usize::BITS as usize {
+        (1 << DEGREE) | POLY as usize
+    } else {
+        usize::MAX
+    };
+
+    const SYMBOL_SIZE: usize = Self::CHECKED_DEGREE / 8;
+
+    type Elem = u64;
+
+    #[inline]
+    fn zero() -> Self::Elem {
+        0
+    }
+
+    #[inline]
+    fn one() -> Self::Elem {
+        1
+    }
+
+    #[inline]
+    fn add(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        a ^ b
+    }
+
+    #[inline]
+    fn sub(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        a ^ b
+    }
+
+    #[inline]
+    fn mul(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        #[cfg(target_arch = "x86_64")]
+        if Self::has_gfni() {
+            // SAFETY: the feature was detected at runtime
+            return unsafe { x86::gf2p8mul(a as u8, b as u8) } as u64;
+        }
+
+        Self::reduce(clmul(a, b), clmul)
+    }
+
+    #[inline]
+    fn exp(a: Self::Elem, power: usize) -> Self::Elem {
+        Self::pow(a, power as u64)
+    }
+
+    #[inline]
+    fn inverse(a: Self::Elem) -> Option<Self::Elem> {
+        if a == 0 {
+            return None;
+        }
+
+        // a^(2^m - 2)
+        Some(Self::pow(a, Self::MASK - 1))
+    }
+
+    #[inline]
+    fn div(a: Self::Elem, b: Self::Elem) -> Option<Self::Elem> {
+        Self::inverse(b).map(|inv| Self::mul(a, inv))
+    }
+
+    #[inline]
+    fn neg(a: Self::Elem) -> Self::Elem {
+        a
+    }
+
+    #[inline]
+    fn eq(a: &Self::Elem, b: &Self::Elem) -> bool {
+        a == b
+    }
+
+    fn from_u8(a: u8) -> Self::Elem {
+        a as u64 & Self::MASK
+    }
+
+    fn to_u8(a: Self::Elem) -> u8 {
+        a as u8
+    }
+
+    fn from_u64(a: u64) -> Self::Elem {
+        a & Self::MASK
+    }
+
+    fn to_u64(a: Self::Elem) -> u64 {
+        a
+    }
+
+    fn mul_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        Self::slice(c, src, dst, false)
+    }
+
+    fn mul_add_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        Self::slice(c, src, dst, true)
+    }
+}
+
+impl<const DEGREE: usize, const POLY: u64> BinaryField for ClmulField<DEGREE, POLY> {
+    const DEGREE: usize = DEGREE;
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "pclmulqdq")]
+    pub(super) fn clmul(a: u64, b: u64) -> u128 {
+        let product =
+            _mm_clmulepi64_si128::<0x00>(_mm_set_epi64x(0, a as i64), _mm_set_epi64x(0, b as i64));
+
+        let mut bytes = [0u8; 16];
+        // SAFETY: `bytes` holds the 16 bytes stored
+        unsafe { _mm_storeu_si128(bytes.as_mut_ptr().cast(), product) };
+        u128::from_le_bytes(bytes)
+    }
+
+    #[target_feature(enable = "gfni")]
+    pub(super) fn gf2p8mul(a: u8, b: u8) -> u8 {
+        let product = _mm_gf2p8mul_epi8(_mm_cvtsi32_si128(a as i32), _mm_cvtsi32_si128(b as i32));
+        _mm_cvtsi128_si32(product) as u8
+    }
+
+    /// Multiplies the whole 16 byte vectors of `src` by `c` into `dst`, returning how
+    /// many bytes that was.
+    #[target_feature(enable = "gfni")]
+    pub(super) unsafe fn gf2p8mul_slice(c: u8, src: &[u8], dst: &mut [u8], add: bool) -> usize {
+        let coeff = _mm_set1_epi8(c as i8);
+
+        let len = src.len() / 16 * 16;
+        for i in (0..len).step_by(16) {
+            let s = _mm_loadu_si128(src.as_ptr().add(i).cast());
+
+            let d = dst.as_mut_ptr().add(i).cast();
+            let mut product = _mm_gf2p8mul_epi8(s, coeff);
+            if add {
+                product = _mm_xor_si128(product, _mm_loadu_si128(d));
+            }
+            _mm_storeu_si128(d, product);
+        }
+
+        len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::check_mul;
+    use crate::backend::gf256::AesField;
+    use crate::backend::gf2_16::GF2_16;
+    use crate::erasure::ShardEncoder;
+
+    /// Deterministic pseudo-random elements
+    fn samples(m: usize) -> Vec<u64> {
+        let mut x: u64 = 0x9e37_79b9_7f4a_7c15;
+        (0..200)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                if m == 64 {
+                    x
+                } else {
+                    x & ((1 << m) - 1)
+                }
+            })
+            .chain([0, 1, u64::MAX >> (64 - m)])
+            .collect()
+    }
+
+    fn check_field<const DEGREE: usize, const POLY: u64>() {
+        let xs = samples(DEGREE);
+        let ys: Vec<u64> = xs.iter().step_by(7).copied().collect();
+        check_mul(
+            DEGREE,
+            POLY,
+            &xs,
+            &ys,
+            ClmulField::<DEGREE, POLY>::mul_bitwise,
+        );
+        check_mul(DEGREE, POLY, &xs, &ys, ClmulField::<DEGREE, POLY>::mul);
+
+        for &a in &xs {
+            if a != 0 {
+                let inv = ClmulField::<DEGREE, POLY>::inverse(a).unwrap();
+                assert_eq!(ClmulField::<DEGREE, POLY>::mul(a, inv), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn clmul_matches_bitwise() {
+        for &a in &samples(64) {
+            for &b in samples(64).iter().step_by(11) {
+                assert_eq!(clmul(a, b), clmul_bitwise(a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn fields() {
+        check_field::<8, 0x1b>();
+        check_field::<16, 0x100b>();
+        check_field::<32, 0x8d>();
+        check_field::<64, 0x1b>();
+
+        assert_eq!(GF2_16Clmul::PRIME_POLY, GF2_16::PRIME_POLY);
+        assert_eq!(GF2_32::ORDER, 1 << 32);
+        assert_eq!(GF2_64::SYMBOL_SIZE, 8);
+    }
+
+    #[test]
+    fn irreducible() {
+        // there are 30 irreducible polynomials of degree 8 over GF(2)
+        assert_eq!((0..256).filter(|&poly| is_irreducible(8, poly)).count(), 30);
+
+        assert!(is_irreducible(16, 0x100b));
+        assert!(is_irreducible(32, 0x8d));
+        assert!(is_irreducible(64, 0x1b));
+        // x^16 + 1 = (x + 1)^16, and x^32 + x^8 + 1 = (x^4 + x + 1)^8
+        assert!(!is_irreducible(16, 1));
+        assert!(!is_irreducible(32, 0x101));
+    }
+
+    #[test]
+    fn matches_tables() {
+        for a in 0..=255 {
+            for b in 0..=255 {
+                assert_eq!(
+                    AesClmulField::mul(a, b),
+                    AesField::mul(a as u8, b as u8) as u64
+                );
+            }
+        }
+
+        for &a in &samples(16) {
+            for &b in samples(16).iter().step_by(3) {
+                assert_eq!(
+                    GF2_16Clmul::mul(a, b),
+                    GF2_16::mul(a as u16, b as u16) as u64
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn slices() {
+        let src: Vec<u8> = (0..203).map(|i| (i * 37 + 11) as u8).collect();
+        let acc: Vec<u8> = (0..203).map(|i| (i * 91 + 5) as u8).collect();
+
+        for c in [0, 1, 0x53, 0xff] {
+            let product: Vec<u8> = src
+                .iter()
+                .map(|&s| AesClmulField::mul_bitwise(c, s as u64) as u8)
+                .collect();
+
+            let mut dst = acc.clone();
+            AesClmulField::mul_slice(c, &src, &mut dst);
+            assert_eq!(dst, product);
+
+            let mut dst = acc.clone();
+            AesClmulField::mul_add_slice(c, &src, &mut dst);
+            let sum: Vec<u8> = product.iter().zip(&acc).map(|(p, a)| p ^ a).collect();
+            assert_eq!(dst, sum);
+        }
+    }
+
+    #[test]
+    fn erasure_roundtrip() {
+        let encoder = ShardEncoder::<GF2_64>::new_cauchy(5, 3).unwrap();
+        let mut shards: Vec<Vec<u8>> = (0..8)
+            .map(|i| (0..64).map(|j| (i * 13 + j * 7) as u8).collect())
+            .collect();
+        encoder.encode(&mut shards).unwrap();
+
+        let mut damaged: Vec<Option<Vec<u8>>> = shards.iter().cloned().map(Some).collect();
+        for i in [0, 3, 6] {
+            damaged[i] = None;
+        }
+        encoder.reconstruct(&mut damaged).unwrap();
+
+        let rebuilt: Vec<Vec<u8>> = damaged.into_iter().map(Option::unwrap).collect();
+        assert_eq!(rebuilt, shards);
+    }
+}
@@ -0,0 +1,504 @@
+//! GF(2^8) defined by any primitive polynomial and generator.
+//!
+//! [`GF256Config`] builds the log/exp tables with a `const fn`, so it can be used as a
+//! runtime value. It plugs into [`FieldElement`](crate::ff::FieldElement) and
+//! [`ReedSolomon`](crate::reedsolomon::ReedSolomon) without a hand written type either
+//! through [`GF256`], which takes the polynomial and generator as const generics, or
+//! through [`DynGF256`], which takes a configuration chosen at runtime.
+
+use std::cell::Cell;
+use std::sync::Mutex;
+
+use super::mul_mod;
+use super::slice::NibbleTables;
+use crate::errors::Errors;
+use crate::ff::{BinaryField, Fp};
+
+/// Log/exp tables of GF(2^8) for a given prime polynomial and generator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GF256Config {
+    poly: usize,
+    generator: u8,
+    log: [u8; 256],
+    exp: [u8; 510],
+}
+
+impl GF256Config {
+    /// Builds the tables from the powers of `generator`, failing unless they run through
+    /// every non-zero element.
+    pub const fn new(poly: usize, generator: u8) -> Result<Self, Errors> {
+        if poly >> 8 != 1 {
+            return Err(Errors::NotPrimitive);
+        }
+
+        let mut log = [0u8; 256];
+        let mut exp = [0u8; 510];
+        let mut seen = [false; 256];
+
+        let mut x: usize = 1;
+        let mut i = 0;
+        while i < 255 {
+            if seen[x] {
+                return Err(Errors::NotPrimitive);
+            }
+            seen[x] = true;
+
+            exp[i] = x as u8;
+            exp[i + 255] = x as u8;
+            log[x] = i as u8;
+
+            x = mul_mod(8, (poly & 0xff) as u64, x as u64, generator as u64) as usize;
+            i += 1;
+        }
+
+        Ok(GF256Config {
+            poly,
+            generator,
+            log,
+            exp,
+        })
+    }
+
+    /// Same as [`GF256Config::new`], panicking (at compile time in const contexts) on
+    /// non-primitive parameters.
+    pub const fn new_or_panic(poly: usize, generator: u8) -> Self {
+        match Self::new(poly, generator) {
+            Ok(config) => config,
+            Err(_) => panic!("polynomial and generator do not generate GF(2^8)"),
+        }
+    }
+
+    pub fn poly(&self) -> usize {
+        self.poly
+    }
+
+    pub fn generator(&self) -> u8 {
+        self.generator
+    }
+
+    #[inline]
+    pub fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+
+        self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+    }
+
+    #[inline]
+    pub fn exp(&self, a: u8, power: usize) -> u8 {
+        if power == 0 {
+            return 1;
+        }
+        if a == 0 {
+            return 0;
+        }
+
+        self.exp[(self.log[a as usize] as usize * (power % 255)) % 255]
+    }
+
+    #[inline]
+    pub fn inverse(&self, a: u8) -> Option<u8> {
+        if a == 0 {
+            return None;
+        }
+
+        Some(self.exp[255 - self.log[a as usize] as usize])
+    }
+
+    #[inline]
+    pub fn div(&self, a: u8, b: u8) -> Option<u8> {
+        Some(self.mul(a, self.inverse(b)?))
+    }
+
+    /// Configuration of the field modulo `poly` with its smallest generator, built once
+    /// per polynomial and shared by all callers.
+    pub fn shared(poly: usize) -> Result<&'static Self, Errors> {
+        static SHARED: Mutex<Vec<&'static GF256Config>> = Mutex::new(Vec::new());
+
+        let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(config) = shared.iter().find(|config| config.poly == poly) {
+            return Ok(config);
+        }
+
+        let config = (2..=255)
+            .find_map(|generator| Self::new(poly, generator).ok())
+            .ok_or(Errors::NotPrimitive)?;
+        let config = Box::leak(Box::new(config));
+        shared.push(config);
+
+        Ok(config)
+    }
+
+    /// Discrete logarithm of `a` to the base of the generator
+    #[inline]
+    pub fn log(&self, a: u8) -> Option<u8> {
+        if a == 0 {
+            return None;
+        }
+
+        Some(self.log[a as usize])
+    }
+}
+
+/// GF(2^8) with prime polynomial `POLY` and tables generated by `GENERATOR`, both checked
+/// at compile time.
+#[derive(Debug, Clone, Copy)]
+pub struct GF256<const POLY: usize, const GENERATOR: u8>;
+
+/// QR codes and most storage systems, `x^8 + x^4 + x^3 + x^2 + 1`
+pub type QrField = GF256<0x11d, 2>;
+
+/// Data Matrix ECC 200, `x^8 + x^5 + x^3 + x^2 + 1`
+pub type DataMatrixField = GF256<0x12d, 2>;
+
+/// AES, `x^8 + x^4 + x^3 + x + 1` generated by `x + 1`
+pub type AesField = GF256<0x11b, 3>;
+
+/// CCSDS conventional representation, `x^8 + x^7 + x^2 + x + 1`
+pub type CcsdsField = GF256<0x187, 2>;
+
+impl<const POLY: usize, const GENERATOR: u8> GF256<POLY, GENERATOR> {
+    pub const CONFIG: &'static GF256Config = &GF256Config::new_or_panic(POLY, GENERATOR);
+}
+
+impl<const POLY: usize, const GENERATOR: u8> Fp for GF256<POLY, GENERATOR> {
+    const ORDER: usize = 256;
+
+    const PRIME_POLY: usize = POLY;
+
+    const SYMBOL_SIZE: usize = 1;
+
+    type Elem = u8;
+
+    #[inline]
+    fn zero() -> Self::Elem {
+        0
+    }
+
+    #[inline]
+    fn one() -> Self::Elem {
+        1
+    }
+
+    #[inline]
+    fn add(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        a ^ b
+    }
+
+    #[inline]
+    fn sub(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        a ^ b
+    }
+
+    #[inline]
+    fn mul(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        Self::CONFIG.mul(a, b)
+    }
+
+    #[inline]
+    fn exp(a: Self::Elem, power: usize) -> Self::Elem {
+        Self::CONFIG.exp(a, power)
+    }
+
+    #[inline]
+    fn inverse(a: Self::Elem) -> Option<Self::Elem> {
+        Self::CONFIG.inverse(a)
+    }
+
+    #[inline]
+    fn div(a: Self::Elem, b: Self::Elem) -> Option<Self::Elem> {
+        Self::CONFIG.div(a, b)
+    }
+
+    #[inline]
+    fn neg(a: Self::Elem) -> Self::Elem {
+        a
+    }
+
+    #[inline]
+    fn eq(a: &Self::Elem, b: &Self::Elem) -> bool {
+        a == b
+    }
+
+    fn from_u8(a: u8) -> Self::Elem {
+        a
+    }
+
+    fn to_u8(a: Self::Elem) -> u8 {
+        a
+    }
+
+    fn from_u64(a: u64) -> Self::Elem {
+        a as u8
+    }
+
+    fn to_u64(a: Self::Elem) -> u64 {
+        a as u64
+    }
+
+    fn mul_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        NibbleTables::new::<Self>(c).mul_slice(src, dst)
+    }
+
+    fn mul_add_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        NibbleTables::new::<Self>(c).mul_add_slice(src, dst)
+    }
+}
+
+impl<const POLY: usize, const GENERATOR: u8> BinaryField for GF256<POLY, GENERATOR> {
+    const DEGREE: usize = 8;
+}
+
+thread_local! {
+    static SCOPE: Cell<Option<&'static GF256Config>> = const { Cell::new(None) };
+}
+
+/// GF(2^8) whose operations use the [`GF256Config`] of the innermost
+/// [`DynGF256::scope`] on the current thread.
+///
+/// Codecs keep field elements around, so a codec over this field must only be used in
+/// scopes of the configuration it was built in.
+#[derive(Debug, Clone, Copy)]
+pub struct DynGF256;
+
+impl DynGF256 {
+    /// Runs `f` with this field defined by `config`.
+    pub fn scope<R>(config: &'static GF256Config, f: impl FnOnce() -> R) -> R {
+        struct Restore(Option<&'static GF256Config>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SCOPE.with(|scope| scope.set(self.0));
+            }
+        }
+
+        let _restore = Restore(SCOPE.with(|scope| scope.replace(Some(config))));
+        f()
+    }
+
+    /// Configuration of the innermost scope
+    ///
+    /// # Panics
+    ///
+    /// Outside of [`DynGF256::scope`].
+    #[inline]
+    pub fn config() -> &'static GF256Config {
+        SCOPE
+            .with(|scope| scope.get())
+            .expect("DynGF256 used outside of DynGF256::scope")
+    }
+}
+
+impl Fp for DynGF256 {
+    const ORDER: usize = 256;
+
+    /// Unknown until runtime, see [`Fp::prime_poly`]
+    const PRIME_POLY: usize = 0;
+
+    const SYMBOL_SIZE: usize = 1;
+
+    type Elem = u8;
+
+    #[inline]
+    fn zero() -> Self::Elem {
+        0
+    }
+
+    #[inline]
+    fn one() -> Self::Elem {
+        1
+    }
+
+    #[inline]
+    fn add(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        a ^ b
+    }
+
+    #[inline]
+    fn sub(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        a ^ b
+    }
+
+    #[inline]
+    fn mul(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        Self::config().mul(a, b)
+    }
+
+    #[inline]
+    fn exp(a: Self::Elem, power: usize) -> Self::Elem {
+        Self::config().exp(a, power)
+    }
+
+    #[inline]
+    fn inverse(a: Self::Elem) -> Option<Self::Elem> {
+        Self::config().inverse(a)
+    }
+
+    #[inline]
+    fn div(a: Self::Elem, b: Self::Elem) -> Option<Self::Elem> {
+        Self::config().div(a, b)
+    }
+
+    #[inline]
+    fn neg(a: Self::Elem) -> Self::Elem {
+        a
+    }
+
+    #[inline]
+    fn eq(a: &Self::Elem, b: &Self::Elem) -> bool {
+        a == b
+    }
+
+    fn from_u8(a: u8) -> Self::Elem {
+        a
+    }
+
+    fn to_u8(a: Self::Elem) -> u8 {
+        a
+    }
+
+    fn from_u64(a: u64) -> Self::Elem {
+        a as u8
+    }
+
+    fn to_u64(a: Self::Elem) -> u64 {
+        a as u64
+    }
+
+    fn prime_poly() -> usize {
+        Self::config().poly()
+    }
+
+    fn mul_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        NibbleTables::new::<Self>(c).mul_slice(src, dst)
+    }
+
+    fn mul_add_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        NibbleTables::new::<Self>(c).mul_add_slice(src, dst)
+    }
+}
+
+impl BinaryField for DynGF256 {
+    const DEGREE: usize = 8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::check_mul;
+    use crate::backend::gf2_8::GF2k;
+    use crate::ff::FieldElement;
+    use crate::reedsolomon::ReedSolomon;
+
+    #[test]
+    fn test_config() {
+        let aes = GF256Config::new(0x11b, 3).unwrap();
+
+        // FIPS-197 section 4.2
+        assert_eq!(aes.mul(0x57, 0x83), 0xc1);
+        assert_eq!(aes.mul(0x57, 0x13), 0xfe);
+        assert_eq!(aes.inverse(0x53), Some(0xca));
+        assert_eq!(aes.log(3), Some(1));
+        assert_eq!(aes.log(0), None);
+        assert_eq!(aes.exp(3, 255), 1);
+    }
+
+    #[test]
+    fn test_not_primitive() {
+        // 2 only has order 51 in the AES field
+        assert_eq!(GF256Config::new(0x11b, 2), Err(Errors::NotPrimitive));
+        assert_eq!(GF256Config::new(0x11d, 0), Err(Errors::NotPrimitive));
+        assert_eq!(GF256Config::new(0x1d, 2), Err(Errors::NotPrimitive));
+        assert!(GF256Config::new(0x187, 2).is_ok());
+    }
+
+    #[test]
+    fn test_matches_bitwise() {
+        let xs: Vec<u64> = (0..256).collect();
+        check_mul(8, 0x1b, &xs, &xs, |a, b| {
+            AesField::mul(a as u8, b as u8) as u64
+        });
+        check_mul(8, 0x87, &xs, &xs, |a, b| {
+            CcsdsField::mul(a as u8, b as u8) as u64
+        });
+    }
+
+    #[test]
+    fn test_matches_gf2k() {
+        for a in 0..=255u8 {
+            for b in (0..=255u8).step_by(7) {
+                assert_eq!(QrField::mul(a, b), GF2k::mul(a, b));
+                assert_eq!(QrField::div(a, b), GF2k::div(a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_reedsolomon() {
+        let message = b"configurable fields";
+        let qr = ReedSolomon::<QrField>::new(29, 19, FieldElement::new(2)).unwrap();
+        let gf2k = ReedSolomon::<GF2k>::new(29, 19, FieldElement::new(2)).unwrap();
+        assert_eq!(qr.encode(message), gf2k.encode(message));
+
+        let aes = ReedSolomon::<AesField>::new(29, 19, FieldElement::new(3)).unwrap();
+        let mut codeword = aes.encode(message).unwrap();
+        assert_ne!(Ok(codeword.clone()), gf2k.encode(message));
+
+        codeword[2] ^= 0x40;
+        codeword[25] = 0;
+        let decoded = aes.decode(&codeword).unwrap();
+        assert_eq!(decoded.message, message.to_vec());
+        assert_eq!(decoded.errors, vec![2, 25]);
+    }
+
+    #[test]
+    fn test_shared() {
+        let aes = GF256Config::shared(0x11b).unwrap();
+        assert_eq!(aes.generator(), 3);
+        assert!(std::ptr::eq(aes, GF256Config::shared(0x11b).unwrap()));
+        assert_eq!(GF256Config::shared(0x11d).unwrap().generator(), 2);
+        // x^8 + 1 = (x + 1)^8
+        assert_eq!(GF256Config::shared(0x101), Err(Errors::NotPrimitive));
+    }
+
+    #[test]
+    fn test_dyn_reedsolomon() {
+        let message = b"runtime fields";
+        let aes = ReedSolomon::<AesField>::new(24, 14, FieldElement::new(3)).unwrap();
+        let expected = aes.encode(message).unwrap();
+
+        DynGF256::scope(AesField::CONFIG, || {
+            let rs = ReedSolomon::<DynGF256>::builder(24, 14, FieldElement::new(3))
+                .field_polynomial(0x11b)
+                .build()
+                .unwrap();
+            let mut codeword = rs.encode(message).unwrap();
+            assert_eq!(codeword, expected);
+
+            codeword[5] ^= 0x11;
+            codeword[20] = 0;
+            let decoded = rs.decode(&codeword).unwrap();
+            assert_eq!(decoded.message, message.to_vec());
+            assert_eq!(decoded.errors, vec![5, 20]);
+
+            // inner scopes shadow the outer one until they return
+            DynGF256::scope(QrField::CONFIG, || {
+                assert_eq!(DynGF256::prime_poly(), 0x11d);
+                assert!(
+                    ReedSolomon::<DynGF256>::builder(24, 14, FieldElement::new(2))
+                        .field_polynomial(0x11b)
+                        .build()
+                        .is_err()
+                );
+            });
+            assert_eq!(DynGF256::prime_poly(), 0x11b);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of DynGF256::scope")]
+    fn test_dyn_unscoped() {
+        DynGF256::mul(2, 3);
+    }
+}
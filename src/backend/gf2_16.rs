@@ -0,0 +1,185 @@
+include!(concat!(env!("OUT_DIR"), "/table16.rs"));
+
+use super::params::{GF2_16_GENERATOR, GF2_16_PRIME_POLY};
+use crate::ff::{BinaryField, Fp};
+
+/// GF(2^16) with prime polynomial `x^16 + x^12 + x^3 + x + 1`, allowing codewords of up
+/// to 65535 symbols.
+#[derive(Debug, Clone, Copy)]
+pub struct GF2_16;
+
+impl GF2_16 {
+    /// Primitive element the log/exp tables are built from
+    pub const GENERATOR: u16 = GF2_16_GENERATOR as u16;
+}
+
+impl Fp for GF2_16 {
+    const ORDER: usize = 65536;
+
+    const PRIME_POLY: usize = GF2_16_PRIME_POLY;
+
+    const SYMBOL_SIZE: usize = 2;
+
+    type Elem = u16;
+
+    #[inline]
+    fn zero() -> Self::Elem {
+        0
+    }
+
+    #[inline]
+    fn one() -> Self::Elem {
+        1
+    }
+
+    #[inline]
+    fn add(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        a ^ b
+    }
+
+    #[inline]
+    fn sub(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        a ^ b
+    }
+
+    #[inline]
+    fn mul(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+
+        EXP_TABLE_16[LOG_TABLE_16[a as usize] as usize + LOG_TABLE_16[b as usize] as usize]
+    }
+
+    #[inline]
+    fn exp(a: Self::Elem, power: usize) -> Self::Elem {
+        if power == 0 {
+            return 1;
+        }
+        if a == 0 {
+            return 0;
+        }
+
+        let res_exp =
+            (LOG_TABLE_16[a as usize] as usize * (power % (Self::ORDER - 1))) % (Self::ORDER - 1);
+
+        EXP_TABLE_16[res_exp]
+    }
+
+    #[inline]
+    fn inverse(a: Self::Elem) -> Option<Self::Elem> {
+        if a == 0 {
+            return None;
+        }
+
+        Some(EXP_TABLE_16[Self::ORDER - 1 - LOG_TABLE_16[a as usize] as usize])
+    }
+
+    #[inline]
+    fn div(a: Self::Elem, b: Self::Elem) -> Option<Self::Elem> {
+        if b == 0 {
+            return None;
+        }
+
+        if a == 0 {
+            return Some(0);
+        }
+
+        Some(Self::mul(a, Self::inverse(b).unwrap()))
+    }
+
+    #[inline]
+    fn neg(a: Self::Elem) -> Self::Elem {
+        a
+    }
+
+    #[inline]
+    fn eq(a: &Self::Elem, b: &Self::Elem) -> bool {
+        a == b
+    }
+
+    fn from_u8(a: u8) -> Self::Elem {
+        a as u16
+    }
+
+    fn to_u8(a: Self::Elem) -> u8 {
+        a as u8
+    }
+
+    fn from_u64(a: u64) -> Self::Elem {
+        a as u16
+    }
+
+    fn to_u64(a: Self::Elem) -> u64 {
+        a as u64
+    }
+}
+
+impl BinaryField for GF2_16 {
+    const DEGREE: usize = 16;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::check_mul;
+
+    #[test]
+    fn test_add() {
+        assert_eq!(GF2_16::add(0, 0), 0);
+        assert_eq!(GF2_16::add(0x1234, 0x00ff), 0x12cb);
+        assert_eq!(GF2_16::add(0xffff, 0xffff), 0);
+    }
+
+    #[test]
+    fn test_mul() {
+        assert_eq!(GF2_16::mul(0, 0x1234), 0);
+        assert_eq!(GF2_16::mul(1, 0x1234), 0x1234);
+        assert_eq!(GF2_16::mul(2, 0x8000), 0x100b);
+        assert_eq!(GF2_16::mul(0x100, 0x100), 0x100b);
+    }
+
+    #[test]
+    fn test_exp() {
+        assert_eq!(GF2_16::exp(0, 0), 1);
+        assert_eq!(GF2_16::exp(0, 1), 0);
+        assert_eq!(GF2_16::exp(2, 16), 0x100b);
+        assert_eq!(GF2_16::exp(2, 65535), 1);
+    }
+
+    #[test]
+    fn test_inverse() {
+        assert_eq!(GF2_16::inverse(0), None);
+        assert_eq!(GF2_16::inverse(1), Some(1));
+
+        for a in (1..=u16::MAX).step_by(97) {
+            assert_eq!(GF2_16::mul(a, GF2_16::inverse(a).unwrap()), 1);
+        }
+    }
+
+    #[test]
+    fn test_div() {
+        assert_eq!(GF2_16::div(0, 0), None);
+        assert_eq!(GF2_16::div(0, 1), Some(0));
+        assert_eq!(GF2_16::div(0x1234, 0x1234), Some(1));
+    }
+
+    #[test]
+    fn test_tables_match_prime_poly() {
+        let xs: Vec<u64> = (0..GF2_16::ORDER as u64).step_by(251).collect();
+        let ys: Vec<u64> = (0..GF2_16::ORDER as u64).step_by(509).collect();
+        let poly = GF2_16::PRIME_POLY as u64 & 0xffff;
+        check_mul(16, poly, &xs, &ys, |a, b| {
+            GF2_16::mul(a as u16, b as u16) as u64
+        });
+    }
+
+    #[test]
+    fn test_generator_is_primitive() {
+        let mut x = 1;
+        for i in 1..GF2_16::ORDER {
+            x = GF2_16::mul(x, GF2_16::GENERATOR);
+            assert_eq!(x == 1, i == GF2_16::ORDER - 1);
+        }
+    }
+}
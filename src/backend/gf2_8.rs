@@ -1,14 +1,24 @@
-include!("./table.rs");
+include!(concat!(env!("OUT_DIR"), "/table8.rs"));
 
-use crate::ff::Fp;
+use super::params::{GF2_8_GENERATOR, GF2_8_PRIME_POLY};
+use super::slice::NibbleTables;
+use crate::ff::{BinaryField, Fp};
 
+/// GF(2^8) with prime polynomial `x^8 + x^4 + x^3 + x^2 + 1`
 #[derive(Debug, Clone, Copy)]
 pub struct GF2k;
 
+impl GF2k {
+    /// Primitive element the log/exp tables are built from
+    pub const GENERATOR: u8 = GF2_8_GENERATOR as u8;
+}
+
 impl Fp for GF2k {
     const ORDER: usize = 256;
 
-    const PRIME_POLY: usize = 0x11b;
+    const PRIME_POLY: usize = GF2_8_PRIME_POLY;
+
+    const SYMBOL_SIZE: usize = 1;
 
     type Elem = u8;
 
@@ -43,19 +53,17 @@ impl Fp for GF2k {
 
     #[inline]
     fn exp(a: Self::Elem, power: usize) -> Self::Elem {
-        if a == 0 {
-            return 0;
-        }
         if power == 0 {
             return 1;
         }
-
-        let mut res_exp = LOG_TABLE[a as usize] * power as u8;
-        while res_exp >= Self::ORDER as u8 {
-            res_exp -= Self::ORDER as u8;
+        if a == 0 {
+            return 0;
         }
 
-        EXP_TABLE[res_exp as usize]
+        let res_exp =
+            (LOG_TABLE[a as usize] as usize * (power % (Self::ORDER - 1))) % (Self::ORDER - 1);
+
+        EXP_TABLE[res_exp]
     }
 
     #[inline]
@@ -64,7 +72,7 @@ impl Fp for GF2k {
             return None;
         }
 
-        Some(EXP_TABLE[Self::ORDER - LOG_TABLE[a as usize] as usize])
+        Some(EXP_TABLE[Self::ORDER - 1 - LOG_TABLE[a as usize] as usize])
     }
 
     #[inline]
@@ -97,11 +105,32 @@ impl Fp for GF2k {
     fn to_u8(a: Self::Elem) -> u8 {
         a
     }
+
+    fn from_u64(a: u64) -> Self::Elem {
+        a as u8
+    }
+
+    fn to_u64(a: Self::Elem) -> u64 {
+        a as u64
+    }
+
+    fn mul_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        NibbleTables::new::<Self>(c).mul_slice(src, dst)
+    }
+
+    fn mul_add_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        NibbleTables::new::<Self>(c).mul_add_slice(src, dst)
+    }
+}
+
+impl BinaryField for GF2k {
+    const DEGREE: usize = 8;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::check_mul;
 
     #[test]
     fn test_add() {
@@ -154,4 +183,21 @@ mod tests {
         assert_eq!(GF2k::neg(0), 0);
         assert_eq!(GF2k::neg(1), 1);
     }
+
+    #[test]
+    fn test_tables_match_prime_poly() {
+        let xs: Vec<u64> = (0..GF2k::ORDER as u64).collect();
+        let ys: Vec<u64> = xs.iter().step_by(3).copied().collect();
+        let poly = GF2k::PRIME_POLY as u64 & 0xff;
+        check_mul(8, poly, &xs, &ys, |a, b| GF2k::mul(a as u8, b as u8) as u64);
+    }
+
+    #[test]
+    fn test_generator_is_primitive() {
+        let mut x = 1;
+        for i in 1..GF2k::ORDER {
+            x = GF2k::mul(x, GF2k::GENERATOR);
+            assert_eq!(x == 1, i == GF2k::ORDER - 1);
+        }
+    }
 }
@@ -1 +1,37 @@
+pub mod clmul;
+pub mod gf256;
+pub mod gf2_16;
 pub mod gf2_8;
+mod params;
+pub mod prime;
+pub mod slice;
+
+/// `a * b` modulo `x^m + poly`, by shifts and additions, independent of any tables or
+/// instructions.
+const fn mul_mod(m: usize, poly: u64, mut a: u64, mut b: u64) -> u64 {
+    let mask = if m == 64 { u64::MAX } else { (1 << m) - 1 };
+    let mut result = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            result ^= a;
+        }
+        let carry = (a >> (m - 1)) & 1 == 1;
+        a = (a << 1) & mask;
+        if carry {
+            a ^= poly;
+        }
+        b >>= 1;
+    }
+
+    result
+}
+
+/// Checks `mul` against [`mul_mod`] for every `a` of `xs` and `b` of `ys`.
+#[cfg(test)]
+fn check_mul(m: usize, poly: u64, xs: &[u64], ys: &[u64], mul: impl Fn(u64, u64) -> u64) {
+    for &a in xs {
+        for &b in ys {
+            assert_eq!(mul(a, b), mul_mod(m, poly, a, b), "{a:#x} * {b:#x}");
+        }
+    }
+}
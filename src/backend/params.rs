@@ -0,0 +1,14 @@
+// Field parameters shared by `build.rs`, which generates the log/exp tables from them,
+// and the backends reading those tables.
+
+/// Prime polynomial of GF(2^8), `x^8 + x^4 + x^3 + x^2 + 1`
+pub const GF2_8_PRIME_POLY: usize = 0x11d;
+
+/// Primitive element generating the log/exp tables of GF(2^8)
+pub const GF2_8_GENERATOR: usize = 2;
+
+/// Prime polynomial of GF(2^16), `x^16 + x^12 + x^3 + x + 1`
+pub const GF2_16_PRIME_POLY: usize = 0x1100b;
+
+/// Primitive element generating the log/exp tables of GF(2^16)
+pub const GF2_16_GENERATOR: usize = 2;
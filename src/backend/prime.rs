@@ -0,0 +1,639 @@
+//! Prime fields with elements kept in [Montgomery form](https://cp-algorithms.com/algebra/montgomery_multiplication.html).
+//!
+//! The byte level codec APIs read each `SYMBOL_SIZE` chunk as a big-endian integer, and
+//! the encoding ones reject symbols that are not smaller than the modulus, as those would
+//! not round-trip.
+
+use std::marker::PhantomData;
+
+use crate::ff::Fp;
+
+/// Prime field of odd order `P < 2^64`, with `R = 2^64`.
+#[derive(Debug, Clone, Copy)]
+pub struct PrimeField<const P: u64>;
+
+/// `998244353 = 119 * 2^23 + 1`, with primitive root 3
+pub type Mod998244353 = PrimeField<998_244_353>;
+
+/// Goldilocks `2^64 - 2^32 + 1`, with primitive root 7
+pub type Goldilocks = PrimeField<0xffff_ffff_0000_0001>;
+
+/// BabyBear `2^31 - 2^27 + 1`, with primitive root 31
+pub type BabyBear = PrimeField<0x7800_0001>;
+
+impl<const P: u64> PrimeField<P> {
+    /// `-P^-1 mod 2^64`
+    const INV: u64 = {
+        assert!(P > 2 && P & 1 == 1, "modulus must be an odd prime");
+
+        // Newton iteration, every step doubles the number of correct low bits
+        let mut inv: u64 = 1;
+        let mut i = 0;
+        while i < 6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(P.wrapping_mul(inv)));
+            i += 1;
+        }
+
+        inv.wrapping_neg()
+    };
+
+    /// `R^2 mod P`
+    const R2: u64 = {
+        let r = ((1u128 << 64) % P as u128) as u64;
+        ((r as u128 * r as u128) % P as u128) as u64
+    };
+
+    #[inline]
+    fn reduce(t: u128) -> u64 {
+        let m = (t as u64).wrapping_mul(Self::INV);
+        let (sum, carry) = t.overflowing_add(m as u128 * P as u128);
+        let u = (sum >> 64) | ((carry as u128) << 64);
+
+        if u >= P as u128 {
+            (u - P as u128) as u64
+        } else {
+            u as u64
+        }
+    }
+
+    #[inline]
+    fn to_montgomery(a: u64) -> u64 {
+        Self::reduce((a % P) as u128 * Self::R2 as u128)
+    }
+
+    #[inline]
+    fn from_montgomery(a: u64) -> u64 {
+        Self::reduce(a as u128)
+    }
+
+    fn pow(a: u64, mut power: u64) -> u64 {
+        let mut result = <Self as Fp>::one();
+        let mut base = a;
+
+        while power > 0 {
+            if power & 1 == 1 {
+                result = <Self as Fp>::mul(result, base);
+            }
+            base = <Self as Fp>::mul(base, base);
+            power >>= 1;
+        }
+
+        result
+    }
+}
+
+impl<const P: u64> Fp for PrimeField<P> {
+    const ORDER: usize = if P > usize::MAX as u64 {
+        usize::MAX
+    } else {
+        P as usize
+    };
+
+    const PRIME_POLY: usize = Self::ORDER;
+
+    const SYMBOL_SIZE: usize = (64 - P.leading_zeros() as usize).div_ceil(8);
+
+    type Elem = u64;
+
+    #[inline]
+    fn zero() -> Self::Elem {
+        0
+    }
+
+    #[inline]
+    fn one() -> Self::Elem {
+        Self::to_montgomery(1)
+    }
+
+    #[inline]
+    fn add(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        let (sum, carry) = a.overflowing_add(b);
+        if carry || sum >= P {
+            sum.wrapping_sub(P)
+        } else {
+            sum
+        }
+    }
+
+    #[inline]
+    fn sub(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        let (diff, borrow) = a.overflowing_sub(b);
+        if borrow {
+            diff.wrapping_add(P)
+        } else {
+            diff
+        }
+    }
+
+    #[inline]
+    fn mul(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        Self::reduce(a as u128 * b as u128)
+    }
+
+    #[inline]
+    fn exp(a: Self::Elem, power: usize) -> Self::Elem {
+        Self::pow(a, power as u64)
+    }
+
+    #[inline]
+    fn inverse(a: Self::Elem) -> Option<Self::Elem> {
+        if a == 0 {
+            return None;
+        }
+
+        Some(Self::pow(a, P - 2))
+    }
+
+    #[inline]
+    fn div(a: Self::Elem, b: Self::Elem) -> Option<Self::Elem> {
+        Some(Self::mul(a, Self::inverse(b)?))
+    }
+
+    #[inline]
+    fn neg(a: Self::Elem) -> Self::Elem {
+        Self::sub(0, a)
+    }
+
+    #[inline]
+    fn eq(a: &Self::Elem, b: &Self::Elem) -> bool {
+        a == b
+    }
+
+    fn from_u8(a: u8) -> Self::Elem {
+        Self::to_montgomery(a as u64)
+    }
+
+    fn to_u8(a: Self::Elem) -> u8 {
+        Self::from_montgomery(a) as u8
+    }
+
+    fn from_u64(a: u64) -> Self::Elem {
+        Self::to_montgomery(a)
+    }
+
+    fn to_u64(a: Self::Elem) -> u64 {
+        Self::from_montgomery(a)
+    }
+
+    fn is_canonical(bytes: &[u8]) -> bool {
+        bytes.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128) < P as u128
+    }
+}
+
+/// Modulus of a [`PrimeField256`], as little-endian 64-bit limbs.
+pub trait Modulus256: std::fmt::Debug + Clone + Copy {
+    const MODULUS: [u64; 4];
+}
+
+/// Scalar field of the BN254 curve, with primitive root 5
+#[derive(Debug, Clone, Copy)]
+pub struct Bn254Scalar;
+
+impl Modulus256 for Bn254Scalar {
+    const MODULUS: [u64; 4] = [
+        0x43e1f593f0000001,
+        0x2833e84879b97091,
+        0xb85045b68181585d,
+        0x30644e72e131a029,
+    ];
+}
+
+pub type Bn254Fr = PrimeField256<Bn254Scalar>;
+
+/// Prime field of odd order below `2^256`, with `R = 2^256`.
+///
+/// The field order does not fit `Fp::ORDER`, which saturates at `usize::MAX`.
+#[derive(Debug, Clone, Copy)]
+pub struct PrimeField256<M: Modulus256> {
+    _modulus: PhantomData<M>,
+}
+
+type Limbs = [u64; 4];
+
+#[inline]
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 * c as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline]
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline]
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (t as u64, (t >> 127) as u64)
+}
+
+/// Reads the last 32 bytes of the big-endian `bytes` as limbs.
+fn limbs_from_bytes(bytes: &[u8]) -> Limbs {
+    let mut limbs = [0u64; 4];
+    for (i, b) in bytes.iter().rev().enumerate().take(32) {
+        limbs[i / 8] |= (*b as u64) << (8 * (i % 8));
+    }
+
+    limbs
+}
+
+const fn geq(a: &Limbs, b: &Limbs) -> bool {
+    let mut i = 4;
+    while i > 0 {
+        i -= 1;
+        if a[i] != b[i] {
+            return a[i] > b[i];
+        }
+    }
+    true
+}
+
+const fn sub_limbs(a: &Limbs, b: &Limbs) -> (Limbs, bool) {
+    let mut result = [0u64; 4];
+    let mut borrow = false;
+    let mut i = 0;
+    while i < 4 {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(borrow as u64);
+        result[i] = d;
+        borrow = b1 || b2;
+        i += 1;
+    }
+    (result, borrow)
+}
+
+/// Computes `2 * a mod m` for `a < m`.
+const fn double_mod(a: &Limbs, m: &Limbs) -> Limbs {
+    let mut result = [0u64; 4];
+    let mut carry = 0;
+    let mut i = 0;
+    while i < 4 {
+        result[i] = (a[i] << 1) | carry;
+        carry = a[i] >> 63;
+        i += 1;
+    }
+
+    if carry == 1 || geq(&result, m) {
+        sub_limbs(&result, m).0
+    } else {
+        result
+    }
+}
+
+impl<M: Modulus256> PrimeField256<M> {
+    /// `-M^-1 mod 2^64`
+    const INV: u64 = {
+        let m = M::MODULUS[0];
+        assert!(m & 1 == 1, "modulus must be an odd prime");
+
+        let mut inv: u64 = 1;
+        let mut i = 0;
+        while i < 6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(m.wrapping_mul(inv)));
+            i += 1;
+        }
+
+        inv.wrapping_neg()
+    };
+
+    /// `R^2 mod M`, found by doubling one 512 times
+    const R2: Limbs = {
+        let mut r = [1, 0, 0, 0];
+        let mut i = 0;
+        while i < 512 {
+            r = double_mod(&r, &M::MODULUS);
+            i += 1;
+        }
+        r
+    };
+
+    /// Montgomery multiplication using the CIOS method.
+    #[inline]
+    fn mont_mul(a: &Limbs, b: &Limbs) -> Limbs {
+        let m = &M::MODULUS;
+        let mut t = [0u64; 6];
+
+        for bi in b.iter() {
+            let mut carry = 0;
+            for j in 0..4 {
+                (t[j], carry) = mac(t[j], a[j], *bi, carry);
+            }
+            (t[4], t[5]) = adc(t[4], carry, 0);
+
+            let k = t[0].wrapping_mul(Self::INV);
+            let (_, mut carry) = mac(t[0], k, m[0], 0);
+            for j in 1..4 {
+                (t[j - 1], carry) = mac(t[j], k, m[j], carry);
+            }
+            (t[3], carry) = adc(t[4], carry, 0);
+            t[4] = t[5] + carry;
+        }
+
+        let result = [t[0], t[1], t[2], t[3]];
+        if t[4] != 0 || geq(&result, m) {
+            sub_limbs(&result, m).0
+        } else {
+            result
+        }
+    }
+
+    fn to_montgomery(a: &Limbs) -> Limbs {
+        let mut a = *a;
+        while geq(&a, &M::MODULUS) {
+            a = sub_limbs(&a, &M::MODULUS).0;
+        }
+
+        Self::mont_mul(&a, &Self::R2)
+    }
+
+    fn from_montgomery(a: &Limbs) -> Limbs {
+        Self::mont_mul(a, &[1, 0, 0, 0])
+    }
+
+    /// Exponentiates by a 256-bit power.
+    fn pow(a: &Limbs, power: &Limbs) -> Limbs {
+        let mut result = <Self as Fp>::one();
+
+        for limb in power.iter().rev() {
+            for bit in (0..64).rev() {
+                result = Self::mont_mul(&result, &result);
+                if (limb >> bit) & 1 == 1 {
+                    result = Self::mont_mul(&result, a);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl<M: Modulus256> Fp for PrimeField256<M> {
+    const ORDER: usize = usize::MAX;
+
+    const PRIME_POLY: usize = usize::MAX;
+
+    const SYMBOL_SIZE: usize = 32;
+
+    type Elem = Limbs;
+
+    #[inline]
+    fn zero() -> Self::Elem {
+        [0; 4]
+    }
+
+    #[inline]
+    fn one() -> Self::Elem {
+        Self::to_montgomery(&[1, 0, 0, 0])
+    }
+
+    #[inline]
+    fn add(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        let mut sum = [0u64; 4];
+        let mut carry = 0;
+        for i in 0..4 {
+            (sum[i], carry) = adc(a[i], b[i], carry);
+        }
+
+        if carry == 1 || geq(&sum, &M::MODULUS) {
+            sub_limbs(&sum, &M::MODULUS).0
+        } else {
+            sum
+        }
+    }
+
+    #[inline]
+    fn sub(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        let mut diff = [0u64; 4];
+        let mut borrow = 0;
+        for i in 0..4 {
+            (diff[i], borrow) = sbb(a[i], b[i], borrow);
+        }
+
+        if borrow == 1 {
+            let mut carry = 0;
+            for (d, m) in diff.iter_mut().zip(M::MODULUS.iter()) {
+                (*d, carry) = adc(*d, *m, carry);
+            }
+        }
+
+        diff
+    }
+
+    #[inline]
+    fn mul(a: Self::Elem, b: Self::Elem) -> Self::Elem {
+        Self::mont_mul(&a, &b)
+    }
+
+    #[inline]
+    fn exp(a: Self::Elem, power: usize) -> Self::Elem {
+        Self::pow(&a, &[power as u64, 0, 0, 0])
+    }
+
+    #[inline]
+    fn inverse(a: Self::Elem) -> Option<Self::Elem> {
+        if a == [0; 4] {
+            return None;
+        }
+
+        let (power, _) = sub_limbs(&M::MODULUS, &[2, 0, 0, 0]);
+        Some(Self::pow(&a, &power))
+    }
+
+    #[inline]
+    fn div(a: Self::Elem, b: Self::Elem) -> Option<Self::Elem> {
+        Some(Self::mul(a, Self::inverse(b)?))
+    }
+
+    #[inline]
+    fn neg(a: Self::Elem) -> Self::Elem {
+        Self::sub([0; 4], a)
+    }
+
+    #[inline]
+    fn eq(a: &Self::Elem, b: &Self::Elem) -> bool {
+        a == b
+    }
+
+    fn from_u8(a: u8) -> Self::Elem {
+        Self::to_montgomery(&[a as u64, 0, 0, 0])
+    }
+
+    fn to_u8(a: Self::Elem) -> u8 {
+        Self::from_montgomery(&a)[0] as u8
+    }
+
+    fn from_u64(a: u64) -> Self::Elem {
+        Self::to_montgomery(&[a, 0, 0, 0])
+    }
+
+    fn to_u64(a: Self::Elem) -> u64 {
+        Self::from_montgomery(&a)[0]
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self::Elem {
+        Self::to_montgomery(&limbs_from_bytes(bytes))
+    }
+
+    fn is_canonical(bytes: &[u8]) -> bool {
+        let high = &bytes[..bytes.len().saturating_sub(32)];
+        high.iter().all(|&b| b == 0) && !geq(&limbs_from_bytes(bytes), &M::MODULUS)
+    }
+
+    fn to_bytes(a: Self::Elem, bytes: &mut [u8]) {
+        let limbs = Self::from_montgomery(&a);
+        for (i, b) in bytes.iter_mut().rev().enumerate() {
+            *b = if i < 32 {
+                (limbs[i / 8] >> (8 * (i % 8))) as u8
+            } else {
+                0
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Errors;
+    use crate::ff::FieldElement;
+    use crate::reedsolomon::ReedSolomon;
+
+    type F = Mod998244353;
+
+    #[test]
+    fn test_add_sub() {
+        let a = F::from_u64(998_244_352);
+        let b = F::from_u64(5);
+
+        assert_eq!(F::to_u64(F::add(a, b)), 4);
+        assert_eq!(F::to_u64(F::sub(b, a)), 6);
+        assert_eq!(F::to_u64(F::neg(b)), 998_244_348);
+        assert_eq!(F::neg(F::zero()), F::zero());
+    }
+
+    #[test]
+    fn test_mul() {
+        let a = F::from_u64(123_456_789);
+        let b = F::from_u64(987_654_321);
+
+        assert_eq!(
+            F::to_u64(F::mul(a, b)),
+            (123_456_789u64 * 987_654_321) % 998_244_353
+        );
+        assert_eq!(F::mul(a, F::one()), a);
+    }
+
+    #[test]
+    fn test_exp_inverse() {
+        let three = F::from_u8(3);
+
+        assert_eq!(F::exp(three, 998_244_352), F::one());
+        assert_ne!(F::exp(three, 998_244_352 / 2), F::one());
+        assert_eq!(F::inverse(F::zero()), None);
+        assert_eq!(F::mul(three, F::inverse(three).unwrap()), F::one());
+        assert_eq!(F::div(F::one(), F::zero()), None);
+    }
+
+    #[test]
+    fn test_goldilocks() {
+        let p = 0xffff_ffff_0000_0001u64;
+        let a = Goldilocks::from_u64(p - 1);
+
+        assert_eq!(Goldilocks::to_u64(Goldilocks::mul(a, a)), 1);
+        assert_eq!(Goldilocks::to_u64(Goldilocks::add(a, a)), p - 2);
+        assert_eq!(
+            Goldilocks::exp(Goldilocks::from_u8(7), (p - 1) as usize),
+            Goldilocks::one()
+        );
+        assert_eq!(Goldilocks::SYMBOL_SIZE, 8);
+        assert_eq!(BabyBear::SYMBOL_SIZE, 4);
+    }
+
+    #[test]
+    fn test_bn254() {
+        let mut bytes = [0u8; 32];
+        let a = FieldElement::<Bn254Fr>::from_bytes(
+            &(0..32)
+                .map(|i| [0x12, 0x34, 0x56, 0x78, 0x90, 0xab, 0xcd, 0xef][i % 8])
+                .collect::<Vec<u8>>(),
+        );
+        let b = FieldElement::<Bn254Fr>::from_bytes(
+            &[0xfe, 0xdc, 0xba, 0x09, 0x87, 0x65, 0x43, 0x21].repeat(4),
+        );
+
+        (a * b).write_bytes(&mut bytes);
+        assert_eq!(
+            bytes.to_vec(),
+            [
+                0x08, 0x47, 0xfe, 0xa8, 0x56, 0x60, 0x0c, 0x9c, 0x38, 0xca, 0x53, 0xa4, 0x7d, 0x36,
+                0xc0, 0x20, 0xa4, 0x2c, 0xeb, 0x95, 0x05, 0x6f, 0xd4, 0xeb, 0x8d, 0xec, 0x2c, 0xc9,
+                0xfc, 0x7d, 0x39, 0x81
+            ]
+        );
+
+        (FieldElement::<Bn254Fr>::new(Bn254Fr::one()) / a).write_bytes(&mut bytes);
+        assert_eq!(bytes[..4], [0x2b, 0xb1, 0x3c, 0x11]);
+        assert_eq!(
+            Bn254Fr::to_u64(Bn254Fr::neg(Bn254Fr::neg(Bn254Fr::from_u64(9)))),
+            9
+        );
+    }
+
+    #[test]
+    fn reedsolomon_prime() {
+        let rs = ReedSolomon::<BabyBear>::new(40, 30, FieldElement::from(31)).unwrap();
+        let message: Vec<u8> = (0..120)
+            .map(|i| if i % 4 == 0 { 0 } else { (i * 3) as u8 })
+            .collect();
+        let mut codeword = rs.encode(&message).unwrap();
+
+        assert!(rs.check(&codeword).unwrap());
+
+        codeword[5] ^= 0x10;
+        codeword[77] ^= 0x01;
+        codeword[150] ^= 0xff;
+        let decoded = rs.decode_with_erasures(&codeword, &[0, 1]).unwrap();
+
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, vec![19, 37]);
+        assert_eq!(decoded.erasures, vec![0, 1]);
+
+        // symbols of at least the modulus would come back reduced
+        let mut large = message.clone();
+        large[4..8].copy_from_slice(&0x7800_0001u32.to_be_bytes());
+        assert_eq!(rs.encode(&large), Err(Errors::InvalidMessage));
+        assert_eq!(rs.encode(&[0xff; 32]), Err(Errors::InvalidMessage));
+        assert_eq!(
+            rs.encode_into(&large, &mut [0; 40]),
+            Err(Errors::InvalidMessage)
+        );
+        large[4..8].copy_from_slice(&0x7800_0000u32.to_be_bytes());
+        let codeword = rs.encode(&large).unwrap();
+        assert_eq!(rs.decode(&codeword).unwrap().message, large);
+
+        let mut codeword = codeword;
+        codeword[0..4].copy_from_slice(&[0xff; 4]);
+        assert_eq!(rs.check(&codeword), Ok(false));
+        let decoded = rs.decode(&codeword).unwrap();
+        assert_eq!(decoded.message, large);
+        assert_eq!(decoded.erasures, vec![0]);
+    }
+
+    #[test]
+    fn reedsolomon_bn254() {
+        let rs = ReedSolomon::<Bn254Fr>::new(8, 4, FieldElement::from(5)).unwrap();
+        let message: Vec<u8> = (0..128)
+            .map(|i| if i % 32 == 0 { 0 } else { i as u8 })
+            .collect();
+        let mut codeword = rs.encode(&message).unwrap();
+
+        assert_eq!(rs.encode(&[0xff; 128]), Err(Errors::InvalidMessage));
+
+        codeword[40] ^= 0x42;
+        let decoded = rs.decode_with_erasures(&codeword, &[6]).unwrap();
+
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, vec![1]);
+    }
+}
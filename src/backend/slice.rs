@@ -0,0 +1,300 @@
+//! Bulk GF(2^8) multiplication of byte slices with split nibble tables.
+//!
+//! `c * b = c * (b & 0x0f) + c * (b & 0xf0)`, so two 16 entry tables per coefficient turn
+//! a multiplication into two lookups, which PSHUFB on x86 and TBL on aarch64 perform for
+//! 16, 32 or 64 bytes at once. The widest instruction set reported by the CPU at runtime
+//! is used, with the same lookups done byte by byte as the portable fallback.
+
+#[cfg(test)]
+use std::cell::Cell;
+use std::sync::OnceLock;
+
+use crate::ff::BinaryField;
+
+#[cfg(test)]
+thread_local! {
+    static SLICE_BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Bytes multiplied by [`NibbleTables`] on the current thread, to check that bulk paths
+/// reach the kernels.
+#[cfg(test)]
+pub(crate) fn slice_bytes() -> usize {
+    SLICE_BYTES.with(|bytes| bytes.get())
+}
+
+/// Implementation of the slice kernels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    /// AVX-512BW, 64 bytes per step
+    Avx512,
+    /// AVX2, 32 bytes per step
+    Avx2,
+    /// SSSE3, 16 bytes per step
+    Ssse3,
+    /// NEON, 16 bytes per step
+    Neon,
+    /// Table lookups one byte at a time
+    Portable,
+}
+
+impl Kernel {
+    /// The fastest kernel supported by the running CPU, detected once.
+    pub fn detect() -> Self {
+        static KERNEL: OnceLock<Kernel> = OnceLock::new();
+
+        *KERNEL.get_or_init(|| {
+            [Kernel::Avx512, Kernel::Avx2, Kernel::Ssse3, Kernel::Neon]
+                .into_iter()
+                .find(|kernel| kernel.is_supported())
+                .unwrap_or(Kernel::Portable)
+        })
+    }
+
+    /// Returns whether the running CPU can execute the kernel.
+    pub fn is_supported(self) -> bool {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx512 => {
+                is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw")
+            }
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Ssse3 => is_x86_feature_detected!("ssse3"),
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            Kernel::Portable => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+/// Products of a coefficient with every low nibble and every high nibble
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NibbleTables {
+    lo: [u8; 16],
+    hi: [u8; 16],
+}
+
+impl NibbleTables {
+    /// Tables multiplying by `c` in the GF(2^8) `F`.
+    pub fn new<F: BinaryField<Elem = u8>>(c: u8) -> Self {
+        let mut tables = NibbleTables {
+            lo: [0; 16],
+            hi: [0; 16],
+        };
+        for i in 0..16 {
+            tables.lo[i] = F::mul(c, i as u8);
+            tables.hi[i] = F::mul(c, (i as u8) << 4);
+        }
+
+        tables
+    }
+
+    #[inline]
+    fn mul(&self, b: u8) -> u8 {
+        self.lo[(b & 0x0f) as usize] ^ self.hi[(b >> 4) as usize]
+    }
+
+    /// Sets `dst` to `c * src` byte by byte.
+    ///
+    /// # Panics
+    ///
+    /// If `src` and `dst` differ in length.
+    pub fn mul_slice(&self, src: &[u8], dst: &mut [u8]) {
+        self.run(Kernel::detect(), src, dst, false);
+    }
+
+    /// Adds `c * src` to `dst` byte by byte.
+    ///
+    /// # Panics
+    ///
+    /// If `src` and `dst` differ in length.
+    pub fn mul_add_slice(&self, src: &[u8], dst: &mut [u8]) {
+        self.run(Kernel::detect(), src, dst, true);
+    }
+
+    /// Runs `kernel` over the whole vectors of `src` and finishes the tail portably.
+    /// `kernel` must be supported by the CPU.
+    fn run(&self, kernel: Kernel, src: &[u8], dst: &mut [u8], add: bool) {
+        assert_eq!(src.len(), dst.len(), "slices differ in length");
+        debug_assert!(kernel.is_supported());
+        #[cfg(test)]
+        SLICE_BYTES.with(|bytes| bytes.set(bytes.get() + src.len()));
+
+        // SAFETY: the kernel's target features were detected at runtime and the slices
+        // have equal length, which bounds every vector load and store
+        let done = match kernel {
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx512 => unsafe { x86::avx512(self, src, dst, add) },
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx2 => unsafe { x86::avx2(self, src, dst, add) },
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Ssse3 => unsafe { x86::ssse3(self, src, dst, add) },
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => unsafe { arm::neon(self, src, dst, add) },
+            _ => 0,
+        };
+
+        for (d, &s) in dst[done..].iter_mut().zip(&src[done..]) {
+            *d = if add { *d ^ self.mul(s) } else { self.mul(s) };
+        }
+    }
+}
+
+/// Each kernel handles the whole vectors of `src` and returns how many bytes that was.
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::NibbleTables;
+
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn ssse3(t: &NibbleTables, src: &[u8], dst: &mut [u8], add: bool) -> usize {
+        let lo = _mm_loadu_si128(t.lo.as_ptr().cast());
+        let hi = _mm_loadu_si128(t.hi.as_ptr().cast());
+        let mask = _mm_set1_epi8(0x0f);
+
+        let len = src.len() / 16 * 16;
+        for i in (0..len).step_by(16) {
+            let s = _mm_loadu_si128(src.as_ptr().add(i).cast());
+            let l = _mm_shuffle_epi8(lo, _mm_and_si128(s, mask));
+            let h = _mm_shuffle_epi8(hi, _mm_and_si128(_mm_srli_epi64::<4>(s), mask));
+
+            let d = dst.as_mut_ptr().add(i).cast();
+            let mut product = _mm_xor_si128(l, h);
+            if add {
+                product = _mm_xor_si128(product, _mm_loadu_si128(d));
+            }
+            _mm_storeu_si128(d, product);
+        }
+
+        len
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn avx2(t: &NibbleTables, src: &[u8], dst: &mut [u8], add: bool) -> usize {
+        // PSHUFB looks up within each 128 bit lane, so both lanes get the tables
+        let lo = _mm256_broadcastsi128_si256(_mm_loadu_si128(t.lo.as_ptr().cast()));
+        let hi = _mm256_broadcastsi128_si256(_mm_loadu_si128(t.hi.as_ptr().cast()));
+        let mask = _mm256_set1_epi8(0x0f);
+
+        let len = src.len() / 32 * 32;
+        for i in (0..len).step_by(32) {
+            let s = _mm256_loadu_si256(src.as_ptr().add(i).cast());
+            let l = _mm256_shuffle_epi8(lo, _mm256_and_si256(s, mask));
+            let h = _mm256_shuffle_epi8(hi, _mm256_and_si256(_mm256_srli_epi64::<4>(s), mask));
+
+            let d = dst.as_mut_ptr().add(i).cast();
+            let mut product = _mm256_xor_si256(l, h);
+            if add {
+                product = _mm256_xor_si256(product, _mm256_loadu_si256(d));
+            }
+            _mm256_storeu_si256(d, product);
+        }
+
+        len
+    }
+
+    #[target_feature(enable = "avx512f,avx512bw")]
+    pub(super) unsafe fn avx512(t: &NibbleTables, src: &[u8], dst: &mut [u8], add: bool) -> usize {
+        let lo = _mm512_broadcast_i32x4(_mm_loadu_si128(t.lo.as_ptr().cast()));
+        let hi = _mm512_broadcast_i32x4(_mm_loadu_si128(t.hi.as_ptr().cast()));
+        let mask = _mm512_set1_epi8(0x0f);
+
+        let len = src.len() / 64 * 64;
+        for i in (0..len).step_by(64) {
+            let s = _mm512_loadu_si512(src.as_ptr().add(i).cast());
+            let l = _mm512_shuffle_epi8(lo, _mm512_and_si512(s, mask));
+            let h = _mm512_shuffle_epi8(hi, _mm512_and_si512(_mm512_srli_epi64::<4>(s), mask));
+
+            let d = dst.as_mut_ptr().add(i).cast();
+            let mut product = _mm512_xor_si512(l, h);
+            if add {
+                product = _mm512_xor_si512(product, _mm512_loadu_si512(d));
+            }
+            _mm512_storeu_si512(d, product);
+        }
+
+        len
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use std::arch::aarch64::*;
+
+    use super::NibbleTables;
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn neon(t: &NibbleTables, src: &[u8], dst: &mut [u8], add: bool) -> usize {
+        let lo = vld1q_u8(t.lo.as_ptr());
+        let hi = vld1q_u8(t.hi.as_ptr());
+        let mask = vdupq_n_u8(0x0f);
+
+        let len = src.len() / 16 * 16;
+        for i in (0..len).step_by(16) {
+            let s = vld1q_u8(src.as_ptr().add(i));
+            let l = vqtbl1q_u8(lo, vandq_u8(s, mask));
+            let h = vqtbl1q_u8(hi, vshrq_n_u8::<4>(s));
+
+            let d = dst.as_mut_ptr().add(i);
+            let mut product = veorq_u8(l, h);
+            if add {
+                product = veorq_u8(product, vld1q_u8(d));
+            }
+            vst1q_u8(d, product);
+        }
+
+        len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::gf256::CcsdsField;
+    use crate::backend::gf2_8::GF2k;
+
+    fn check_kernels<F: BinaryField<Elem = u8>>() {
+        // odd lengths leave a tail after the widest vectors
+        let src: Vec<u8> = (0..203).map(|i| (i * 37 + 11) as u8).collect();
+        let acc: Vec<u8> = (0..203).map(|i| (i * 91 + 5) as u8).collect();
+
+        for c in [0, 1, 2, 0x53, 0x80, 0xff] {
+            let tables = NibbleTables::new::<F>(c);
+            let product: Vec<u8> = src.iter().map(|&s| F::mul(c, s)).collect();
+            let sum: Vec<u8> = product.iter().zip(&acc).map(|(p, a)| p ^ a).collect();
+
+            for kernel in [
+                Kernel::Avx512,
+                Kernel::Avx2,
+                Kernel::Ssse3,
+                Kernel::Neon,
+                Kernel::Portable,
+            ] {
+                if !kernel.is_supported() {
+                    continue;
+                }
+
+                let mut dst = acc.clone();
+                tables.run(kernel, &src, &mut dst, false);
+                assert_eq!(dst, product, "{kernel:?} c = {c}");
+
+                let mut dst = acc.clone();
+                tables.run(kernel, &src, &mut dst, true);
+                assert_eq!(dst, sum, "{kernel:?} c = {c}");
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_match_mul() {
+        check_kernels::<GF2k>();
+        check_kernels::<CcsdsField>();
+
+        assert!(Kernel::detect().is_supported());
+    }
+}
@@ -0,0 +1,666 @@
+//! Protects files against bit rot with Reed-Solomon codes.
+//!
+//! Exits with 0 if the data is clean, 1 if it was (or can be) repaired, 2 if it is
+//! unrecoverable and 3 on usage or I/O errors.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use shittyrs_rs::backend::{gf2_16::GF2_16, gf2_8::GF2k};
+use shittyrs_rs::block::BlockCodec;
+use shittyrs_rs::erasure::ShardEncoder;
+use shittyrs_rs::errors::Errors;
+use shittyrs_rs::ff::{FieldElement, Fp};
+use shittyrs_rs::reedsolomon::ReedSolomon;
+use shittyrs_rs::stream::{RsReader, RsWriter, StreamStats};
+
+const EXIT_CLEAN: u8 = 0;
+const EXIT_REPAIRED: u8 = 1;
+const EXIT_UNRECOVERABLE: u8 = 2;
+const EXIT_ERROR: u8 = 3;
+
+const USAGE: &str = "usage: shittyrs <command> [options] <files>
+
+commands:
+    encode <input> <output>    protect <input> into <output>, or into <output>.0.. with --shards
+    decode <input> <output>    recover the original data of <input> into <output>
+    verify <input>             check <input> without modifying it
+    repair <input>             fix <input> in place
+    info                       print the code parameters
+
+options:
+    -n <n>                     codeword symbols (default 255)
+    -k <k>                     message symbols (default 223)
+    -i <depth>                 interleaving depth (default 1)
+    --field <gf256|gf65536>    symbol field (default gf256)
+    --shards <data>+<parity>   split into shard files instead of a single file";
+
+/// Outcome of a command, mapped onto the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Clean,
+    Repaired,
+    Unrecoverable,
+}
+
+impl Status {
+    fn exit_code(self) -> u8 {
+        match self {
+            Status::Clean => EXIT_CLEAN,
+            Status::Repaired => EXIT_REPAIRED,
+            Status::Unrecoverable => EXIT_UNRECOVERABLE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    GF256,
+    GF65536,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Options {
+    command: String,
+    files: Vec<PathBuf>,
+    n: usize,
+    k: usize,
+    interleave: usize,
+    field: Field,
+    shards: Option<(usize, usize)>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let command = args.next().ok_or("missing command")?.clone();
+
+    let mut options = Options {
+        command,
+        files: vec![],
+        n: 255,
+        k: 223,
+        interleave: 1,
+        field: Field::GF256,
+        shards: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for {name}"))
+                .cloned()
+        };
+        let number = |name: &str, v: String| {
+            v.parse::<usize>()
+                .map_err(|_| format!("invalid value for {name}: {v}"))
+        };
+
+        match arg.as_str() {
+            "-n" => options.n = number("-n", value("-n")?)?,
+            "-k" => options.k = number("-k", value("-k")?)?,
+            "-i" => options.interleave = number("-i", value("-i")?)?,
+            "--field" => {
+                options.field = match value("--field")?.as_str() {
+                    "gf256" => Field::GF256,
+                    "gf65536" => Field::GF65536,
+                    other => return Err(format!("unknown field {other}")),
+                }
+            }
+            "--shards" => {
+                let v = value("--shards")?;
+                let (data, parity) = v
+                    .split_once('+')
+                    .ok_or_else(|| format!("invalid value for --shards: {v}"))?;
+                options.shards = Some((
+                    number("--shards", data.to_string())?,
+                    number("--shards", parity.to_string())?,
+                ));
+            }
+            other if other.starts_with('-') => return Err(format!("unknown option {other}")),
+            file => options.files.push(PathBuf::from(file)),
+        }
+    }
+
+    let files = match options.command.as_str() {
+        "encode" | "decode" => 2,
+        "verify" | "repair" => 1,
+        "info" => 0,
+        other => return Err(format!("unknown command {other}")),
+    };
+    if options.files.len() != files {
+        return Err(format!("{} expects {files} file(s)", options.command));
+    }
+
+    Ok(options)
+}
+
+fn other_error(e: Errors) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
+
+/// Whether `e` is a decoding failure rather than an I/O error
+fn is_unrecoverable(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::InvalidData
+}
+
+fn block_codec<F: Fp>(options: &Options, gen: FieldElement<F>) -> io::Result<BlockCodec<F>> {
+    let rs = ReedSolomon::new(options.n, options.k, gen).map_err(other_error)?;
+    BlockCodec::new(rs)
+        .interleaved(options.interleave)
+        .map_err(other_error)
+}
+
+/// Streams `input` through a decoder into `output`, returning the correction statistics.
+fn decode_stream<F: Fp>(
+    options: &Options,
+    gen: FieldElement<F>,
+    input: &Path,
+    output: impl Write,
+) -> io::Result<StreamStats> {
+    let mut reader = RsReader::new(
+        BufReader::new(File::open(input)?),
+        block_codec(options, gen)?,
+    );
+    let mut output = output;
+    io::copy(&mut reader, &mut output)?;
+    output.flush()?;
+
+    Ok(reader.stats().clone())
+}
+
+fn report(stats: &StreamStats) -> Status {
+    for block in stats.corrected.iter() {
+        eprintln!("block {}: {} symbols corrected", block.index, block.errors);
+    }
+
+    if stats.errors == 0 {
+        Status::Clean
+    } else {
+        Status::Repaired
+    }
+}
+
+/// Scratch file next to `path` that no input can be named after
+fn temp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".shittyrs-tmp.{}", std::process::id()));
+    PathBuf::from(tmp)
+}
+
+fn run_stream<F: Fp>(options: &Options, gen: FieldElement<F>) -> io::Result<Status> {
+    let files = &options.files;
+
+    let result = match options.command.as_str() {
+        "encode" => {
+            let mut writer = RsWriter::new(
+                BufWriter::new(File::create(&files[1])?),
+                block_codec(options, gen)?,
+            );
+            io::copy(&mut BufReader::new(File::open(&files[0])?), &mut writer)?;
+            writer.finish()?.flush()?;
+
+            return Ok(Status::Clean);
+        }
+        "decode" => decode_stream(
+            options,
+            gen,
+            &files[0],
+            BufWriter::new(File::create(&files[1])?),
+        ),
+        "verify" => decode_stream(options, gen, &files[0], io::sink()),
+        "repair" => {
+            let tmp = temp_path(&files[0]);
+            let stats = (|| {
+                let mut reader = RsReader::new(
+                    BufReader::new(File::open(&files[0])?),
+                    block_codec(options, gen)?,
+                );
+                let mut writer = RsWriter::new(
+                    BufWriter::new(File::create(&tmp)?),
+                    block_codec(options, gen)?,
+                );
+                io::copy(&mut reader, &mut writer)?;
+                writer.finish()?.flush()?;
+
+                Ok(reader.stats().clone())
+            })();
+
+            match stats {
+                Ok(stats) if stats.errors > 0 => {
+                    fs::rename(&tmp, &files[0])?;
+                    Ok(stats)
+                }
+                result => {
+                    let _ = fs::remove_file(&tmp);
+                    result
+                }
+            }
+        }
+        _ => unreachable!(),
+    };
+
+    match result {
+        Ok(stats) => Ok(report(&stats)),
+        Err(e) if is_unrecoverable(&e) => {
+            eprintln!("{}: {e}", files[0].display());
+            Ok(Status::Unrecoverable)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn shard_path(prefix: &Path, i: usize) -> PathBuf {
+    let mut path = prefix.as_os_str().to_owned();
+    path.push(format!(".{i}"));
+    PathBuf::from(path)
+}
+
+fn read_shards(prefix: &Path, total: usize) -> io::Result<Vec<Option<Vec<u8>>>> {
+    (0..total)
+        .map(|i| match fs::read(shard_path(prefix, i)) {
+            Ok(shard) => Ok(Some(shard)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        })
+        .collect()
+}
+
+/// Reconstructs missing shards, or the single shard whose removal makes the rest
+/// consistent, and returns the indices of the shards that were rebuilt.
+fn repair_shards<F: Fp>(
+    encoder: &ShardEncoder<F>,
+    shards: &mut [Option<Vec<u8>>],
+) -> Result<Vec<usize>, Errors> {
+    let missing: Vec<usize> = (0..shards.len()).filter(|&i| shards[i].is_none()).collect();
+
+    // without spare parity, nothing can tell a corrupt shard apart
+    if missing.len() >= encoder.parity_shards() {
+        encoder.reconstruct(shards)?;
+        return Ok(missing);
+    }
+
+    let rebuild = |without: Option<usize>| {
+        let mut trial = shards.to_vec();
+        if let Some(i) = without {
+            trial[i] = None;
+        }
+        encoder.reconstruct(&mut trial).ok()?;
+        let trial: Vec<Vec<u8>> = trial.into_iter().flatten().collect();
+        encoder.verify(&trial).ok()?.then_some(trial)
+    };
+
+    let (rebuilt, fixed) = match rebuild(None) {
+        Some(fixed) => (missing, fixed),
+        None => {
+            // a present shard is corrupt, find the one whose reconstruction is consistent
+            let candidates: Vec<(usize, Vec<Vec<u8>>)> = (0..shards.len())
+                .filter(|&i| shards[i].is_some())
+                .filter_map(|i| Some((i, rebuild(Some(i))?)))
+                .collect();
+
+            match <[_; 1]>::try_from(candidates) {
+                Ok([(corrupt, fixed)]) => {
+                    let mut rebuilt = missing;
+                    rebuilt.push(corrupt);
+                    rebuilt.sort_unstable();
+                    (rebuilt, fixed)
+                }
+                Err(_) => return Err(Errors::TooManyErrors),
+            }
+        }
+    };
+
+    for (shard, fixed) in shards.iter_mut().zip(fixed) {
+        *shard = Some(fixed);
+    }
+    Ok(rebuilt)
+}
+
+fn run_shards<F: Fp>(options: &Options, data: usize, parity: usize) -> io::Result<Status> {
+    let encoder = ShardEncoder::<F>::new(data, parity).map_err(other_error)?;
+    let files = &options.files;
+
+    if options.command == "encode" {
+        let mut input = fs::read(&files[0])?;
+        let len = input.len() as u64;
+        input.splice(0..0, len.to_be_bytes());
+
+        let shard_size = input.len().div_ceil(data).next_multiple_of(F::SYMBOL_SIZE);
+        input.resize(shard_size * data, 0);
+        let mut shards: Vec<Vec<u8>> = input.chunks(shard_size).map(|s| s.to_vec()).collect();
+        shards.resize(data + parity, vec![0; shard_size]);
+        encoder.encode(&mut shards).map_err(other_error)?;
+
+        for (i, shard) in shards.iter().enumerate() {
+            fs::write(shard_path(&files[1], i), shard)?;
+        }
+        return Ok(Status::Clean);
+    }
+
+    let mut shards = read_shards(&files[0], data + parity)?;
+    let rebuilt = match repair_shards(&encoder, &mut shards) {
+        Ok(rebuilt) => rebuilt,
+        Err(e) => {
+            eprintln!("{}: {e}", files[0].display());
+            return Ok(Status::Unrecoverable);
+        }
+    };
+    for &i in rebuilt.iter() {
+        eprintln!("shard {i}: rebuilt");
+    }
+
+    match options.command.as_str() {
+        "decode" => {
+            let joined: Vec<u8> = shards[..data].iter().flatten().flatten().copied().collect();
+            if joined.len() < 8 {
+                eprintln!("{}: {}", files[0].display(), Errors::InvalidCodeword);
+                return Ok(Status::Unrecoverable);
+            }
+
+            let len = u64::from_be_bytes(joined[..8].try_into().unwrap()) as usize;
+            if len > joined.len() - 8 {
+                eprintln!("{}: {}", files[0].display(), Errors::InvalidCodeword);
+                return Ok(Status::Unrecoverable);
+            }
+            fs::write(&files[1], &joined[8..8 + len])?;
+        }
+        "repair" => {
+            for &i in rebuilt.iter() {
+                fs::write(shard_path(&files[0], i), shards[i].as_ref().unwrap())?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(if rebuilt.is_empty() {
+        Status::Clean
+    } else {
+        Status::Repaired
+    })
+}
+
+fn info(options: &Options) {
+    let (name, poly, symbol) = match options.field {
+        Field::GF256 => ("GF(2^8)", GF2k::PRIME_POLY, GF2k::SYMBOL_SIZE),
+        Field::GF65536 => ("GF(2^16)", GF2_16::PRIME_POLY, GF2_16::SYMBOL_SIZE),
+    };
+
+    println!("field: {name}, prime polynomial {poly:#x}, {symbol} byte symbols");
+    match options.shards {
+        Some((data, parity)) => {
+            println!("shards: {data} data + {parity} parity, any {data} recover the file")
+        }
+        None => {
+            let t = (options.n - options.k.min(options.n)) / 2;
+            println!(
+                "code: RS({}, {}), corrects {t} symbol errors per codeword",
+                options.n, options.k
+            );
+            println!("interleaving depth: {}", options.interleave);
+        }
+    }
+}
+
+fn run(options: &Options) -> io::Result<Status> {
+    if options.command == "info" {
+        info(options);
+        return Ok(Status::Clean);
+    }
+
+    match (options.shards, options.field) {
+        (Some((data, parity)), Field::GF256) => run_shards::<GF2k>(options, data, parity),
+        (Some((data, parity)), Field::GF65536) => run_shards::<GF2_16>(options, data, parity),
+        (None, Field::GF256) => run_stream(options, FieldElement::<GF2k>::new(2)),
+        (None, Field::GF65536) => run_stream(options, FieldElement::<GF2_16>::new(2)),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    match run(&options) {
+        Ok(status) => ExitCode::from(status.exit_code()),
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shittyrs-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn run_line(line: &str) -> Status {
+        run(&parse_args(&args(line)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let options = parse_args(&args("encode -n 32 -k 24 --field gf65536 -i 4 a b")).unwrap();
+        assert_eq!((options.n, options.k, options.interleave), (32, 24, 4));
+        assert_eq!(options.field, Field::GF65536);
+        assert_eq!(options.files, vec![PathBuf::from("a"), PathBuf::from("b")]);
+
+        let options = parse_args(&args("verify --shards 10+4 a")).unwrap();
+        assert_eq!(options.shards, Some((10, 4)));
+
+        assert!(parse_args(&args("verify a b")).is_err());
+        assert!(parse_args(&args("encode -n x a b")).is_err());
+        assert!(parse_args(&args("scrub a")).is_err());
+        assert!(parse_args(&args("")).is_err());
+    }
+
+    #[test]
+    fn stream_file() {
+        let dir = temp_dir("stream");
+        let (input, output, decoded) = (dir.join("in"), dir.join("out"), dir.join("dec"));
+        let data: Vec<u8> = (0..5000).map(|x| (x * 7 + x / 300) as u8).collect();
+        fs::write(&input, &data).unwrap();
+
+        let opts = "-n 64 -k 48 -i 2";
+        let (i, o, d) = (input.display(), output.display(), decoded.display());
+        assert_eq!(run_line(&format!("encode {opts} {i} {o}")), Status::Clean);
+        assert_eq!(run_line(&format!("verify {opts} {o}")), Status::Clean);
+
+        let mut encoded = fs::read(&output).unwrap();
+        encoded[100] ^= 0xff;
+        encoded[3000] ^= 0x01;
+        fs::write(&output, &encoded).unwrap();
+        assert_eq!(run_line(&format!("verify {opts} {o}")), Status::Repaired);
+        assert_eq!(run_line(&format!("repair {opts} {o}")), Status::Repaired);
+        assert_eq!(run_line(&format!("verify {opts} {o}")), Status::Clean);
+        assert_eq!(run_line(&format!("decode {opts} {o} {d}")), Status::Clean);
+        assert_eq!(fs::read(&decoded).unwrap(), data);
+
+        let mut encoded = fs::read(&output).unwrap();
+        for byte in encoded[128..192].iter_mut() {
+            *byte = !*byte;
+        }
+        fs::write(&output, &encoded).unwrap();
+        assert_eq!(
+            run_line(&format!("repair {opts} {o}")),
+            Status::Unrecoverable
+        );
+        assert_eq!(fs::read(&output).unwrap(), encoded);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn repair_keeps_input() {
+        let dir = temp_dir("repair-name");
+        let (input, output) = (dir.join("in"), dir.join("data.repair"));
+        let data: Vec<u8> = (0..700).map(|x| (x * 3) as u8).collect();
+        fs::write(&input, &data).unwrap();
+
+        let (i, o) = (input.display(), output.display());
+        assert_eq!(
+            run_line(&format!("encode -n 64 -k 48 {i} {o}")),
+            Status::Clean
+        );
+
+        let mut encoded = fs::read(&output).unwrap();
+        encoded[10] ^= 0x55;
+        fs::write(&output, &encoded).unwrap();
+        assert_eq!(
+            run_line(&format!("repair -n 64 -k 48 {o}")),
+            Status::Repaired
+        );
+        assert_eq!(run_line(&format!("verify -n 64 -k 48 {o}")), Status::Clean);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn shard_files() {
+        let dir = temp_dir("shards");
+        let (input, prefix, decoded) = (dir.join("in"), dir.join("out"), dir.join("dec"));
+        let data: Vec<u8> = (0..1001).map(|x| (x * 13) as u8).collect();
+        fs::write(&input, &data).unwrap();
+
+        let (i, p, d) = (input.display(), prefix.display(), decoded.display());
+        assert_eq!(
+            run_line(&format!("encode --shards 4+2 {i} {p}")),
+            Status::Clean
+        );
+        assert_eq!(run_line(&format!("verify --shards 4+2 {p}")), Status::Clean);
+
+        fs::remove_file(shard_path(&prefix, 1)).unwrap();
+        fs::remove_file(shard_path(&prefix, 4)).unwrap();
+        assert_eq!(
+            run_line(&format!("repair --shards 4+2 {p}")),
+            Status::Repaired
+        );
+
+        // a single corrupt shard is found by elimination
+        let mut shard = fs::read(shard_path(&prefix, 2)).unwrap();
+        shard[0] ^= 0x01;
+        fs::write(shard_path(&prefix, 2), &shard).unwrap();
+        assert_eq!(
+            run_line(&format!("verify --shards 4+2 {p}")),
+            Status::Repaired
+        );
+        assert_eq!(
+            run_line(&format!("decode --shards 4+2 {p} {d}")),
+            Status::Repaired
+        );
+        assert_eq!(fs::read(&decoded).unwrap(), data);
+
+        for i in 0..3 {
+            fs::remove_file(shard_path(&prefix, i)).unwrap();
+        }
+        assert_eq!(
+            run_line(&format!("verify --shards 4+2 {p}")),
+            Status::Unrecoverable
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_and_corrupt_shard() {
+        let dir = temp_dir("shards_corrupt");
+        let (input, prefix, decoded) = (dir.join("in"), dir.join("out"), dir.join("dec"));
+        let data: Vec<u8> = (0..1001).map(|x| (x * 7) as u8).collect();
+        fs::write(&input, &data).unwrap();
+
+        let damage = |shards: &str| {
+            let (i, p) = (input.display(), prefix.display());
+            assert_eq!(
+                run_line(&format!("encode --shards {shards} {i} {p}")),
+                Status::Clean
+            );
+            fs::remove_file(shard_path(&prefix, 1)).unwrap();
+            let mut shard = fs::read(shard_path(&prefix, 2)).unwrap();
+            shard[10] ^= 0x40;
+            fs::write(shard_path(&prefix, 2), &shard).unwrap();
+        };
+        let (p, d) = (prefix.display(), decoded.display());
+
+        // two spare parity shards find the corrupt one
+        damage("4+3");
+        assert_eq!(
+            run_line(&format!("decode --shards 4+3 {p} {d}")),
+            Status::Repaired
+        );
+        assert_eq!(fs::read(&decoded).unwrap(), data);
+
+        // one is only enough to notice it
+        fs::remove_file(&decoded).unwrap();
+        damage("4+2");
+        assert_eq!(
+            run_line(&format!("decode --shards 4+2 {p} {d}")),
+            Status::Unrecoverable
+        );
+        assert!(!decoded.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn shard_files_gf65536() {
+        let dir = temp_dir("shards16");
+        let (input, prefix, decoded) = (dir.join("in"), dir.join("out"), dir.join("dec"));
+        let data: Vec<u8> = (0..333).map(|x| (x * 5) as u8).collect();
+        fs::write(&input, &data).unwrap();
+
+        let opts = "--shards 4+2 --field gf65536";
+        let (i, p, d) = (input.display(), prefix.display(), decoded.display());
+        assert_eq!(run_line(&format!("encode {opts} {i} {p}")), Status::Clean);
+        assert_eq!(fs::read(shard_path(&prefix, 0)).unwrap().len() % 2, 0);
+
+        fs::remove_file(shard_path(&prefix, 3)).unwrap();
+        assert_eq!(
+            run_line(&format!("decode {opts} {p} {d}")),
+            Status::Repaired
+        );
+        assert_eq!(fs::read(&decoded).unwrap(), data);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn short_shards() {
+        let dir = temp_dir("short");
+        let (input, prefix, decoded) = (dir.join("in"), dir.join("out"), dir.join("dec"));
+        fs::write(&input, [1, 2]).unwrap();
+
+        let (i, p, d) = (input.display(), prefix.display(), decoded.display());
+        assert_eq!(
+            run_line(&format!("encode --shards 4+2 {i} {p}")),
+            Status::Clean
+        );
+        for i in 0..6 {
+            let shard = fs::read(shard_path(&prefix, i)).unwrap();
+            fs::write(shard_path(&prefix, i), &shard[..1]).unwrap();
+        }
+        assert_eq!(
+            run_line(&format!("decode --shards 4+2 {p} {d}")),
+            Status::Unrecoverable
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
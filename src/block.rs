@@ -0,0 +1,413 @@
+//! Encoding of messages of any length as a sequence of codewords.
+
+use std::borrow::Cow;
+
+use crate::errors::Errors;
+use crate::ff::Fp;
+use crate::reedsolomon::{Decoded, Encoding, ReedSolomon};
+
+/// Bytes of the length header in front of the message
+pub const HEADER_SIZE: usize = 8;
+
+/// Splits messages of any length into blocks of `k` symbols and encodes each of them into
+/// a codeword of `n` symbols.
+///
+/// The message is prefixed with its length as a big-endian `u64` and zero padded to a
+/// whole number of blocks, so the header is protected like the rest of the data and the
+/// padding can be stripped on decode. With an interleaving depth `I > 1`, groups of `I`
+/// codewords are interleaved symbol by symbol, turning a burst of up to `I·t` symbols into
+/// at most `t` errors per codeword.
+///
+/// Over fields smaller than `2^(8·SYMBOL_SIZE)`, like the prime fields, not every byte
+/// string is a message, so each symbol carries `SYMBOL_SIZE - 1` bytes of data behind a
+/// zero byte.
+#[derive(Debug)]
+pub struct BlockCodec<F: Fp> {
+    rs: ReedSolomon<F>,
+    interleave: usize,
+    /// Data bytes per symbol
+    symbol_bytes: usize,
+}
+
+impl<F: Fp> BlockCodec<F> {
+    /// # Panics
+    ///
+    /// If `F` does not hold every `SYMBOL_SIZE - 1` byte value while not holding every
+    /// `SYMBOL_SIZE` byte value, which no field of this crate does.
+    pub fn new(rs: ReedSolomon<F>) -> Self {
+        let symbol_bytes = if F::is_canonical(&vec![0xff; F::SYMBOL_SIZE]) {
+            F::SYMBOL_SIZE
+        } else {
+            let mut packed = vec![0xff; F::SYMBOL_SIZE];
+            packed[0] = 0;
+            assert!(
+                F::SYMBOL_SIZE > 1 && F::is_canonical(&packed),
+                "field too small to pack bytes into"
+            );
+            F::SYMBOL_SIZE - 1
+        };
+
+        BlockCodec {
+            rs,
+            interleave: 1,
+            symbol_bytes,
+        }
+    }
+
+    /// Interleaves groups of `depth` codewords.
+    pub fn interleaved(mut self, depth: usize) -> Result<Self, Errors> {
+        if depth == 0 {
+            return Err(Errors::InvalidInterleave);
+        }
+
+        self.interleave = depth;
+        Ok(self)
+    }
+
+    pub fn code(&self) -> &ReedSolomon<F> {
+        &self.rs
+    }
+
+    pub fn interleave(&self) -> usize {
+        self.interleave
+    }
+
+    /// Message bytes of a block
+    pub fn block_size(&self) -> usize {
+        self.rs.message_size() * self.symbol_bytes
+    }
+
+    /// Bytes of an encoded block
+    pub fn codeword_size(&self) -> usize {
+        self.rs.total_size() * F::SYMBOL_SIZE
+    }
+
+    /// Message bytes of an interleaving group
+    pub fn group_size(&self) -> usize {
+        self.block_size() * self.interleave
+    }
+
+    /// Bytes of an encoded interleaving group
+    pub fn encoded_group_size(&self) -> usize {
+        self.codeword_size() * self.interleave
+    }
+
+    /// Number of blocks needed for a message of `len` bytes, including the header and
+    /// the padding up to a whole interleaving group.
+    pub fn block_count(&self, len: usize) -> usize {
+        let blocks = (HEADER_SIZE + len).div_ceil(self.block_size());
+        blocks.next_multiple_of(self.interleave)
+    }
+
+    /// Bytes of the encoding of a message of `len` bytes
+    pub fn encoded_size(&self, len: usize) -> usize {
+        self.block_count(len) * self.codeword_size()
+    }
+
+    /// Encodes `message` into [`BlockCodec::encoded_size`] bytes.
+    pub fn encode(&self, message: &[u8]) -> Result<Vec<u8>, Errors> {
+        let mut data = Vec::with_capacity(self.block_count(message.len()) * self.block_size());
+        data.extend_from_slice(&(message.len() as u64).to_be_bytes());
+        data.extend_from_slice(message);
+        data.resize(self.block_count(message.len()) * self.block_size(), 0);
+
+        let mut encoded = Vec::with_capacity(self.encoded_size(message.len()));
+        for group in data.chunks(self.group_size()) {
+            encoded.extend_from_slice(&self.encode_group(group)?);
+        }
+
+        Ok(encoded)
+    }
+
+    /// Encodes exactly [`BlockCodec::group_size`] bytes into an interleaving group, without
+    /// any header or padding.
+    pub fn encode_group(&self, data: &[u8]) -> Result<Vec<u8>, Errors> {
+        if data.len() != self.group_size() {
+            return Err(Errors::InvalidMessage);
+        }
+
+        let data = self.unpack(data);
+        if self.interleave > 1 && self.rs.encoding() == Encoding::Systematic {
+            return self.rs.encode_interleaved(&data, self.interleave);
+        }
+
+        let codewords = data
+            .chunks(self.rs.message_size() * F::SYMBOL_SIZE)
+            .map(|block| self.rs.encode(block))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(self.interleave_group(&codewords))
+    }
+
+    /// Decodes an interleaving group produced by [`BlockCodec::encode_group`] and returns
+    /// its data, along with the positions of the corrected symbols within the group.
+    pub fn decode_group(&self, group: &[u8]) -> Result<Decoded, Errors> {
+        if group.len() != self.encoded_group_size() {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        let mut data = Vec::with_capacity(self.group_size());
+        let mut errors = vec![];
+        for (i, codeword) in self.deinterleave_group(group).iter().enumerate() {
+            let decoded = self.rs.decode(codeword)?;
+
+            data.extend_from_slice(&self.pack(&decoded.message)?);
+            // symbols the field would reduce come back as erasures, corrected all the same
+            let corrected = decoded.errors.into_iter().chain(decoded.erasures);
+            errors.extend(corrected.map(|m| m * self.interleave + i));
+        }
+        errors.sort_unstable();
+
+        Ok(Decoded {
+            message: data,
+            errors,
+            erasures: vec![],
+        })
+    }
+
+    /// Returns the data of an interleaving group without correcting it, which only
+    /// systematic codes allow.
+    pub fn extract_group(&self, group: &[u8]) -> Result<Vec<u8>, Errors> {
+        if self.rs.encoding() != Encoding::Systematic {
+            return Err(Errors::NotSystematic);
+        }
+
+        if group.len() != self.encoded_group_size() {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        let message_size = self.rs.message_size() * F::SYMBOL_SIZE;
+        let mut data = Vec::with_capacity(self.group_size());
+        for codeword in self.deinterleave_group(group) {
+            data.extend_from_slice(&self.pack(&codeword[..message_size])?);
+        }
+
+        Ok(data)
+    }
+
+    /// Spreads `data` over whole symbols, each behind zero bytes if they carry less.
+    fn unpack<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.symbol_bytes == F::SYMBOL_SIZE {
+            return Cow::Borrowed(data);
+        }
+
+        let pad = F::SYMBOL_SIZE - self.symbol_bytes;
+        let mut symbols = Vec::with_capacity(data.len() / self.symbol_bytes * F::SYMBOL_SIZE);
+        for chunk in data.chunks(self.symbol_bytes) {
+            symbols.resize(symbols.len() + pad, 0);
+            symbols.extend_from_slice(chunk);
+        }
+
+        Cow::Owned(symbols)
+    }
+
+    /// Inverse of [`BlockCodec::unpack`], failing if a symbol is too large to come from
+    /// it, which only a miscorrected codeword has.
+    fn pack(&self, symbols: &[u8]) -> Result<Vec<u8>, Errors> {
+        if self.symbol_bytes == F::SYMBOL_SIZE {
+            return Ok(symbols.to_vec());
+        }
+
+        let pad = F::SYMBOL_SIZE - self.symbol_bytes;
+        let mut data = Vec::with_capacity(symbols.len() / F::SYMBOL_SIZE * self.symbol_bytes);
+        for symbol in symbols.chunks(F::SYMBOL_SIZE) {
+            if symbol[..pad].iter().any(|&b| b != 0) {
+                return Err(Errors::TooManyErrors);
+            }
+            data.extend_from_slice(&symbol[pad..]);
+        }
+
+        Ok(data)
+    }
+
+    /// Corrects every codeword of `encoded` and returns the original message, along with
+    /// the positions of the corrected symbols within `encoded`.
+    pub fn decode(&self, encoded: &[u8]) -> Result<Decoded, Errors> {
+        let group_size = self.encoded_group_size();
+        if encoded.is_empty() || !encoded.len().is_multiple_of(group_size) {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        let mut data = Vec::with_capacity(encoded.len() / group_size * self.group_size());
+        let mut errors = vec![];
+        for (g, group) in encoded.chunks(group_size).enumerate() {
+            let offset = g * self.rs.total_size() * self.interleave;
+            let decoded = self.decode_group(group)?;
+
+            data.extend_from_slice(&decoded.message);
+            errors.extend(decoded.errors.into_iter().map(|j| offset + j));
+        }
+
+        if data.len() < HEADER_SIZE {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        let (header, message) = data.split_at(HEADER_SIZE);
+        let len = u64::from_be_bytes(header.try_into().unwrap()) as usize;
+        if len > message.len() {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        Ok(Decoded {
+            message: message[..len].to_vec(),
+            errors,
+            erasures: vec![],
+        })
+    }
+
+    /// Interleaves the symbols of `codewords`, the first symbol of every codeword first.
+    fn interleave_group(&self, codewords: &[Vec<u8>]) -> Vec<u8> {
+        let mut group = vec![0; codewords.len() * self.codeword_size()];
+
+        for (i, codeword) in codewords.iter().enumerate() {
+            for (m, symbol) in codeword.chunks(F::SYMBOL_SIZE).enumerate() {
+                let start = (m * codewords.len() + i) * F::SYMBOL_SIZE;
+                group[start..start + F::SYMBOL_SIZE].copy_from_slice(symbol);
+            }
+        }
+
+        group
+    }
+
+    /// Inverse of [`BlockCodec::interleave_group`].
+    fn deinterleave_group(&self, group: &[u8]) -> Vec<Vec<u8>> {
+        let symbols: Vec<&[u8]> = group.chunks(F::SYMBOL_SIZE).collect();
+
+        (0..self.interleave)
+            .map(|i| {
+                symbols
+                    .iter()
+                    .skip(i)
+                    .step_by(self.interleave)
+                    .flat_map(|s| s.iter().copied())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{gf2_16::GF2_16, gf2_8::GF2k, prime::BabyBear};
+    use crate::ff::FieldElement;
+
+    fn codec() -> BlockCodec<GF2k> {
+        BlockCodec::new(ReedSolomon::new(32, 24, FieldElement::new(2)).unwrap())
+    }
+
+    #[test]
+    fn round_trip() {
+        let codec = codec();
+
+        for len in [0, 1, 15, 16, 17, 100, 1000] {
+            let message: Vec<u8> = (0..len).map(|x| (x * 7) as u8).collect();
+            let encoded = codec.encode(&message).unwrap();
+
+            assert_eq!(encoded.len(), codec.encoded_size(len));
+            assert_eq!(encoded.len(), (len + 8).div_ceil(24) * 32);
+            assert_eq!(codec.decode(&encoded).unwrap().message, message);
+        }
+
+        assert_eq!(codec.decode(&[0; 31]), Err(Errors::InvalidCodeword));
+
+        // a single codeword carries fewer message bytes than the length header
+        let short = BlockCodec::new(ReedSolomon::<GF2k>::new(8, 4, FieldElement::new(2)).unwrap());
+        assert_eq!(short.decode(&[0; 8]), Err(Errors::InvalidCodeword));
+    }
+
+    #[test]
+    fn errors() {
+        let codec = codec();
+        let message = vec![0xab; 100];
+        let mut encoded = codec.encode(&message).unwrap();
+
+        for j in [0, 5, 33, 64, 65, 66, 67, 159] {
+            encoded[j] ^= 0x01;
+        }
+
+        let decoded = codec.decode(&encoded).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, vec![0, 5, 33, 64, 65, 66, 67, 159]);
+
+        for byte in encoded[96..101].iter_mut() {
+            *byte ^= 0x01;
+        }
+        assert_eq!(codec.decode(&encoded), Err(Errors::TooManyErrors));
+    }
+
+    #[test]
+    fn interleaved_burst() {
+        assert!(matches!(
+            codec().interleaved(0),
+            Err(Errors::InvalidInterleave)
+        ));
+
+        let codec = codec().interleaved(4).unwrap();
+        let message: Vec<u8> = (0..200).map(|x| x as u8).collect();
+        let mut encoded = codec.encode(&message).unwrap();
+        assert_eq!(encoded.len(), 12 * 32);
+
+        // a burst of 16 bytes is 4 errors in each of 4 codewords
+        let burst: Vec<usize> = (130..146).collect();
+        for &j in burst.iter() {
+            encoded[j] = !encoded[j];
+        }
+
+        let decoded = codec.decode(&encoded).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, burst);
+
+        let clean = codec.encode(&message).unwrap();
+        assert_eq!(
+            codec.extract_group(&clean[..128]).unwrap(),
+            codec.decode_group(&encoded[..128]).unwrap().message
+        );
+    }
+
+    #[test]
+    fn wide_symbols() {
+        let rs = ReedSolomon::<GF2_16>::new(300, 280, FieldElement::new(2)).unwrap();
+        let codec = BlockCodec::new(rs).interleaved(2).unwrap();
+        let message: Vec<u8> = (0..2001).map(|x| (x % 251) as u8).collect();
+        let mut encoded = codec.encode(&message).unwrap();
+
+        encoded[1] ^= 0xff;
+        encoded[2] ^= 0xff;
+        let decoded = codec.decode(&encoded).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, vec![0, 1]);
+    }
+
+    #[test]
+    fn prime_field() {
+        let rs = ReedSolomon::new(40, 30, FieldElement::<BabyBear>::new(31)).unwrap();
+        let codec = BlockCodec::new(rs).interleaved(3).unwrap();
+        assert_eq!(codec.block_size(), 30 * 3);
+
+        // every byte value, including those that would make symbols above the modulus
+        let message: Vec<u8> = (0..1000).map(|x| (255 - x % 256) as u8).collect();
+        let mut encoded = codec.encode(&message).unwrap();
+        assert_eq!(encoded.len(), codec.encoded_size(message.len()));
+
+        encoded[1] ^= 0x40;
+        encoded[200] ^= 0x10;
+        let decoded = codec.decode(&encoded).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, vec![0, 50]);
+
+        // above the modulus
+        let mut damaged = encoded.clone();
+        damaged[4..8].fill(0xff);
+        let decoded = codec.decode(&damaged).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, vec![0, 1, 50]);
+
+        let clean = codec.encode(&message).unwrap();
+        let group = codec.encoded_group_size();
+        assert_eq!(
+            codec.extract_group(&clean[..group]).unwrap(),
+            codec.decode_group(&encoded[..group]).unwrap().message
+        );
+    }
+}
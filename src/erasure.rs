@@ -0,0 +1,592 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use num_traits::Zero;
+
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::ff::Fp;
+use crate::matrix::Matrix;
+
+/// Shard level erasure coder, splitting a blob across `k` data shards and `m` parity
+/// shards, any `k` of which are enough to rebuild the rest.
+///
+/// Every byte column of the shards is treated as a codeword of a systematic code whose
+/// `(k+m) x k` encoding matrix has the identity on top and the parity rows below.
+#[derive(Debug)]
+pub struct ShardEncoder<F: Fp> {
+    data_shards: usize,
+    parity_shards: usize,
+    matrix: Matrix<F>,
+    cache: Option<Mutex<DecodeCache<F>>>,
+}
+
+/// Hit and miss counters of the decode matrix cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Decode matrices currently cached
+    pub entries: usize,
+}
+
+/// Least recently used cache of decode matrices, keyed by the bitmap of the shards they
+/// are inverted from
+#[derive(Debug)]
+struct DecodeCache<F: Fp> {
+    capacity: usize,
+    /// Most recently used first
+    entries: VecDeque<(Vec<u64>, Matrix<F>)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<F: Fp> DecodeCache<F> {
+    fn get(&mut self, key: &[u64]) -> Option<Matrix<F>> {
+        match self.entries.iter().position(|(k, _)| k == key) {
+            Some(i) => {
+                self.hits += 1;
+                let entry = self.entries.remove(i).unwrap();
+                let matrix = entry.1.clone();
+                self.entries.push_front(entry);
+                Some(matrix)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Adds the matrix for `key` as the most recently used, replacing any it already has.
+    fn insert(&mut self, key: Vec<u64>, matrix: Matrix<F>) {
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.truncate(self.capacity - 1);
+        self.entries.push_front((key, matrix));
+    }
+}
+
+impl<F: Fp> ShardEncoder<F> {
+    /// Creates an encoder from a Vandermonde matrix, made systematic by multiplying with
+    /// the inverse of its top `k x k` square.
+    pub fn new(data_shards: usize, parity_shards: usize) -> Result<Self, Errors> {
+        Self::validate(data_shards, parity_shards)?;
+
+        let xs: Vec<FE<F>> = (0..data_shards + parity_shards)
+            .map(|r| FE::from_u64(r as u64))
+            .collect();
+        let vandermonde = Matrix::vandermonde(&xs, data_shards);
+
+        let top: Vec<usize> = (0..data_shards).collect();
+        let top = vandermonde.select_rows(&top).inverse()?;
+
+        Ok(ShardEncoder {
+            data_shards,
+            parity_shards,
+            matrix: vandermonde.multiply(&top)?,
+            cache: None,
+        })
+    }
+
+    /// Creates an encoder whose parity rows form a Cauchy matrix `1 / (x_i - y_j)` with
+    /// `x_i = k + i` and `y_j = j`, so every square submatrix is invertible by construction.
+    pub fn new_cauchy(data_shards: usize, parity_shards: usize) -> Result<Self, Errors> {
+        Self::validate(data_shards, parity_shards)?;
+
+        let xs: Vec<FE<F>> = (0..parity_shards)
+            .map(|i| FE::from_u64((data_shards + i) as u64))
+            .collect();
+        let ys: Vec<FE<F>> = (0..data_shards).map(|j| FE::from_u64(j as u64)).collect();
+        let cauchy = Matrix::cauchy(&xs, &ys)?;
+
+        Ok(ShardEncoder {
+            data_shards,
+            parity_shards,
+            matrix: Matrix::identity(data_shards).stack(&cauchy)?,
+            cache: None,
+        })
+    }
+
+    /// Keeps the decode matrices of the last `capacity` erasure patterns, sparing the
+    /// inversion when the same shards go missing again. A capacity of 0 disables the
+    /// cache.
+    pub fn with_decode_cache(mut self, capacity: usize) -> Self {
+        self.cache = (capacity > 0).then(|| {
+            Mutex::new(DecodeCache {
+                capacity,
+                entries: VecDeque::with_capacity(capacity),
+                hits: 0,
+                misses: 0,
+            })
+        });
+        self
+    }
+
+    /// Counters of the decode matrix cache, all zero without one
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache
+            .as_ref()
+            .map_or_else(CacheStats::default, |cache| {
+                let cache = cache.lock().unwrap();
+                CacheStats {
+                    hits: cache.hits,
+                    misses: cache.misses,
+                    entries: cache.entries.len(),
+                }
+            })
+    }
+
+    /// Inverse of the encoding rows of the first `k` present shards, mapping them back
+    /// onto the data shards.
+    fn decode_matrix(&self, present: &[usize]) -> Result<Matrix<F>, Errors> {
+        let invert = || {
+            self.matrix
+                .select_rows(&present[..self.data_shards])
+                .inverse()
+        };
+
+        let Some(cache) = &self.cache else {
+            return invert();
+        };
+
+        // only the shards the matrix is inverted from tell patterns apart
+        let mut key = vec![0u64; self.total_shards().div_ceil(64)];
+        for &i in &present[..self.data_shards] {
+            key[i / 64] |= 1 << (i % 64);
+        }
+
+        if let Some(decode) = cache.lock().unwrap().get(&key) {
+            return Ok(decode);
+        }
+
+        // invert without holding the lock, other patterns need not wait
+        let decode = invert()?;
+        cache.lock().unwrap().insert(key, decode.clone());
+
+        Ok(decode)
+    }
+
+    fn validate(data_shards: usize, parity_shards: usize) -> Result<(), Errors> {
+        if data_shards == 0 {
+            return Err(Errors::TooFewNumbers);
+        }
+
+        if parity_shards == 0 {
+            return Err(Errors::TooFewParity);
+        }
+
+        if data_shards + parity_shards > F::ORDER {
+            return Err(Errors::TooManyNumbers);
+        }
+
+        Ok(())
+    }
+
+    pub fn data_shards(&self) -> usize {
+        self.data_shards
+    }
+
+    pub fn parity_shards(&self) -> usize {
+        self.parity_shards
+    }
+
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+
+    /// Rows of the encoding matrix producing the parity shards
+    fn parity_rows(&self) -> Matrix<F> {
+        let rows: Vec<usize> = (self.data_shards..self.total_shards()).collect();
+        self.matrix.select_rows(&rows)
+    }
+
+    /// Computes `rows * inputs` symbol column by symbol column into `outputs`.
+    fn code_shards(rows: &Matrix<F>, inputs: &[&[u8]], outputs: &mut [&mut Vec<u8>]) {
+        for (r, output) in outputs.iter_mut().enumerate() {
+            output.clear();
+            output.resize(inputs[0].len(), 0);
+
+            for (coeff, input) in rows.row(r).iter().zip(inputs.iter()) {
+                if coeff.is_zero() {
+                    continue;
+                }
+
+                F::mul_add_slice(coeff.value(), input, output);
+            }
+        }
+    }
+
+    fn check_sizes<'a, I: Iterator<Item = &'a [u8]>>(mut shards: I) -> Result<usize, Errors> {
+        let len = match shards.next() {
+            Some(shard) => shard.len(),
+            None => return Err(Errors::InvalidShardCount),
+        };
+
+        if len == 0 || !len.is_multiple_of(F::SYMBOL_SIZE) || shards.any(|shard| shard.len() != len)
+        {
+            return Err(Errors::InvalidShardSize);
+        }
+
+        Ok(len)
+    }
+
+    /// Rejects data holding symbols that `F` would reduce, which could not round-trip.
+    fn check_symbols<'a, I: Iterator<Item = &'a [u8]>>(mut shards: I) -> Result<(), Errors> {
+        if shards.any(|shard| !shard.chunks(F::SYMBOL_SIZE).all(F::is_canonical)) {
+            return Err(Errors::InvalidMessage);
+        }
+
+        Ok(())
+    }
+
+    /// Fills the parity shards of `shards` from its first `k` data shards.
+    pub fn encode(&self, shards: &mut [Vec<u8>]) -> Result<(), Errors> {
+        if shards.len() != self.total_shards() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let (data, parity) = shards.split_at_mut(self.data_shards);
+        Self::check_sizes(data.iter().map(|s| s.as_slice()))?;
+        Self::check_symbols(data.iter().map(|s| s.as_slice()))?;
+
+        let inputs: Vec<&[u8]> = data.iter().map(|s| s.as_slice()).collect();
+        let mut outputs: Vec<&mut Vec<u8>> = parity.iter_mut().collect();
+        Self::code_shards(&self.parity_rows(), &inputs, &mut outputs);
+
+        Ok(())
+    }
+
+    /// Updates the `m` parity shards for data shard `shard_index` changing from `old` to
+    /// `new`, adding the delta times the matching column of the encoding matrix instead
+    /// of re-encoding the stripe.
+    pub fn update_parity(
+        &self,
+        shard_index: usize,
+        old: &[u8],
+        new: &[u8],
+        parity: &mut [Vec<u8>],
+    ) -> Result<(), Errors> {
+        if shard_index >= self.data_shards {
+            return Err(Errors::InvalidShardIndex);
+        }
+
+        if parity.len() != self.parity_shards {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        Self::check_sizes(
+            [old, new]
+                .into_iter()
+                .chain(parity.iter().map(|p| p.as_slice())),
+        )?;
+        Self::check_symbols([old, new].into_iter())?;
+
+        let delta: Vec<FE<F>> = FE::from_byte_slice(old)
+            .unwrap()
+            .into_iter()
+            .zip(FE::from_byte_slice(new).unwrap())
+            .map(|(o, n)| n - o)
+            .collect();
+        let delta = FE::to_byte_vec(&delta);
+
+        for (r, shard) in parity.iter_mut().enumerate() {
+            let coeff = self.matrix[(self.data_shards + r, shard_index)];
+            F::mul_add_slice(coeff.value(), &delta, shard);
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the parity shards match the data shards.
+    pub fn verify(&self, shards: &[Vec<u8>]) -> Result<bool, Errors> {
+        if shards.len() != self.total_shards() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        Self::check_sizes(shards.iter().map(|s| s.as_slice()))?;
+
+        let inputs: Vec<&[u8]> = shards[..self.data_shards]
+            .iter()
+            .map(|s| s.as_slice())
+            .collect();
+        let mut parity = vec![vec![]; self.parity_shards];
+        let mut outputs: Vec<&mut Vec<u8>> = parity.iter_mut().collect();
+        Self::code_shards(&self.parity_rows(), &inputs, &mut outputs);
+
+        Ok(parity.as_slice() == &shards[self.data_shards..])
+    }
+
+    /// Rebuilds every missing (`None`) shard from any `k` of the present ones.
+    pub fn reconstruct(&self, shards: &mut [Option<Vec<u8>>]) -> Result<(), Errors> {
+        if shards.len() != self.total_shards() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let present: Vec<usize> = (0..shards.len()).filter(|&i| shards[i].is_some()).collect();
+
+        if present.len() < self.data_shards {
+            return Err(Errors::TooFewShards);
+        }
+
+        Self::check_sizes(shards.iter().flatten().map(|s| s.as_slice()))?;
+
+        if present.len() == shards.len() {
+            return Ok(());
+        }
+
+        let decode = self.decode_matrix(&present)?;
+
+        let missing_data: Vec<usize> = (0..self.data_shards)
+            .filter(|&i| shards[i].is_none())
+            .collect();
+
+        if !missing_data.is_empty() {
+            let inputs: Vec<&[u8]> = present[..self.data_shards]
+                .iter()
+                .map(|&i| shards[i].as_deref().unwrap())
+                .collect();
+            let decode_rows = decode.select_rows(&missing_data);
+            let mut rebuilt = vec![vec![]; missing_data.len()];
+            let mut outputs: Vec<&mut Vec<u8>> = rebuilt.iter_mut().collect();
+            Self::code_shards(&decode_rows, &inputs, &mut outputs);
+
+            for (i, shard) in missing_data.into_iter().zip(rebuilt) {
+                shards[i] = Some(shard);
+            }
+        }
+
+        let missing_parity: Vec<usize> = (self.data_shards..shards.len())
+            .filter(|&i| shards[i].is_none())
+            .collect();
+
+        if !missing_parity.is_empty() {
+            let inputs: Vec<&[u8]> = shards[..self.data_shards]
+                .iter()
+                .map(|s| s.as_deref().unwrap())
+                .collect();
+            let parity_rows = self.matrix.select_rows(&missing_parity);
+            let mut rebuilt = vec![vec![]; missing_parity.len()];
+            let mut outputs: Vec<&mut Vec<u8>> = rebuilt.iter_mut().collect();
+            Self::code_shards(&parity_rows, &inputs, &mut outputs);
+
+            for (i, shard) in missing_parity.into_iter().zip(rebuilt) {
+                shards[i] = Some(shard);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{CacheStats, DecodeCache, ShardEncoder};
+    use crate::backend::{gf2_16::GF2_16, gf2_8::GF2k, prime::BabyBear};
+    use crate::errors::Errors;
+    use crate::matrix::Matrix;
+
+    fn shards(data: usize, parity: usize, len: usize) -> Vec<Vec<u8>> {
+        let mut shards: Vec<Vec<u8>> = (0..data)
+            .map(|i| (0..len).map(|j| (i * 31 + j * 7) as u8).collect())
+            .collect();
+        shards.resize(data + parity, vec![]);
+        shards
+    }
+
+    #[test]
+    fn encode_verify() {
+        let encoder = ShardEncoder::<GF2k>::new(5, 3).unwrap();
+        let mut shards = shards(5, 3, 64);
+
+        encoder.encode(&mut shards).unwrap();
+        assert!(encoder.verify(&shards).unwrap());
+
+        shards[6][10] ^= 1;
+        assert!(!encoder.verify(&shards).unwrap());
+    }
+
+    #[test]
+    fn update_parity() {
+        let encoder = ShardEncoder::<GF2_16>::new_cauchy(6, 3).unwrap();
+        let mut shards = shards(6, 3, 32);
+        encoder.encode(&mut shards).unwrap();
+
+        let new: Vec<u8> = (0..32).map(|j| j * 5 + 1).collect();
+        let mut parity = shards[6..].to_vec();
+        encoder
+            .update_parity(2, &shards[2], &new, &mut parity)
+            .unwrap();
+
+        shards[2] = new;
+        encoder.encode(&mut shards).unwrap();
+        assert_eq!(parity, &shards[6..]);
+
+        assert_eq!(
+            encoder.update_parity(6, &shards[0], &shards[1], &mut parity),
+            Err(Errors::InvalidShardIndex)
+        );
+        assert_eq!(
+            encoder.update_parity(0, &shards[0][1..], &shards[1][1..], &mut parity),
+            Err(Errors::InvalidShardSize)
+        );
+        assert_eq!(
+            encoder.update_parity(0, &shards[0], &shards[1], &mut parity[1..]),
+            Err(Errors::InvalidShardCount)
+        );
+    }
+
+    #[test]
+    fn reconstruct_vandermonde() {
+        let encoder = ShardEncoder::<GF2k>::new(5, 3).unwrap();
+        let mut original = shards(5, 3, 64);
+        encoder.encode(&mut original).unwrap();
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.iter().cloned().map(Some).collect();
+        damaged[0] = None;
+        damaged[3] = None;
+        damaged[6] = None;
+
+        encoder.reconstruct(&mut damaged).unwrap();
+        let rebuilt: Vec<Vec<u8>> = damaged.into_iter().map(Option::unwrap).collect();
+        assert_eq!(rebuilt, original);
+    }
+
+    #[test]
+    fn decode_cache() {
+        let encoder = ShardEncoder::<GF2k>::new(4, 3)
+            .unwrap()
+            .with_decode_cache(2);
+        let mut original = shards(4, 3, 16);
+        encoder.encode(&mut original).unwrap();
+
+        let patterns: [&[usize]; 7] = [&[0, 5], &[1], &[0, 5], &[2, 3], &[0, 5], &[1], &[0, 6]];
+        for missing in patterns {
+            let mut damaged: Vec<Option<Vec<u8>>> = original.iter().cloned().map(Some).collect();
+            for &i in missing {
+                damaged[i] = None;
+            }
+
+            encoder.reconstruct(&mut damaged).unwrap();
+            let rebuilt: Vec<Vec<u8>> = damaged.into_iter().map(Option::unwrap).collect();
+            assert_eq!(rebuilt, original);
+        }
+
+        // [2, 3] evicts [1], the least recently used, which misses again, while [0, 6]
+        // inverts the same shards as [0, 5]
+        assert_eq!(
+            encoder.cache_stats(),
+            CacheStats {
+                hits: 3,
+                misses: 4,
+                entries: 2
+            }
+        );
+
+        let uncached = ShardEncoder::<GF2k>::new(4, 3).unwrap();
+        assert_eq!(uncached.cache_stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn decode_cache_reinsert() {
+        // two threads missing the same pattern both insert its matrix
+        let mut cache = DecodeCache::<GF2k> {
+            capacity: 3,
+            entries: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        };
+        cache.insert(vec![1], Matrix::new(1, 1));
+        cache.insert(vec![2], Matrix::new(2, 2));
+        cache.insert(vec![1], Matrix::new(1, 1));
+
+        let keys: Vec<&[u64]> = cache.entries.iter().map(|(k, _)| &k[..]).collect();
+        assert_eq!(keys, [&[1][..], &[2]]);
+
+        cache.insert(vec![3], Matrix::new(3, 3));
+        cache.insert(vec![4], Matrix::new(4, 4));
+        assert!(cache.get(&[1]).is_some());
+        assert!(cache.get(&[2]).is_none());
+    }
+
+    #[test]
+    fn reconstruct_cauchy() {
+        let encoder = ShardEncoder::<GF2k>::new_cauchy(4, 4).unwrap();
+        let mut original = shards(4, 4, 33);
+        encoder.encode(&mut original).unwrap();
+        assert!(encoder.verify(&original).unwrap());
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.iter().cloned().map(Some).collect();
+        for i in [0, 1, 2, 7] {
+            damaged[i] = None;
+        }
+
+        encoder.reconstruct(&mut damaged).unwrap();
+        let rebuilt: Vec<Vec<u8>> = damaged.into_iter().map(Option::unwrap).collect();
+        assert_eq!(rebuilt, original);
+    }
+
+    #[test]
+    fn reconstruct_too_few() {
+        let encoder = ShardEncoder::<GF2k>::new(3, 2).unwrap();
+        let mut original = shards(3, 2, 8);
+        encoder.encode(&mut original).unwrap();
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.into_iter().map(Some).collect();
+        damaged[1] = None;
+        damaged[2] = None;
+        damaged[4] = None;
+
+        assert_eq!(encoder.reconstruct(&mut damaged), Err(Errors::TooFewShards));
+    }
+
+    #[test]
+    fn invalid_shards() {
+        let encoder = ShardEncoder::<GF2k>::new(3, 2).unwrap();
+
+        assert_eq!(
+            encoder.encode(&mut shards(3, 1, 8)),
+            Err(Errors::InvalidShardCount)
+        );
+
+        let mut uneven = shards(3, 2, 8);
+        uneven[1].pop();
+        assert_eq!(encoder.encode(&mut uneven), Err(Errors::InvalidShardSize));
+        assert!(ShardEncoder::<GF2k>::new(200, 57).is_err());
+
+        // symbols of at least the modulus would be reduced
+        let encoder = ShardEncoder::<BabyBear>::new(3, 2).unwrap();
+        let mut large = shards(3, 2, 8);
+        large[2][4..].copy_from_slice(&[0x78, 0, 0, 1]);
+        assert_eq!(encoder.encode(&mut large), Err(Errors::InvalidMessage));
+
+        let mut parity = vec![vec![0; 8]; 2];
+        for (old, new) in [(&large[2], &large[1]), (&large[1], &large[2])] {
+            assert_eq!(
+                encoder.update_parity(0, old, new, &mut parity),
+                Err(Errors::InvalidMessage)
+            );
+        }
+    }
+
+    #[test]
+    fn reconstruct_gf2_16() {
+        let encoder = ShardEncoder::<GF2_16>::new_cauchy(250, 20).unwrap();
+        let mut original = shards(250, 20, 16);
+        encoder.encode(&mut original).unwrap();
+        assert!(encoder.verify(&original).unwrap());
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.iter().cloned().map(Some).collect();
+        for i in (0..270).step_by(14) {
+            damaged[i] = None;
+        }
+
+        encoder.reconstruct(&mut damaged).unwrap();
+        let rebuilt: Vec<Vec<u8>> = damaged.into_iter().map(Option::unwrap).collect();
+        assert_eq!(rebuilt, original);
+
+        original[0].pop();
+        assert_eq!(encoder.verify(&original), Err(Errors::InvalidShardSize));
+    }
+}
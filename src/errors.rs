@@ -4,6 +4,26 @@ pub enum Errors {
     TooFewParity,
     TooManyNumbers,
     InvalidMessage,
+    InvalidCodeword,
+    TooManyErrors,
+    InvalidErasure,
+    InvalidParity,
+    InvalidShardCount,
+    InvalidShardSize,
+    InvalidShardIndex,
+    TooFewShards,
+    SingularMatrix,
+    InvalidRoot,
+    NotSystematic,
+    InvalidSubspace,
+    NotPrimitive,
+    FieldMismatch,
+    InvalidProfile,
+    InvalidFormat,
+    InvalidDimensions,
+    NotCyclic,
+    InvalidInterleave,
+    InvalidShortening,
 }
 
 impl Errors {
@@ -13,6 +33,26 @@ impl Errors {
             Self::TooFewParity => "Total parity number provided is less than one",
             Self::TooManyNumbers => "Total number provided is greater than order of the field",
             Self::InvalidMessage => "Invalid message to encode",
+            Self::InvalidCodeword => "Codeword length does not match the code",
+            Self::TooManyErrors => "Codeword has more errors than the code can correct",
+            Self::InvalidErasure => "Erasure position is outside the codeword",
+            Self::InvalidParity => "Parity buffer length does not match the code",
+            Self::InvalidShardCount => "Number of shards does not match the encoder",
+            Self::InvalidShardSize => "Shards are empty or of different sizes",
+            Self::InvalidShardIndex => "Shard index is outside the data shards",
+            Self::TooFewShards => "Too few shards present to reconstruct the data",
+            Self::SingularMatrix => "Matrix is singular and cannot be inverted",
+            Self::InvalidRoot => "Root is not a primitive root of unity of the required order",
+            Self::NotSystematic => "Operation is only supported by systematic codes",
+            Self::InvalidSubspace => "Evaluation subspace is not a power of two within the field",
+            Self::NotPrimitive => "Polynomial and generator do not generate the whole field",
+            Self::FieldMismatch => "Field polynomial does not match the field of the code",
+            Self::InvalidProfile => "Parameters are not supported by the profile",
+            Self::InvalidFormat => "Data is not in the expected format",
+            Self::InvalidDimensions => "Matrix dimensions do not match the operation",
+            Self::NotCyclic => "Operation is only supported by full-length cyclic codes",
+            Self::InvalidInterleave => "Interleaving depth must be at least one",
+            Self::InvalidShortening => "Shortening leaves no message symbols",
         }
     }
 }
@@ -1,4 +1,4 @@
-/// Implementation adapted from [lambaworks_kzg](https://github.com/lambdaclass/lambdaworks_kzg)
+//! Implementation adapted from [lambaworks_kzg](https://github.com/lambdaclass/lambdaworks_kzg)
 
 /// Trait for finite fields
 pub trait Fp: Sized + Clone {
@@ -8,6 +8,9 @@ pub trait Fp: Sized + Clone {
     /// The prime polynomial of the field
     const PRIME_POLY: usize;
 
+    /// Number of bytes a serialized element occupies
+    const SYMBOL_SIZE: usize;
+
     /// Element of the field
     type Elem: Sized + Copy + Default + Clone + PartialEq + Eq + std::fmt::Debug;
 
@@ -44,13 +47,84 @@ pub trait Fp: Sized + Clone {
     fn from_u8(a: u8) -> Self::Elem;
 
     fn to_u8(a: Self::Elem) -> u8;
+
+    fn from_u64(a: u64) -> Self::Elem;
+
+    fn to_u64(a: Self::Elem) -> u64;
+
+    /// Reads an element from its big-endian `SYMBOL_SIZE` byte representation
+    fn from_bytes(bytes: &[u8]) -> Self::Elem {
+        Self::from_u64(bytes.iter().fold(0, |acc, b| (acc << 8) | *b as u64))
+    }
+
+    /// Writes the big-endian `SYMBOL_SIZE` byte representation of an element
+    fn to_bytes(a: Self::Elem, bytes: &mut [u8]) {
+        let value = Self::to_u64(a);
+        for (i, b) in bytes.iter_mut().rev().enumerate() {
+            *b = (value >> (8 * i)) as u8;
+        }
+    }
+
+    /// Returns whether the `SYMBOL_SIZE` bytes `bytes` read as an element without being
+    /// reduced, i.e. whether [`Fp::from_bytes`] and [`Fp::to_bytes`] round-trip them
+    fn is_canonical(_bytes: &[u8]) -> bool {
+        true
+    }
+
+    /// The prime polynomial of the field, which only differs from [`Fp::PRIME_POLY`] for
+    /// fields chosen at runtime
+    fn prime_poly() -> usize {
+        Self::PRIME_POLY
+    }
+
+    /// Sets the symbols of `dst` to `c` times those of `src`, both of equal length and
+    /// made of `SYMBOL_SIZE` byte big-endian symbols
+    fn mul_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        assert_eq!(src.len(), dst.len(), "slices differ in length");
+
+        for (s, d) in src
+            .chunks(Self::SYMBOL_SIZE)
+            .zip(dst.chunks_mut(Self::SYMBOL_SIZE))
+        {
+            Self::to_bytes(Self::mul(c, Self::from_bytes(s)), d);
+        }
+    }
+
+    /// Adds `c` times the symbols of `src` to those of `dst`, see [`Fp::mul_slice`]
+    fn mul_add_slice(c: Self::Elem, src: &[u8], dst: &mut [u8]) {
+        assert_eq!(src.len(), dst.len(), "slices differ in length");
+
+        for (s, d) in src
+            .chunks(Self::SYMBOL_SIZE)
+            .zip(dst.chunks_mut(Self::SYMBOL_SIZE))
+        {
+            let sum = Self::add(Self::from_bytes(d), Self::mul(c, Self::from_bytes(s)));
+            Self::to_bytes(sum, d);
+        }
+    }
+}
+
+/// Binary extension fields GF(2^m), whose elements are bit vectors over the polynomial
+/// basis and add by XOR
+pub trait BinaryField: Fp {
+    /// Degree `m` of the extension
+    const DEGREE: usize;
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct FieldElement<F: Fp> {
     value: F::Elem,
 }
 
+// Implemented by hand so that `FieldElement<F>` is `Copy` without requiring `F: Copy`.
+impl<F: Fp> Clone for FieldElement<F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<F: Fp> Copy for FieldElement<F> {}
+
 impl<F: Fp> From<u8> for FieldElement<F> {
     fn from(value: u8) -> Self {
         Self {
@@ -213,4 +287,50 @@ impl<F: Fp> FieldElement<F> {
     pub fn new(value: F::Elem) -> Self {
         Self { value }
     }
+
+    pub fn value(&self) -> F::Elem {
+        self.value
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Self {
+            value: F::from_u64(value),
+        }
+    }
+
+    pub fn to_u64(&self) -> u64 {
+        F::to_u64(self.value)
+    }
+
+    /// Reads an element from its big-endian `F::SYMBOL_SIZE` byte representation.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            value: F::from_bytes(bytes),
+        }
+    }
+
+    /// Writes the big-endian `F::SYMBOL_SIZE` byte representation of the element.
+    pub fn write_bytes(&self, bytes: &mut [u8]) {
+        F::to_bytes(self.value, bytes)
+    }
+
+    /// Splits `bytes` into elements of `F::SYMBOL_SIZE` bytes each, returning `None` if
+    /// the length is not a multiple of the symbol size.
+    pub fn from_byte_slice(bytes: &[u8]) -> Option<Vec<Self>> {
+        if !bytes.len().is_multiple_of(F::SYMBOL_SIZE) {
+            return None;
+        }
+
+        Some(bytes.chunks(F::SYMBOL_SIZE).map(Self::from_bytes).collect())
+    }
+
+    /// Serializes elements back into bytes, the inverse of [`FieldElement::from_byte_slice`].
+    pub fn to_byte_vec(elems: &[Self]) -> Vec<u8> {
+        let mut bytes = vec![0; elems.len() * F::SYMBOL_SIZE];
+        for (elem, chunk) in elems.iter().zip(bytes.chunks_mut(F::SYMBOL_SIZE)) {
+            elem.write_bytes(chunk);
+        }
+
+        bytes
+    }
 }
@@ -0,0 +1,237 @@
+//! Radix-2 number theoretic transforms over multiplicative subgroups of order `2^m`.
+//!
+//! Unlike [`Polynomial`](crate::polynomial::Polynomial), coefficient vectors here are
+//! stored lowest degree first, so that `values[i]` of the transform is the evaluation at
+//! `root^i`.
+
+use num_traits::{One, Pow, Zero};
+
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::ff::Fp;
+
+/// Checks that `root` is a primitive `n`-th root of unity for a power of two `n`.
+pub fn check_root<F: Fp>(n: usize, root: &FE<F>) -> Result<(), Errors> {
+    if n < 2 || !n.is_power_of_two() {
+        return Err(Errors::InvalidRoot);
+    }
+
+    if root.pow(n) != FE::one() || root.pow(n / 2) == FE::one() {
+        return Err(Errors::InvalidRoot);
+    }
+
+    Ok(())
+}
+
+/// Reorders `values` by bit-reversed index.
+fn bit_reverse<T>(values: &mut [T]) {
+    let bits = values.len().trailing_zeros();
+    if bits == 0 {
+        return;
+    }
+
+    for i in 0..values.len() {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+}
+
+/// Evaluates the polynomial with coefficients `values` at `root^0..root^(n-1)` in place,
+/// where `root` is a primitive `n`-th root of unity and `n = values.len()`.
+pub fn ntt<F: Fp>(values: &mut [FE<F>], root: &FE<F>) -> Result<(), Errors> {
+    check_root(values.len(), root)?;
+
+    bit_reverse(values);
+
+    let n = values.len();
+    let mut len = 2;
+    while len <= n {
+        let step = root.pow(n / len);
+        for chunk in values.chunks_mut(len) {
+            let (lo, hi) = chunk.split_at_mut(len / 2);
+            let mut twiddle = FE::<F>::one();
+            for (a, b) in lo.iter_mut().zip(hi.iter_mut()) {
+                let t = *b * twiddle;
+                *b = *a - t;
+                *a += t;
+                twiddle *= step;
+            }
+        }
+        len <<= 1;
+    }
+
+    Ok(())
+}
+
+/// Interpolates evaluations at `root^0..root^(n-1)` back into coefficients in place.
+pub fn intt<F: Fp>(values: &mut [FE<F>], root: &FE<F>) -> Result<(), Errors> {
+    check_root(values.len(), root)?;
+
+    let inverse_root = FE::one() / *root;
+    ntt(values, &inverse_root)?;
+
+    let n_inv = FE::one() / FE::from_u64(values.len() as u64);
+    for v in values.iter_mut() {
+        *v *= n_inv;
+    }
+
+    Ok(())
+}
+
+/// Evaluates `coeffs` on the coset `shift * <root>`.
+fn coset_ntt<F: Fp>(coeffs: &mut [FE<F>], root: &FE<F>, shift: &FE<F>) -> Result<(), Errors> {
+    let mut power = FE::<F>::one();
+    for c in coeffs.iter_mut() {
+        *c *= power;
+        power *= *shift;
+    }
+
+    ntt(coeffs, root)
+}
+
+/// Inverse of [`coset_ntt`].
+fn coset_intt<F: Fp>(values: &mut [FE<F>], root: &FE<F>, shift: &FE<F>) -> Result<(), Errors> {
+    intt(values, root)?;
+
+    let shift_inv = FE::one() / *shift;
+    let mut power = FE::<F>::one();
+    for v in values.iter_mut() {
+        *v *= power;
+        power *= shift_inv;
+    }
+
+    Ok(())
+}
+
+/// Recovers the coefficients of a polynomial of degree `< k` from its evaluations at
+/// `root^0..root^(n-1)`, where the evaluations at `erasures` are missing.
+///
+/// Multiplying by the vanishing polynomial `Z` of the erased points zeroes them out, so
+/// the product `m·Z` can be interpolated from every position. Dividing by `Z` pointwise on
+/// a coset, where `Z` has no roots, then yields `m` in `O(n log n)` plus the `O(n·e)`
+/// needed to build `Z`.
+pub fn recover_erasures<F: Fp>(
+    values: &[FE<F>],
+    erasures: &[usize],
+    k: usize,
+    root: &FE<F>,
+) -> Result<Vec<FE<F>>, Errors> {
+    let n = values.len();
+    check_root(n, root)?;
+
+    if erasures.iter().any(|&j| j >= n) {
+        return Err(Errors::InvalidErasure);
+    }
+
+    if erasures.len() + k > n {
+        return Err(Errors::TooManyErrors);
+    }
+
+    // Z(x) = Π (x - root^j), lowest degree first
+    let mut vanishing = vec![FE::<F>::zero(); n];
+    vanishing[0] = FE::one();
+    for (degree, &j) in erasures.iter().enumerate() {
+        let point = root.pow(j);
+        for i in (1..=degree + 1).rev() {
+            vanishing[i] = vanishing[i - 1] - vanishing[i] * point;
+        }
+        vanishing[0] = -(vanishing[0] * point);
+    }
+
+    let mut z_evals = vanishing.clone();
+    ntt(&mut z_evals, root)?;
+
+    let mut product: Vec<FE<F>> = values
+        .iter()
+        .zip(z_evals.iter())
+        .map(|(v, z)| *v * *z)
+        .collect();
+    intt(&mut product, root)?;
+
+    // any element outside the subgroup works as coset shift
+    let shift = (2..256)
+        .map(FE::<F>::from_u64)
+        .find(|g| !g.is_zero() && g.pow(n) != FE::one())
+        .ok_or(Errors::InvalidRoot)?;
+
+    coset_ntt(&mut product, root, &shift)?;
+    coset_ntt(&mut vanishing, root, &shift)?;
+    for (p, z) in product.iter_mut().zip(vanishing.iter()) {
+        *p /= *z;
+    }
+    coset_intt(&mut product, root, &shift)?;
+
+    product.truncate(k);
+    Ok(product)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::{One, Pow, Zero};
+
+    use super::*;
+    use crate::backend::gf2_8::GF2k;
+    use crate::backend::prime::{BabyBear, Mod998244353};
+    use crate::polynomial::Polynomial;
+
+    type Elem = FE<Mod998244353>;
+
+    fn root(n: u64) -> Elem {
+        Elem::from(3).pow(((998_244_353 - 1) / n) as usize)
+    }
+
+    #[test]
+    fn ntt_matches_evaluation() {
+        let coeffs: Vec<Elem> = (1..=8).map(|x| Elem::from_u64(x * 1000 + 7)).collect();
+        let mut values = coeffs.clone();
+        ntt(&mut values, &root(8)).unwrap();
+
+        let poly = Polynomial {
+            coeffs: coeffs.iter().rev().cloned().collect(),
+        };
+        for (i, v) in values.iter().enumerate() {
+            assert_eq!(*v, poly.evaluate(&root(8).pow(i)));
+        }
+
+        intt(&mut values, &root(8)).unwrap();
+        assert_eq!(values, coeffs);
+    }
+
+    #[test]
+    fn invalid_root() {
+        let mut values = vec![Elem::one(); 8];
+
+        assert_eq!(ntt(&mut values, &root(4)), Err(Errors::InvalidRoot));
+        assert_eq!(ntt(&mut values[..6], &root(4)), Err(Errors::InvalidRoot));
+        assert_eq!(
+            ntt(&mut [FE::<GF2k>::one(); 4], &FE::new(2)),
+            Err(Errors::InvalidRoot)
+        );
+    }
+
+    #[test]
+    fn recover() {
+        let root = FE::<BabyBear>::from(31).pow(((0x7800_0001u64 - 1) / 16) as usize);
+        let message: Vec<FE<BabyBear>> = (0..10).map(|x| FE::from_u64(x * x + 1)).collect();
+
+        let mut values = message.clone();
+        values.resize(16, FE::zero());
+        ntt(&mut values, &root).unwrap();
+
+        let erasures = [0, 3, 4, 9, 11, 15];
+        for &j in erasures.iter() {
+            values[j] = FE::zero();
+        }
+
+        assert_eq!(
+            recover_erasures(&values, &erasures, 10, &root).unwrap(),
+            message
+        );
+        assert_eq!(
+            recover_erasures(&values, &[0, 1, 2, 3, 4, 5, 6], 10, &root),
+            Err(Errors::TooManyErrors)
+        );
+    }
+}
@@ -0,0 +1,761 @@
+//! Self-describing container for protected data, decodable without knowing the code it
+//! was written with.
+//!
+//! All integers are big-endian. A container is laid out as
+//!
+//! | part     | bytes                                                 |
+//! |----------|-------------------------------------------------------|
+//! | magic    | `SHRS`                                                |
+//! | header   | [`Header`] followed by 16 parity bytes                |
+//! | groups   | the data encoded by a [`BlockCodec`], zero padded     |
+//! | index    | CRC-32 of every encoded group                         |
+//! | trailer  | data length, group count and CRC-32 of the index, followed by 16 parity bytes |
+//! | magic    | `SHRE`                                                |
+//!
+//! The header and the trailer are protected by RS codes over GF(2^8) of their own, each
+//! correcting 8 damaged bytes, and the magics are recognized with up to 8 flipped bits.
+//! The index lets the reader skip decoding groups whose checksum matches; it is only an
+//! accelerator, so a damaged index costs time but not data.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::backend::clmul::GF2_32;
+use crate::backend::gf256::{DynGF256, GF256Config};
+use crate::backend::prime::{BabyBear, Goldilocks, Mod998244353};
+use crate::backend::{gf2_16::GF2_16, gf2_8::GF2k};
+use crate::block::BlockCodec;
+use crate::checksum::crc32;
+use crate::errors::Errors;
+use crate::ff::{FieldElement as FE, Fp};
+use crate::reedsolomon::ReedSolomon;
+use crate::stream::{BlockStats, StreamStats};
+
+pub const MAGIC: &[u8; 4] = b"SHRS";
+
+pub const END_MAGIC: &[u8; 4] = b"SHRE";
+
+/// Version of the layout written by [`ContainerWriter`]
+pub const VERSION: u8 = 1;
+
+/// Bytes of a serialized [`Header`]
+const HEADER_SIZE: usize = 38;
+
+/// Bytes of the serialized trailer
+const TRAILER_SIZE: usize = 20;
+
+/// Parity bytes protecting the header and the trailer
+const META_PARITY: usize = 16;
+
+/// Bits of a magic that may be flipped, the header or trailer next to it still having
+/// to decode
+const MAGIC_TOLERANCE: u32 = 8;
+
+/// Offset of the first group
+const DATA_START: u64 = (MAGIC.len() + HEADER_SIZE + META_PARITY) as u64;
+
+/// Bytes after the index
+const END_SIZE: u64 = (TRAILER_SIZE + META_PARITY + END_MAGIC.len()) as u64;
+
+/// Returns whether `bytes` are `magic` with at most [`MAGIC_TOLERANCE`] flipped bits.
+fn is_magic(bytes: &[u8], magic: &[u8; 4]) -> bool {
+    let flipped: u32 = bytes
+        .iter()
+        .zip(magic)
+        .map(|(a, b)| (a ^ b).count_ones())
+        .sum();
+    flipped <= MAGIC_TOLERANCE
+}
+
+fn invalid_data(e: Errors) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn invalid_input(e: Errors) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
+
+/// Input of [`decode`], as a trait object
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+type DecodeFn = fn(&mut dyn ReadSeek, &mut dyn Write) -> io::Result<StreamStats>;
+
+/// Symbol size, [`Fp::PRIME_POLY`] and decoder of the fields [`decode`] picks from a
+/// header, on top of GF(2^8) with any polynomial, which decodes as [`DynGF256`]. Fields
+/// of the same polynomial and symbol size are the same field with the same bytes, so
+/// e.g. [`GF2_16Clmul`](crate::backend::clmul::GF2_16Clmul) containers decode as
+/// [`GF2_16`].
+const FIELDS: [(usize, usize, DecodeFn); 6] = [
+    (GF2k::SYMBOL_SIZE, GF2k::PRIME_POLY, decode_as::<GF2k>),
+    (GF2_16::SYMBOL_SIZE, GF2_16::PRIME_POLY, decode_as::<GF2_16>),
+    (GF2_32::SYMBOL_SIZE, GF2_32::PRIME_POLY, decode_as::<GF2_32>),
+    (
+        BabyBear::SYMBOL_SIZE,
+        BabyBear::PRIME_POLY,
+        decode_as::<BabyBear>,
+    ),
+    (
+        Mod998244353::SYMBOL_SIZE,
+        Mod998244353::PRIME_POLY,
+        decode_as::<Mod998244353>,
+    ),
+    (
+        Goldilocks::SYMBOL_SIZE,
+        Goldilocks::PRIME_POLY,
+        decode_as::<Goldilocks>,
+    ),
+];
+
+/// Decoder of the field of `symbol_size` and `poly` listed in [`FIELDS`]
+fn decoder(symbol_size: usize, poly: usize) -> Option<DecodeFn> {
+    FIELDS
+        .iter()
+        .find(|&&(size, p, _)| (size, p) == (symbol_size, poly))
+        .map(|&(_, _, decode)| decode)
+}
+
+/// Code protecting `len` bytes of metadata
+fn meta_code(len: usize) -> ReedSolomon<GF2k> {
+    ReedSolomon::new(len + META_PARITY, len, FE::new(2)).unwrap()
+}
+
+/// Parameters of the code a container was written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    /// Bytes per symbol
+    pub symbol_size: u8,
+    /// [`Fp::prime_poly`] of the field
+    pub prime_poly: u64,
+    /// Primitive element the roots of the code are powers of
+    pub generator: u64,
+    pub first_root: u32,
+    pub root_step: u32,
+    pub n: u32,
+    pub k: u32,
+    pub interleave: u32,
+}
+
+impl Header {
+    /// Describes a systematic `(n, k)` code over `F` with roots `gen^1..gen^(n-k)`.
+    pub fn new<F: Fp>(n: u32, k: u32, generator: FE<F>) -> Self {
+        Header {
+            symbol_size: F::SYMBOL_SIZE as u8,
+            prime_poly: F::prime_poly() as u64,
+            generator: generator.to_u64(),
+            first_root: 1,
+            root_step: 1,
+            n,
+            k,
+            interleave: 1,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0; HEADER_SIZE];
+        bytes[0] = VERSION;
+        bytes[1] = self.symbol_size;
+        bytes[2..10].copy_from_slice(&self.prime_poly.to_be_bytes());
+        bytes[10..18].copy_from_slice(&self.generator.to_be_bytes());
+        for (i, v) in [
+            self.first_root,
+            self.root_step,
+            self.n,
+            self.k,
+            self.interleave,
+        ]
+        .iter()
+        .enumerate()
+        {
+            bytes[18 + 4 * i..22 + 4 * i].copy_from_slice(&v.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; HEADER_SIZE]) -> Result<Self, Errors> {
+        if bytes[0] != VERSION {
+            return Err(Errors::InvalidFormat);
+        }
+
+        let u32_at = |i: usize| u32::from_be_bytes(bytes[i..i + 4].try_into().unwrap());
+        let u64_at = |i: usize| u64::from_be_bytes(bytes[i..i + 8].try_into().unwrap());
+
+        Ok(Header {
+            symbol_size: bytes[1],
+            prime_poly: u64_at(2),
+            generator: u64_at(10),
+            first_root: u32_at(18),
+            root_step: u32_at(22),
+            n: u32_at(26),
+            k: u32_at(30),
+            interleave: u32_at(34),
+        })
+    }
+
+    /// Builds the codec described by the header, which must be over `F`.
+    pub fn codec<F: Fp>(&self) -> Result<BlockCodec<F>, Errors> {
+        if self.symbol_size as usize != F::SYMBOL_SIZE || self.prime_poly != F::prime_poly() as u64
+        {
+            return Err(Errors::FieldMismatch);
+        }
+
+        let rs = ReedSolomon::builder(
+            self.n as usize,
+            self.k as usize,
+            FE::from_u64(self.generator),
+        )
+        .first_root(self.first_root as usize)
+        .root_step(self.root_step as usize)
+        .build()?;
+
+        BlockCodec::new(rs).interleaved(self.interleave as usize)
+    }
+}
+
+/// Reads the magic and the header at the current position, correcting the header if
+/// needed.
+pub fn read_header<R: Read>(input: &mut R) -> io::Result<Header> {
+    let mut bytes = [0; MAGIC.len() + HEADER_SIZE + META_PARITY];
+    input.read_exact(&mut bytes)?;
+
+    if !is_magic(&bytes[..MAGIC.len()], MAGIC) {
+        return Err(invalid_data(Errors::InvalidFormat));
+    }
+
+    let decoded = meta_code(HEADER_SIZE)
+        .decode(&bytes[MAGIC.len()..])
+        .map_err(invalid_data)?;
+
+    Header::from_bytes(&decoded.message.try_into().unwrap()).map_err(invalid_data)
+}
+
+/// Writes a container, encoding everything written to it.
+///
+/// Only the fields [`decode`] can identify from the header are accepted, which leaves out
+/// those whose modulus does not fit in it, like BN254 and GF(2^64).
+///
+/// [`ContainerWriter::finish`] must be called to write the index and the trailer,
+/// dropping the writer does so on a best-effort basis and ignores errors.
+#[derive(Debug)]
+pub struct ContainerWriter<W: Write, F: Fp> {
+    inner: Option<W>,
+    codec: BlockCodec<F>,
+    buffer: Vec<u8>,
+    index: Vec<u32>,
+    written: u64,
+}
+
+impl<W: Write, F: Fp> ContainerWriter<W, F> {
+    /// Writes the magic and `header` to `inner`.
+    pub fn new(mut inner: W, header: Header) -> io::Result<Self> {
+        if F::SYMBOL_SIZE != 1 && decoder(F::SYMBOL_SIZE, F::PRIME_POLY).is_none() {
+            return Err(invalid_input(Errors::FieldMismatch));
+        }
+
+        let codec = header.codec::<F>().map_err(invalid_input)?;
+
+        inner.write_all(MAGIC)?;
+        inner.write_all(
+            &meta_code(HEADER_SIZE)
+                .encode(&header.to_bytes())
+                .map_err(invalid_input)?,
+        )?;
+
+        let buffer = Vec::with_capacity(codec.group_size());
+        Ok(ContainerWriter {
+            inner: Some(inner),
+            codec,
+            buffer,
+            index: vec![],
+            written: 0,
+        })
+    }
+
+    /// Encodes `data` zero padded to a group into `inner`, and adds it to `index`.
+    fn write_group(
+        codec: &BlockCodec<F>,
+        index: &mut Vec<u32>,
+        inner: &mut W,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let mut group = data.to_vec();
+        group.resize(codec.group_size(), 0);
+        let encoded = codec.encode_group(&group).map_err(invalid_input)?;
+
+        inner.write_all(&encoded)?;
+        index.push(crc32(&encoded));
+
+        Ok(())
+    }
+
+    /// Writes the last group, the index and the trailer. The underlying writer is taken
+    /// first, so that a failed end is never retried and appended twice on drop.
+    fn write_end(&mut self) -> io::Result<W> {
+        let mut inner = self.inner.take().unwrap();
+        if !self.buffer.is_empty() {
+            Self::write_group(&self.codec, &mut self.index, &mut inner, &self.buffer)?;
+        }
+
+        let index: Vec<u8> = self.index.iter().flat_map(|c| c.to_be_bytes()).collect();
+
+        let mut trailer = Vec::with_capacity(TRAILER_SIZE);
+        trailer.extend_from_slice(&self.written.to_be_bytes());
+        trailer.extend_from_slice(&(self.index.len() as u64).to_be_bytes());
+        trailer.extend_from_slice(&crc32(&index).to_be_bytes());
+        let trailer = meta_code(TRAILER_SIZE)
+            .encode(&trailer)
+            .map_err(invalid_input)?;
+
+        inner.write_all(&index)?;
+        inner.write_all(&trailer)?;
+        inner.write_all(END_MAGIC)?;
+        inner.flush()?;
+
+        Ok(inner)
+    }
+
+    /// Writes the last group, the index and the trailer, and returns the underlying
+    /// writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_end()
+    }
+}
+
+impl<W: Write, F: Fp> Write for ContainerWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let group_size = self.codec.group_size();
+        let len = buf.len().min(group_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+
+        if self.buffer.len() == group_size {
+            let inner = self.inner.as_mut().unwrap();
+            if let Err(e) = Self::write_group(&self.codec, &mut self.index, inner, &self.buffer) {
+                // none of `buf` is taken, so the write can be retried
+                self.buffer.truncate(group_size - len);
+                return Err(e);
+            }
+            self.buffer.clear();
+        }
+
+        self.written += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write, F: Fp> Drop for ContainerWriter<W, F> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_end();
+        }
+    }
+}
+
+/// Reads the data out of a container written over `F`, correcting the groups whose
+/// checksum does not match the index.
+#[derive(Debug)]
+pub struct ContainerReader<R: Read + Seek, F: Fp> {
+    inner: R,
+    header: Header,
+    codec: BlockCodec<F>,
+    /// Checksums of the groups, or `None` if the index itself is damaged
+    index: Option<Vec<u32>>,
+    len: u64,
+    groups: u64,
+    next: u64,
+    buffer: Vec<u8>,
+    pos: usize,
+    read: u64,
+    stats: StreamStats,
+}
+
+impl<R: Read + Seek, F: Fp> ContainerReader<R, F> {
+    pub fn open(mut inner: R) -> io::Result<Self> {
+        inner.seek(SeekFrom::Start(0))?;
+        let header = read_header(&mut inner)?;
+        let codec = header.codec::<F>().map_err(invalid_data)?;
+
+        let size = inner.seek(SeekFrom::End(0))?;
+        if size < DATA_START + END_SIZE {
+            return Err(invalid_data(Errors::InvalidFormat));
+        }
+
+        let mut end = [0; END_SIZE as usize];
+        inner.seek(SeekFrom::End(-(END_SIZE as i64)))?;
+        inner.read_exact(&mut end)?;
+        if !is_magic(&end[TRAILER_SIZE + META_PARITY..], END_MAGIC) {
+            return Err(invalid_data(Errors::InvalidFormat));
+        }
+
+        let trailer = meta_code(TRAILER_SIZE)
+            .decode(&end[..TRAILER_SIZE + META_PARITY])
+            .map_err(invalid_data)?
+            .message;
+        let len = u64::from_be_bytes(trailer[..8].try_into().unwrap());
+        let groups = u64::from_be_bytes(trailer[8..16].try_into().unwrap());
+        let index_crc = u32::from_be_bytes(trailer[16..20].try_into().unwrap());
+
+        let group_size = codec.encoded_group_size() as u64;
+        let expected = groups
+            .checked_mul(group_size + 4)
+            .and_then(|body| body.checked_add(DATA_START + END_SIZE));
+        if expected != Some(size) || len > groups * codec.group_size() as u64 {
+            return Err(invalid_data(Errors::InvalidFormat));
+        }
+
+        let mut index = vec![0; groups as usize * 4];
+        inner.seek(SeekFrom::Start(DATA_START + groups * group_size))?;
+        inner.read_exact(&mut index)?;
+        let index = (crc32(&index) == index_crc).then(|| {
+            index
+                .chunks(4)
+                .map(|c| u32::from_be_bytes(c.try_into().unwrap()))
+                .collect()
+        });
+
+        inner.seek(SeekFrom::Start(DATA_START))?;
+        Ok(ContainerReader {
+            inner,
+            header,
+            codec,
+            index,
+            len,
+            groups,
+            next: 0,
+            buffer: vec![],
+            pos: 0,
+            read: 0,
+            stats: StreamStats::default(),
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Number of data bytes in the container
+    pub fn data_len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the block index passed its checksum
+    pub fn has_index(&self) -> bool {
+        self.index.is_some()
+    }
+
+    pub fn stats(&self) -> &StreamStats {
+        &self.stats
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        if self.pos < self.buffer.len() || self.next == self.groups {
+            return Ok(());
+        }
+
+        let mut group = vec![0; self.codec.encoded_group_size()];
+        self.inner.read_exact(&mut group)?;
+
+        let expected = self.index.as_ref().map(|index| index[self.next as usize]);
+        let mut data = if expected == Some(crc32(&group)) {
+            self.codec.extract_group(&group).map_err(invalid_data)?
+        } else {
+            let decoded = self.codec.decode_group(&group).map_err(invalid_data)?;
+            if !decoded.errors.is_empty() {
+                self.stats.corrected.push(BlockStats {
+                    index: self.next,
+                    errors: decoded.errors.len(),
+                });
+                self.stats.errors += decoded.errors.len() as u64;
+            }
+            decoded.message
+        };
+        self.next += 1;
+        self.stats.blocks += 1;
+
+        data.truncate((self.len - self.read).min(data.len() as u64) as usize);
+        self.read += data.len() as u64;
+        self.buffer = data;
+        self.pos = 0;
+
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek, F: Fp> Read for ContainerReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill()?;
+
+        let len = buf.len().min(self.buffer.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+fn decode_as<F: Fp>(input: &mut dyn ReadSeek, output: &mut dyn Write) -> io::Result<StreamStats> {
+    let mut reader = ContainerReader::<_, F>::open(input)?;
+    io::copy(&mut reader, output)?;
+
+    Ok(reader.stats)
+}
+
+/// Decodes a container over any of the fields of this crate into `output`, picking the
+/// field from the header.
+pub fn decode<R: Read + Seek, W: Write>(mut input: R, output: &mut W) -> io::Result<StreamStats> {
+    input.seek(SeekFrom::Start(0))?;
+    let header = read_header(&mut input)?;
+
+    let (symbol_size, poly) = (header.symbol_size as usize, header.prime_poly as usize);
+    if let Some(decode) = decoder(symbol_size, poly) {
+        return decode(&mut input, output);
+    }
+
+    if symbol_size != 1 {
+        return Err(invalid_data(Errors::FieldMismatch));
+    }
+
+    let config = GF256Config::shared(poly).map_err(invalid_data)?;
+    DynGF256::scope(config, || decode_as::<DynGF256>(&mut input, output))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::backend::gf256::CcsdsField;
+
+    fn write<F: Fp>(header: Header, data: &[u8]) -> Vec<u8> {
+        let mut writer = ContainerWriter::<_, F>::new(vec![], header).unwrap();
+        for chunk in data.chunks(100) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn header() -> Header {
+        Header {
+            interleave: 2,
+            ..Header::new(64, 48, FE::<GF2k>::new(2))
+        }
+    }
+
+    #[test]
+    fn header_bytes() {
+        let header = Header {
+            first_root: 112,
+            root_step: 11,
+            ..Header::new(255, 223, FE::<CcsdsField>::new(2))
+        };
+        assert_eq!(Header::from_bytes(&header.to_bytes()), Ok(header));
+
+        let mut bytes = header.to_bytes();
+        bytes[0] = 2;
+        assert_eq!(Header::from_bytes(&bytes), Err(Errors::InvalidFormat));
+    }
+
+    #[test]
+    fn round_trip() {
+        for len in [0usize, 1, 95, 96, 97, 5000] {
+            let data: Vec<u8> = (0..len).map(|x| (x * 17 + x / 200) as u8).collect();
+            let container = write::<GF2k>(header(), &data);
+            assert_eq!(container.len(), 58 + len.div_ceil(96) * (128 + 4) + 40);
+
+            let mut reader = ContainerReader::<_, GF2k>::open(Cursor::new(&container)).unwrap();
+            assert_eq!(reader.header(), &header());
+            assert_eq!(reader.data_len(), len as u64);
+            assert!(reader.has_index());
+
+            let mut decoded = vec![];
+            reader.read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+            assert!(reader.stats().corrected.is_empty());
+        }
+    }
+
+    #[test]
+    fn damaged() {
+        let data: Vec<u8> = (0..1000).map(|x| x as u8).collect();
+        let mut container = write::<GF2k>(header(), &data);
+        let size = container.len();
+
+        // header, first group, index and trailer
+        for j in [0, 4, 10, 41, 60, 61, 62, 63, size - 60, size - 30] {
+            container[j] ^= 0x81;
+        }
+
+        let mut reader = ContainerReader::<_, GF2k>::open(Cursor::new(&container)).unwrap();
+        assert!(!reader.has_index());
+
+        let mut decoded = vec![];
+        reader.read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+        assert_eq!(
+            reader.stats().corrected,
+            vec![BlockStats {
+                index: 0,
+                errors: 4
+            }]
+        );
+
+        for byte in container[58 + 128..58 + 160].iter_mut() {
+            *byte = !*byte;
+        }
+        let err = ContainerReader::<_, GF2k>::open(Cursor::new(&container))
+            .unwrap()
+            .read_to_end(&mut vec![])
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn flipped_magic() {
+        let data: Vec<u8> = (0..300).map(|x| x as u8).collect();
+        let mut container = write::<GF2k>(header(), &data);
+        let size = container.len();
+
+        container[1] ^= 0xff;
+        container[size - 1] ^= 0xff;
+        let mut decoded = vec![];
+        decode(Cursor::new(&container), &mut decoded).unwrap();
+        assert_eq!(decoded, data);
+
+        container[2] ^= 0x01;
+        let err = decode(Cursor::new(&container), &mut vec![]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        container[2] ^= 0x01;
+        container[size - 2] ^= 0x01;
+        let err = decode(Cursor::new(&container), &mut vec![]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn invalid() {
+        let container = write::<GF2k>(header(), b"some data");
+
+        let err = ContainerReader::<_, GF2k>::open(Cursor::new(&container[..container.len() - 1]))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = ContainerReader::<_, GF2_16>::open(Cursor::new(&container)).unwrap_err();
+        assert_eq!(
+            err.into_inner().unwrap().downcast::<Errors>().unwrap(),
+            Box::new(Errors::FieldMismatch)
+        );
+
+        let mut truncated = container[..container.len() - 4 - 132].to_vec();
+        truncated.extend_from_slice(&container[container.len() - 4..]);
+        assert!(ContainerReader::<_, GF2k>::open(Cursor::new(&truncated)).is_err());
+
+        assert!(ContainerWriter::<_, GF2_16>::new(vec![], header()).is_err());
+    }
+
+    #[test]
+    fn decode_any_field() {
+        let data: Vec<u8> = (0..3000).map(|x| (x % 253) as u8).collect();
+
+        let ccsds = Header {
+            first_root: 112,
+            root_step: 11,
+            interleave: 4,
+            ..Header::new(255, 223, FE::<CcsdsField>::new(2))
+        };
+        let wide = Header::new(600, 500, FE::<GF2_16>::new(2));
+        // x^8 + x^5 + x^3 + x^2 + 1, which no type of this crate is defined by
+        let runtime = DynGF256::scope(GF256Config::shared(0x12d).unwrap(), || {
+            write::<DynGF256>(Header::new(60, 50, FE::<DynGF256>::new(2)), &data)
+        });
+
+        for container in [
+            write::<CcsdsField>(ccsds, &data),
+            runtime,
+            write::<GF2_16>(wide, &data),
+            write::<GF2k>(header(), &data),
+        ] {
+            let mut decoded = vec![];
+            decode(Cursor::new(&container), &mut decoded).unwrap();
+            assert_eq!(decoded, data);
+        }
+
+        // prime fields pack bytes below the modulus
+        let data: Vec<u8> = (0..3000).map(|x| (255 - x % 256) as u8).collect();
+        for container in [
+            write::<GF2_32>(Header::new(40, 30, FE::<GF2_32>::new(2)), &data),
+            write::<BabyBear>(Header::new(40, 30, FE::<BabyBear>::new(31)), &data),
+            write::<Mod998244353>(Header::new(40, 30, FE::<Mod998244353>::new(3)), &data),
+            write::<Goldilocks>(Header::new(40, 30, FE::<Goldilocks>::new(7)), &data),
+        ] {
+            let mut decoded = vec![];
+            decode(Cursor::new(&container), &mut decoded).unwrap();
+            assert_eq!(decoded, data);
+        }
+    }
+
+    #[test]
+    fn unsupported_fields() {
+        use crate::backend::clmul::GF2_64;
+        use crate::backend::prime::Bn254Fr;
+
+        let err =
+            ContainerWriter::<_, GF2_64>::new(vec![], Header::new(8, 4, FE::<GF2_64>::new(2)));
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        let err =
+            ContainerWriter::<_, Bn254Fr>::new(vec![], Header::new(8, 4, FE::<Bn254Fr>::from(5)));
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    /// Accepts `limit` bytes and fails after that.
+    struct Limited {
+        data: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.data.len() + buf.len() > self.limit {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_writes() {
+        let data: Vec<u8> = (0..300).map(|x| x as u8).collect();
+        let expected = write::<GF2k>(header(), &data);
+
+        // the second group fails, and is written again by the retry
+        let inner = Limited {
+            data: vec![],
+            limit: 58 + 132,
+        };
+        let mut writer = ContainerWriter::<_, GF2k>::new(inner, header()).unwrap();
+        writer.write_all(&data[..96]).unwrap();
+        assert!(writer.write_all(&data[96..192]).is_err());
+        writer.inner.as_mut().unwrap().limit = usize::MAX;
+        writer.write_all(&data[96..]).unwrap();
+        assert_eq!(writer.finish().unwrap().data, expected);
+
+        // a failed finish is not retried on drop
+        let inner = Limited {
+            data: vec![],
+            limit: expected.len() - 1,
+        };
+        let mut writer = ContainerWriter::<_, GF2k>::new(inner, header()).unwrap();
+        writer.write_all(&data).unwrap();
+        assert!(writer.write_end().is_err());
+        assert!(writer.inner.is_none());
+        drop(writer);
+    }
+}
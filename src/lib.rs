@@ -1,5 +1,16 @@
+pub mod additive;
 pub mod backend;
-mod errors;
+pub mod block;
+pub mod checksum;
+pub mod erasure;
+pub mod errors;
 pub mod ff;
+pub mod fft;
+pub mod format;
+pub mod matrix;
+pub mod par2;
 pub mod polynomial;
+pub mod profiles;
+pub mod raid6;
 pub mod reedsolomon;
+pub mod stream;
@@ -0,0 +1,440 @@
+//! Dense matrices over a finite field.
+
+use std::ops::{Index, IndexMut};
+
+use num_traits::{One, Pow, Zero};
+
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::ff::Fp;
+
+/// A `rows x cols` matrix of field elements, stored row by row.
+#[derive(Debug)]
+pub struct Matrix<F: Fp> {
+    rows: usize,
+    cols: usize,
+    data: Vec<FE<F>>,
+}
+
+// Implemented by hand so that the backends need not implement them.
+impl<F: Fp> Clone for Matrix<F> {
+    fn clone(&self) -> Self {
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<F: Fp> PartialEq for Matrix<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows == other.rows && self.cols == other.cols && self.data == other.data
+    }
+}
+
+impl<F: Fp> Index<(usize, usize)> for Matrix<F> {
+    type Output = FE<F>;
+
+    fn index(&self, (r, c): (usize, usize)) -> &Self::Output {
+        assert!(c < self.cols);
+        &self.data[r * self.cols + c]
+    }
+}
+
+impl<F: Fp> IndexMut<(usize, usize)> for Matrix<F> {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Self::Output {
+        assert!(c < self.cols);
+        &mut self.data[r * self.cols + c]
+    }
+}
+
+impl<F: Fp> Matrix<F> {
+    /// Zero matrix
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Matrix {
+            rows,
+            cols,
+            data: vec![FE::zero(); rows * cols],
+        }
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut matrix = Self::new(size, size);
+        for i in 0..size {
+            matrix[(i, i)] = FE::one();
+        }
+        matrix
+    }
+
+    /// Builds a matrix from its rows, which must all have the same length.
+    pub fn from_rows(rows: Vec<Vec<FE<F>>>) -> Result<Self, Errors> {
+        let cols = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err(Errors::InvalidDimensions);
+        }
+
+        Ok(Matrix {
+            rows: rows.len(),
+            cols,
+            data: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Vandermonde matrix with rows `1, x_i, x_i^2, .., x_i^(cols-1)`.
+    pub fn vandermonde(xs: &[FE<F>], cols: usize) -> Self {
+        Matrix {
+            rows: xs.len(),
+            cols,
+            data: xs
+                .iter()
+                .flat_map(|x| (0..cols).map(|c| x.pow(c)))
+                .collect(),
+        }
+    }
+
+    /// Cauchy matrix `1 / (x_i - y_j)`, every square submatrix of which is invertible
+    /// when the `x_i` and the `y_j` are all distinct. Fails if some `x_i` equals some
+    /// `y_j`.
+    pub fn cauchy(xs: &[FE<F>], ys: &[FE<F>]) -> Result<Self, Errors> {
+        let mut data = Vec::with_capacity(xs.len() * ys.len());
+        for x in xs {
+            for y in ys {
+                let d = *x - *y;
+                if d.is_zero() {
+                    return Err(Errors::SingularMatrix);
+                }
+                data.push(FE::one() / d);
+            }
+        }
+
+        Ok(Matrix {
+            rows: xs.len(),
+            cols: ys.len(),
+            data,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, r: usize) -> &[FE<F>] {
+        &self.data[r * self.cols..(r + 1) * self.cols]
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    /// Matrix made of the rows of `self` at `rows`, in that order.
+    pub fn select_rows(&self, rows: &[usize]) -> Self {
+        Matrix {
+            rows: rows.len(),
+            cols: self.cols,
+            data: rows
+                .iter()
+                .flat_map(|&r| self.row(r).iter().copied())
+                .collect(),
+        }
+    }
+
+    /// Matrix made of the columns of `self` at `cols`, in that order.
+    pub fn select_cols(&self, cols: &[usize]) -> Self {
+        Matrix {
+            rows: self.rows,
+            cols: cols.len(),
+            data: (0..self.rows)
+                .flat_map(|r| cols.iter().map(move |&c| self[(r, c)]))
+                .collect(),
+        }
+    }
+
+    /// `[self | other]`
+    pub fn augment(&self, other: &Self) -> Result<Self, Errors> {
+        if self.rows != other.rows {
+            return Err(Errors::InvalidDimensions);
+        }
+
+        Ok(Matrix {
+            rows: self.rows,
+            cols: self.cols + other.cols,
+            data: (0..self.rows)
+                .flat_map(|r| self.row(r).iter().chain(other.row(r)).copied())
+                .collect(),
+        })
+    }
+
+    /// `self` above `other`
+    pub fn stack(&self, other: &Self) -> Result<Self, Errors> {
+        if self.cols != other.cols {
+            return Err(Errors::InvalidDimensions);
+        }
+
+        let mut data = self.data.clone();
+        data.extend_from_slice(&other.data);
+        Ok(Matrix {
+            rows: self.rows + other.rows,
+            cols: self.cols,
+            data,
+        })
+    }
+
+    pub fn transpose(&self) -> Self {
+        Matrix {
+            rows: self.cols,
+            cols: self.rows,
+            data: (0..self.cols)
+                .flat_map(|c| (0..self.rows).map(move |r| self[(r, c)]))
+                .collect(),
+        }
+    }
+
+    pub fn multiply(&self, other: &Self) -> Result<Self, Errors> {
+        if self.cols != other.rows {
+            return Err(Errors::InvalidDimensions);
+        }
+
+        let mut product = Self::new(self.rows, other.cols);
+        for r in 0..self.rows {
+            for (k, a) in self.row(r).iter().enumerate() {
+                if a.is_zero() {
+                    continue;
+                }
+
+                for c in 0..other.cols {
+                    product[(r, c)] += *a * other[(k, c)];
+                }
+            }
+        }
+
+        Ok(product)
+    }
+
+    /// Returns `self * v`.
+    pub fn multiply_vec(&self, v: &[FE<F>]) -> Result<Vec<FE<F>>, Errors> {
+        if self.cols != v.len() {
+            return Err(Errors::InvalidDimensions);
+        }
+
+        Ok((0..self.rows)
+            .map(|r| {
+                self.row(r)
+                    .iter()
+                    .zip(v.iter())
+                    .fold(FE::zero(), |acc, (a, x)| acc + *a * *x)
+            })
+            .collect())
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        if a != b {
+            for c in 0..self.cols {
+                self.data.swap(a * self.cols + c, b * self.cols + c);
+            }
+        }
+    }
+
+    /// Brings the matrix to reduced row echelon form with Gauss-Jordan elimination and
+    /// returns the pivot column of every non-zero row.
+    pub fn row_reduce(&mut self) -> Vec<usize> {
+        let mut pivots = vec![];
+
+        for c in 0..self.cols {
+            let r = pivots.len();
+            if r == self.rows {
+                break;
+            }
+
+            let Some(pivot) = (r..self.rows).find(|&i| !self[(i, c)].is_zero()) else {
+                continue;
+            };
+            self.swap_rows(r, pivot);
+
+            let scale = FE::one() / self[(r, c)];
+            for j in c..self.cols {
+                self[(r, j)] *= scale;
+            }
+
+            for i in 0..self.rows {
+                let factor = self[(i, c)];
+                if i == r || factor.is_zero() {
+                    continue;
+                }
+
+                for j in c..self.cols {
+                    let x = self[(r, j)];
+                    self[(i, j)] -= factor * x;
+                }
+            }
+
+            pivots.push(c);
+        }
+
+        pivots
+    }
+
+    pub fn rank(&self) -> usize {
+        self.clone().row_reduce().len()
+    }
+
+    /// Inverts a square matrix, failing with [`Errors::SingularMatrix`] if it has no
+    /// inverse.
+    pub fn inverse(&self) -> Result<Self, Errors> {
+        if !self.is_square() {
+            return Err(Errors::InvalidDimensions);
+        }
+
+        let size = self.rows;
+        let mut work = self.augment(&Self::identity(size))?;
+        if work.row_reduce().len() < size || (0..size).any(|i| work[(i, i)].is_zero()) {
+            return Err(Errors::SingularMatrix);
+        }
+
+        Ok(work.select_cols(&(size..2 * size).collect::<Vec<_>>()))
+    }
+
+    /// Returns a solution `x` of `self * x = b`, the free variables set to zero, or
+    /// [`Errors::SingularMatrix`] if there is none.
+    pub fn solve(&self, b: &[FE<F>]) -> Result<Vec<FE<F>>, Errors> {
+        if b.len() != self.rows {
+            return Err(Errors::InvalidDimensions);
+        }
+
+        let rhs = Matrix {
+            rows: b.len(),
+            cols: 1,
+            data: b.to_vec(),
+        };
+        let mut work = self.augment(&rhs)?;
+        let pivots = work.row_reduce();
+        if pivots.last() == Some(&self.cols) {
+            return Err(Errors::SingularMatrix);
+        }
+
+        let mut x = vec![FE::zero(); self.cols];
+        for (r, &c) in pivots.iter().enumerate() {
+            x[c] = work[(r, self.cols)];
+        }
+
+        Ok(x)
+    }
+
+    /// Rows of the matrix
+    pub fn to_rows(&self) -> Vec<Vec<FE<F>>> {
+        (0..self.rows).map(|r| self.row(r).to_vec()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{gf2_16::GF2_16, gf2_8::GF2k, prime::Mod998244353};
+
+    fn elems<F: Fp>(values: &[u64]) -> Vec<FE<F>> {
+        values.iter().map(|&v| FE::from_u64(v)).collect()
+    }
+
+    fn xs<F: Fp>(n: usize) -> Vec<FE<F>> {
+        (1..=n as u64).map(FE::from_u64).collect()
+    }
+
+    #[test]
+    fn multiply_transpose() {
+        let a =
+            Matrix::<Mod998244353>::from_rows(vec![elems(&[1, 2, 3]), elems(&[4, 5, 6])]).unwrap();
+        let b = a.transpose();
+        assert_eq!((b.rows(), b.cols()), (3, 2));
+        assert_eq!(b.row(2), &elems::<Mod998244353>(&[3, 6])[..]);
+
+        let product = a.multiply(&b).unwrap();
+        assert_eq!(
+            product,
+            Matrix::from_rows(vec![elems(&[14, 32]), elems(&[32, 77])]).unwrap()
+        );
+        assert_eq!(
+            a.multiply_vec(&elems(&[1, 0, 1])).unwrap(),
+            elems::<Mod998244353>(&[4, 10])
+        );
+
+        assert_eq!(a.multiply(&a), Err(Errors::InvalidDimensions));
+        assert_eq!(
+            Matrix::<GF2k>::from_rows(vec![elems(&[1]), elems(&[1, 2])]),
+            Err(Errors::InvalidDimensions)
+        );
+    }
+
+    fn inverse_round_trip<F: Fp + std::fmt::Debug>(size: usize) {
+        let m = Matrix::<F>::vandermonde(&xs(size), size);
+        let inverse = m.inverse().unwrap();
+
+        assert_eq!(m.multiply(&inverse).unwrap(), Matrix::identity(size));
+        assert_eq!(inverse.multiply(&m).unwrap(), Matrix::identity(size));
+    }
+
+    #[test]
+    fn inverse() {
+        inverse_round_trip::<GF2k>(10);
+        inverse_round_trip::<GF2_16>(30);
+        inverse_round_trip::<Mod998244353>(12);
+
+        // {02} * {02} = {04} over GF(2^8), so the rows are proportional
+        let singular = Matrix::<GF2k>::from_rows(vec![elems(&[1, 2]), elems(&[2, 4])]).unwrap();
+        assert_eq!(singular.rank(), 1);
+        assert_eq!(singular.inverse(), Err(Errors::SingularMatrix));
+        assert_eq!(
+            Matrix::<GF2k>::new(2, 3).inverse(),
+            Err(Errors::InvalidDimensions)
+        );
+    }
+
+    #[test]
+    fn rank_and_solve() {
+        let m = Matrix::<Mod998244353>::from_rows(vec![
+            elems(&[1, 2, 3]),
+            elems(&[2, 4, 6]),
+            elems(&[1, 0, 1]),
+        ])
+        .unwrap();
+        assert_eq!(m.rank(), 2);
+        assert_eq!(m.inverse(), Err(Errors::SingularMatrix));
+
+        // consistent, with the free variable set to zero
+        let b = elems(&[5, 10, 1]);
+        let x = m.solve(&b).unwrap();
+        assert_eq!(m.multiply_vec(&x).unwrap(), b);
+        assert_eq!(m.solve(&elems(&[5, 11, 1])), Err(Errors::SingularMatrix));
+
+        let m = Matrix::<GF2_16>::vandermonde(&xs(8), 8);
+        let b: Vec<FE<GF2_16>> = (0..8).map(|i| FE::from_u64(i * 1000 + 7)).collect();
+        let x = m.solve(&b).unwrap();
+        assert_eq!(m.multiply_vec(&x).unwrap(), b);
+        assert_eq!(m.rank(), 8);
+    }
+
+    #[test]
+    fn cauchy() {
+        let m =
+            Matrix::<GF2k>::cauchy(&elems(&[10, 11, 12, 13]), &elems(&[0, 1, 2, 3, 4])).unwrap();
+        assert_eq!((m.rows(), m.cols()), (4, 5));
+
+        // every square submatrix is invertible
+        for cols in [[0, 1, 2, 3], [1, 2, 3, 4], [0, 2, 3, 4]] {
+            assert!(m.select_cols(&cols).inverse().is_ok());
+        }
+        assert!(m
+            .select_rows(&[1, 3])
+            .select_cols(&[0, 4])
+            .inverse()
+            .is_ok());
+
+        assert_eq!(
+            Matrix::<GF2k>::cauchy(&elems(&[1, 2]), &elems(&[2, 3])),
+            Err(Errors::SingularMatrix)
+        );
+    }
+}
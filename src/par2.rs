@@ -0,0 +1,822 @@
+//! Recovery files in the PAR 2.0 format, as read and written by par2cmdline.
+//!
+//! The input files are cut into slices of a common size, the last slice of every file
+//! zero padded, and the slices are read as little-endian 16-bit words over GF(2^16) with
+//! the polynomial `0x1100b`. Input slice `i` is assigned the constant `c_i = 2^n_i`, `n_i`
+//! being the `i`-th exponent coprime to 65535, and recovery slice `e` is
+//! `sum_i c_i^e * slice_i`. Any `m` recovery slices rebuild up to `m` missing input
+//! slices, barring the rare singular subsets of this Vandermonde-like matrix.
+//!
+//! Every volume repeats the main, file description and slice checksum packets, so any
+//! single intact volume is enough to verify the files. Damaged slices are only looked for
+//! at their own offset, data shifted within a file is not searched for.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use num_traits::{Pow, Zero};
+
+use crate::backend::gf2_16::GF2_16;
+use crate::checksum::{crc32, md5};
+use crate::errors::Errors;
+use crate::ff::{FieldElement as FE, Fp};
+use crate::matrix::Matrix;
+
+const MAGIC: &[u8; 8] = b"PAR2\0PKT";
+
+const MAIN: &[u8; 16] = b"PAR 2.0\0Main\0\0\0\0";
+
+const FILE_DESCRIPTION: &[u8; 16] = b"PAR 2.0\0FileDesc";
+
+const SLICE_CHECKSUMS: &[u8; 16] = b"PAR 2.0\0IFSC\0\0\0\0";
+
+const RECOVERY_SLICE: &[u8; 16] = b"PAR 2.0\0RecvSlic";
+
+const CREATOR: &[u8; 16] = b"PAR 2.0\0Creator\0";
+
+/// Bytes of a packet header
+const PACKET_HEADER: usize = 64;
+
+/// Bytes of the start of a file covered by its 16k hash
+const HASH_16K: usize = 16 * 1024;
+
+/// Input slices that can be given distinct constants
+pub const MAX_SLICES: usize = 32768;
+
+/// Recovery slices that can be given distinct exponents
+pub const MAX_RECOVERY: u32 = 65535;
+
+pub type Hash = [u8; 16];
+
+/// Constants `2^n` of the first `count` input slices, `n` running over the exponents
+/// coprime to 65535.
+pub fn input_constants(count: usize) -> Vec<FE<GF2_16>> {
+    let two = FE::<GF2_16>::new(2);
+
+    (1..MAX_RECOVERY as usize)
+        .filter(|n| n % 3 != 0 && n % 5 != 0 && n % 17 != 0 && n % 257 != 0)
+        .take(count)
+        .map(|n| (&two).pow(n))
+        .collect()
+}
+
+/// Adds `coeff * src` to `dst`, both read as little-endian 16-bit words.
+fn mul_add(dst: &mut [u8], src: &[u8], coeff: FE<GF2_16>) {
+    if coeff.is_zero() {
+        return;
+    }
+
+    for (d, s) in dst.chunks_exact_mut(2).zip(src.chunks_exact(2)) {
+        let word = GF2_16::mul(coeff.value(), u16::from_le_bytes([s[0], s[1]]));
+        let sum = u16::from_le_bytes([d[0], d[1]]) ^ word;
+        d.copy_from_slice(&sum.to_le_bytes());
+    }
+}
+
+fn packet(set_id: &Hash, kind: &[u8; 16], body: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(PACKET_HEADER + body.len());
+    packet.extend_from_slice(MAGIC);
+    packet.extend_from_slice(&((PACKET_HEADER + body.len()) as u64).to_le_bytes());
+    packet.extend_from_slice(&[0; 16]);
+    packet.extend_from_slice(set_id);
+    packet.extend_from_slice(kind);
+    packet.extend_from_slice(body);
+
+    let hash = md5(&packet[32..]);
+    packet[16..32].copy_from_slice(&hash);
+
+    packet
+}
+
+/// Pads `bytes` with zeros to a multiple of 4.
+fn padded(bytes: &[u8]) -> Vec<u8> {
+    let mut padded = bytes.to_vec();
+    padded.resize(bytes.len().next_multiple_of(4), 0);
+    padded
+}
+
+/// A packet whose hash checked out
+struct Packet<'a> {
+    set_id: Hash,
+    kind: &'a [u8],
+    body: &'a [u8],
+}
+
+/// Finds the intact packets of `data`, skipping anything else.
+fn packets(data: &[u8]) -> Vec<Packet<'_>> {
+    let mut packets = vec![];
+    let mut pos = 0;
+
+    while pos + PACKET_HEADER <= data.len() {
+        let header = &data[pos..pos + PACKET_HEADER];
+        let len = u64::from_le_bytes(header[8..16].try_into().unwrap());
+
+        let valid = &header[..8] == MAGIC
+            && len >= PACKET_HEADER as u64
+            && len.is_multiple_of(4)
+            && len <= (data.len() - pos) as u64
+            && md5(&data[pos + 32..pos + len as usize]) == header[16..32];
+        if !valid {
+            pos += 1;
+            continue;
+        }
+
+        packets.push(Packet {
+            set_id: header[32..48].try_into().unwrap(),
+            kind: &header[48..64],
+            body: &data[pos + PACKET_HEADER..pos + len as usize],
+        });
+        pos += len as usize;
+    }
+
+    packets
+}
+
+/// An input file of a recovery set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDescription {
+    /// MD5 of the 16k hash, the length and the name
+    pub id: Hash,
+    /// MD5 of the whole file
+    pub hash: Hash,
+    /// MD5 of the first 16 KiB of the file
+    pub hash_16k: Hash,
+    pub length: u64,
+    pub name: String,
+    /// MD5 and CRC-32 of every slice, zero padded to the slice size
+    pub slices: Vec<(Hash, u32)>,
+}
+
+impl FileDescription {
+    fn new(name: &str, data: &[u8], slice_size: usize) -> Self {
+        let hash_16k = md5(&data[..data.len().min(HASH_16K)]);
+
+        let mut id = hash_16k.to_vec();
+        id.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        id.extend_from_slice(name.as_bytes());
+
+        let slices = data
+            .chunks(slice_size)
+            .map(|chunk| {
+                let mut slice = chunk.to_vec();
+                slice.resize(slice_size, 0);
+                (md5(&slice), crc32(&slice))
+            })
+            .collect();
+
+        FileDescription {
+            id: md5(&id),
+            hash: md5(data),
+            hash_16k,
+            length: data.len() as u64,
+            name: name.to_string(),
+            slices,
+        }
+    }
+
+    fn description_body(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(56 + self.name.len() + 3);
+        body.extend_from_slice(&self.id);
+        body.extend_from_slice(&self.hash);
+        body.extend_from_slice(&self.hash_16k);
+        body.extend_from_slice(&self.length.to_le_bytes());
+        body.extend_from_slice(&padded(self.name.as_bytes()));
+        body
+    }
+
+    fn checksums_body(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(16 + 20 * self.slices.len());
+        body.extend_from_slice(&self.id);
+        for (hash, crc) in self.slices.iter() {
+            body.extend_from_slice(hash);
+            body.extend_from_slice(&crc.to_le_bytes());
+        }
+        body
+    }
+}
+
+/// The files protected by a set of PAR2 volumes, along with the recovery slices at hand.
+#[derive(Debug, Clone)]
+pub struct RecoverySet {
+    id: Hash,
+    slice_size: usize,
+    /// Files in the order of their ids, which is the order of their slices
+    files: Vec<FileDescription>,
+    /// Recovery slices by exponent
+    recovery: BTreeMap<u32, Vec<u8>>,
+}
+
+impl RecoverySet {
+    /// Describes `files`, given as name and contents, and computes recovery slices with
+    /// exponents `0..recovery_slices`.
+    pub fn create(
+        files: &[(&str, &[u8])],
+        slice_size: usize,
+        recovery_slices: u32,
+    ) -> Result<Self, Errors> {
+        if slice_size == 0 || !slice_size.is_multiple_of(4) {
+            return Err(Errors::InvalidShardSize);
+        }
+
+        if files.is_empty() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        if recovery_slices > MAX_RECOVERY {
+            return Err(Errors::TooManyNumbers);
+        }
+
+        let mut descriptions: Vec<FileDescription> = files
+            .iter()
+            .map(|(name, data)| FileDescription::new(name, data, slice_size))
+            .collect();
+        descriptions.sort_by_key(|file| file.id);
+
+        let count: usize = descriptions.iter().map(|file| file.slices.len()).sum();
+        if count > MAX_SLICES {
+            return Err(Errors::TooManyNumbers);
+        }
+
+        let mut set = RecoverySet {
+            id: [0; 16],
+            slice_size,
+            files: descriptions,
+            recovery: BTreeMap::new(),
+        };
+        set.id = md5(&set.main_body());
+
+        let data: BTreeMap<&str, &[u8]> = files.iter().copied().collect();
+        let slices: Vec<Vec<u8>> = set
+            .files
+            .iter()
+            .flat_map(|file| {
+                data[file.name.as_str()].chunks(slice_size).map(|chunk| {
+                    let mut slice = chunk.to_vec();
+                    slice.resize(slice_size, 0);
+                    slice
+                })
+            })
+            .collect();
+        let constants = input_constants(slices.len());
+
+        for exponent in 0..recovery_slices {
+            let mut recovery = vec![0; slice_size];
+            for (slice, c) in slices.iter().zip(constants.iter()) {
+                mul_add(&mut recovery, slice, c.pow(exponent as usize));
+            }
+            set.recovery.insert(exponent, recovery);
+        }
+
+        Ok(set)
+    }
+
+    /// Collects the intact packets of `volumes`, any of which may be damaged or
+    /// truncated.
+    pub fn parse(volumes: &[&[u8]]) -> Result<Self, Errors> {
+        let packets: Vec<Packet> = volumes.iter().flat_map(|volume| packets(volume)).collect();
+
+        let (id, main) = packets
+            .iter()
+            .filter(|p| p.kind == MAIN && p.body.len() >= 12 && md5(p.body) == p.set_id)
+            .map(|p| (p.set_id, p.body))
+            .next()
+            .ok_or(Errors::InvalidFormat)?;
+
+        let slice_size = u64::from_le_bytes(main[..8].try_into().unwrap()) as usize;
+        let count = u32::from_le_bytes(main[8..12].try_into().unwrap()) as usize;
+        let ids: Vec<Hash> = main[12..]
+            .chunks_exact(16)
+            .take(count)
+            .map(|id| id.try_into().unwrap())
+            .collect();
+        if slice_size == 0 || !slice_size.is_multiple_of(4) || ids.len() != count {
+            return Err(Errors::InvalidFormat);
+        }
+
+        let mut descriptions = BTreeMap::new();
+        let mut checksums = BTreeMap::new();
+        let mut recovery = BTreeMap::new();
+        for p in packets.iter().filter(|p| p.set_id == id) {
+            match p.kind {
+                k if k == FILE_DESCRIPTION && p.body.len() >= 56 => {
+                    let id: Hash = p.body[..16].try_into().unwrap();
+                    descriptions.insert(id, p.body);
+                }
+                k if k == SLICE_CHECKSUMS && p.body.len() >= 16 => {
+                    let id: Hash = p.body[..16].try_into().unwrap();
+                    checksums.insert(id, &p.body[16..]);
+                }
+                k if k == RECOVERY_SLICE && p.body.len() == 4 + slice_size => {
+                    let exponent = u32::from_le_bytes(p.body[..4].try_into().unwrap());
+                    recovery.insert(exponent, p.body[4..].to_vec());
+                }
+                _ => {}
+            }
+        }
+
+        let files = ids
+            .iter()
+            .map(|id| {
+                let body = descriptions.get(id).ok_or(Errors::InvalidFormat)?;
+                let sums = checksums.get(id).ok_or(Errors::InvalidFormat)?;
+
+                let length = u64::from_le_bytes(body[48..56].try_into().unwrap());
+                let name = &body[56..];
+                let name = &name[..name.iter().rposition(|&b| b != 0).map_or(0, |p| p + 1)];
+
+                let slices: Vec<(Hash, u32)> = sums
+                    .chunks_exact(20)
+                    .map(|s| {
+                        let crc = u32::from_le_bytes(s[16..].try_into().unwrap());
+                        (s[..16].try_into().unwrap(), crc)
+                    })
+                    .collect();
+                if slices.len() as u64 != length.div_ceil(slice_size as u64) {
+                    return Err(Errors::InvalidFormat);
+                }
+
+                Ok(FileDescription {
+                    id: *id,
+                    hash: body[16..32].try_into().unwrap(),
+                    hash_16k: body[32..48].try_into().unwrap(),
+                    length,
+                    name: String::from_utf8_lossy(name).into_owned(),
+                    slices,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RecoverySet {
+            id,
+            slice_size,
+            files,
+            recovery,
+        })
+    }
+
+    /// Recovery set id, the MD5 of the main packet body
+    pub fn id(&self) -> &Hash {
+        &self.id
+    }
+
+    pub fn slice_size(&self) -> usize {
+        self.slice_size
+    }
+
+    pub fn files(&self) -> &[FileDescription] {
+        &self.files
+    }
+
+    /// Exponents of the recovery slices at hand
+    pub fn recovery_exponents(&self) -> Vec<u32> {
+        self.recovery.keys().copied().collect()
+    }
+
+    fn main_body(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(12 + 16 * self.files.len());
+        body.extend_from_slice(&(self.slice_size as u64).to_le_bytes());
+        body.extend_from_slice(&(self.files.len() as u32).to_le_bytes());
+        for file in self.files.iter() {
+            body.extend_from_slice(&file.id);
+        }
+        body
+    }
+
+    /// Main, file description, slice checksum and creator packets
+    fn critical_packets(&self) -> Vec<u8> {
+        let mut packets = packet(&self.id, MAIN, &self.main_body());
+        for file in self.files.iter() {
+            packets.extend(packet(&self.id, FILE_DESCRIPTION, &file.description_body()));
+            packets.extend(packet(&self.id, SLICE_CHECKSUMS, &file.checksums_body()));
+        }
+
+        let creator = concat!("shittyrs-rs ", env!("CARGO_PKG_VERSION"));
+        packets.extend(packet(&self.id, CREATOR, &padded(creator.as_bytes())));
+
+        packets
+    }
+
+    /// Contents of the index file, `name.par2`, which holds no recovery slices.
+    pub fn index_file(&self) -> Vec<u8> {
+        self.critical_packets()
+    }
+
+    /// Contents of a volume holding the recovery slices with exponents in `exponents`,
+    /// `name.volXX+YY.par2` by convention.
+    pub fn volume(&self, exponents: Range<u32>) -> Vec<u8> {
+        let mut volume = vec![];
+        for (exponent, slice) in self.recovery.range(exponents) {
+            let mut body = exponent.to_le_bytes().to_vec();
+            body.extend_from_slice(slice);
+            volume.extend(packet(&self.id, RECOVERY_SLICE, &body));
+        }
+        volume.extend(self.critical_packets());
+
+        volume
+    }
+
+    /// Input slices of `files` in set order, `None` for those missing or not matching
+    /// their checksums.
+    fn input_slices(&self, files: &BTreeMap<String, Vec<u8>>) -> Vec<Option<Vec<u8>>> {
+        let mut slices = vec![];
+
+        for file in self.files.iter() {
+            let data = files.get(&file.name).map_or(&[][..], |d| d.as_slice());
+            let data = &data[..data.len().min(file.length as usize)];
+
+            for (s, (hash, crc)) in file.slices.iter().enumerate() {
+                let start = (s * self.slice_size).min(data.len());
+                let end = ((s + 1) * self.slice_size).min(data.len());
+                let expected = ((s + 1) * self.slice_size).min(file.length as usize);
+
+                let mut slice = data[start..end].to_vec();
+                let complete = start + slice.len() == expected;
+                slice.resize(self.slice_size, 0);
+
+                let intact = complete && crc32(&slice) == *crc && md5(&slice) == *hash;
+                slices.push(intact.then_some(slice));
+            }
+        }
+
+        slices
+    }
+
+    /// Names of the files that are missing from `files` or do not match their hash.
+    pub fn verify(&self, files: &BTreeMap<String, Vec<u8>>) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter(|file| {
+                files
+                    .get(&file.name)
+                    .is_none_or(|data| md5(data) != file.hash)
+            })
+            .map(|file| file.name.as_str())
+            .collect()
+    }
+
+    /// Restores every damaged or missing file of `files` and returns the number of input
+    /// slices rebuilt.
+    pub fn repair(&self, files: &mut BTreeMap<String, Vec<u8>>) -> Result<usize, Errors> {
+        if self.verify(files).is_empty() {
+            return Ok(0);
+        }
+
+        let mut slices = self.input_slices(files);
+        let missing: Vec<usize> = (0..slices.len()).filter(|&i| slices[i].is_none()).collect();
+        if missing.len() > self.recovery.len() {
+            return Err(Errors::TooFewShards);
+        }
+
+        if !missing.is_empty() {
+            let constants = input_constants(slices.len());
+            let available: Vec<u32> = self.recovery.keys().copied().collect();
+
+            // `matrix * missing = recovery - sum of the intact slices`, row by exponent.
+            // Exponents congruent modulo 65535 give equal rows, so the first subset of
+            // recovery slices whose matrix inverts is used.
+            let mut subset: Vec<usize> = (0..missing.len()).collect();
+            let (exponents, inverse) = loop {
+                let exponents: Vec<u32> = subset.iter().map(|&r| available[r]).collect();
+                let matrix = Matrix::<GF2_16>::from_rows(
+                    exponents
+                        .iter()
+                        .map(|&e| {
+                            missing
+                                .iter()
+                                .map(|&i| constants[i].pow(e as usize))
+                                .collect()
+                        })
+                        .collect(),
+                )?;
+
+                match matrix.inverse() {
+                    Ok(inverse) => break (exponents, inverse),
+                    Err(Errors::SingularMatrix) if next_subset(&mut subset, available.len()) => {}
+                    Err(e) => return Err(e),
+                }
+            };
+
+            let residuals: Vec<Vec<u8>> = exponents
+                .iter()
+                .map(|&e| {
+                    let mut residual = self.recovery[&e].clone();
+                    for (slice, c) in slices.iter().zip(constants.iter()) {
+                        if let Some(slice) = slice {
+                            mul_add(&mut residual, slice, c.pow(e as usize));
+                        }
+                    }
+                    residual
+                })
+                .collect();
+
+            for (r, &i) in missing.iter().enumerate() {
+                let mut slice = vec![0; self.slice_size];
+                for (coeff, residual) in inverse.row(r).iter().zip(residuals.iter()) {
+                    mul_add(&mut slice, residual, *coeff);
+                }
+                slices[i] = Some(slice);
+            }
+        }
+
+        let mut slices = slices.into_iter().flatten();
+        for file in self.files.iter() {
+            let mut data: Vec<u8> = (&mut slices).take(file.slices.len()).flatten().collect();
+            data.truncate(file.length as usize);
+
+            if md5(&data) != file.hash {
+                return Err(Errors::TooManyErrors);
+            }
+            files.insert(file.name.clone(), data);
+        }
+
+        Ok(missing.len())
+    }
+}
+
+/// Advances `subset`, increasing indices into `0..n`, to the next subset of the same
+/// size in lexicographic order, returning `false` after the last one.
+fn next_subset(subset: &mut [usize], n: usize) -> bool {
+    let k = subset.len();
+    let Some(i) = (0..k).rev().find(|&i| subset[i] < n - k + i) else {
+        return false;
+    };
+
+    subset[i] += 1;
+    for j in i + 1..k {
+        subset[j] = subset[j - 1] + 1;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn files() -> BTreeMap<String, Vec<u8>> {
+        [
+            ("a.txt", (0..1000).map(|x| (x % 97) as u8).collect()),
+            (
+                "dir/b.bin",
+                (0..5000).map(|x| (x * 13 + x / 7) as u8).collect(),
+            ),
+            ("c", b"abc".to_vec()),
+        ]
+        .into_iter()
+        .map(|(name, data): (&str, Vec<u8>)| (name.to_string(), data))
+        .collect()
+    }
+
+    fn create(recovery: u32) -> RecoverySet {
+        let files = files();
+        let inputs: Vec<(&str, &[u8])> = files
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+
+        RecoverySet::create(&inputs, 512, recovery).unwrap()
+    }
+
+    #[test]
+    fn constants() {
+        let two = FE::<GF2_16>::new(2);
+        let expected: Vec<_> = [1, 2, 4, 7, 8, 11, 13, 14, 16, 19]
+            .iter()
+            .map(|&n| (&two).pow(n))
+            .collect();
+        assert_eq!(input_constants(10), expected);
+        assert_eq!(input_constants(usize::MAX).len(), MAX_SLICES);
+    }
+
+    #[test]
+    fn packet_layout() {
+        let set = create(2);
+        assert_eq!(set.files().len(), 3);
+        assert!(set.files().windows(2).all(|f| f[0].id < f[1].id));
+
+        let index = set.index_file();
+        let index_packets = packets(&index);
+        // main, 2 per file and creator
+        assert_eq!(index_packets.len(), 8);
+        assert_eq!(
+            index_packets
+                .iter()
+                .map(|p| 64 + p.body.len())
+                .sum::<usize>(),
+            index.len()
+        );
+        assert!(index_packets.iter().all(|p| p.set_id == *set.id()));
+        assert_eq!(index_packets[0].kind, MAIN);
+        assert_eq!(md5(index_packets[0].body), *set.id());
+
+        // the first recovery slice is the sum of all input slices
+        let mut sum = vec![0; 512];
+        for data in files().values() {
+            for chunk in data.chunks(512) {
+                for (s, b) in sum.iter_mut().zip(chunk) {
+                    *s ^= b;
+                }
+            }
+        }
+        let volume = set.volume(0..1);
+        let recovery = &packets(&volume)[0];
+        assert_eq!(recovery.kind, RECOVERY_SLICE);
+        assert_eq!(&recovery.body[..4], &[0; 4]);
+        assert_eq!(&recovery.body[4..], &sum[..]);
+    }
+
+    #[test]
+    fn repair() {
+        let set = create(4);
+        let (index, first, second) = (set.index_file(), set.volume(0..2), set.volume(2..4));
+        let parsed = RecoverySet::parse(&[&index, &first, &second]).unwrap();
+        assert_eq!(parsed.id(), set.id());
+        assert_eq!(parsed.files(), set.files());
+        assert_eq!(parsed.recovery_exponents(), vec![0, 1, 2, 3]);
+
+        let original = files();
+        let mut damaged = files();
+        assert!(parsed.verify(&damaged).is_empty());
+        assert_eq!(parsed.repair(&mut damaged), Ok(0));
+
+        damaged.remove("c");
+        damaged.get_mut("dir/b.bin").unwrap()[1600] ^= 1;
+        damaged.get_mut("a.txt").unwrap().truncate(600);
+        let mut names = parsed.verify(&damaged);
+        names.sort();
+        assert_eq!(names, vec!["a.txt", "c", "dir/b.bin"]);
+
+        assert_eq!(parsed.repair(&mut damaged), Ok(3));
+        assert_eq!(damaged, original);
+
+        // 5 damaged slices with only 4 recovery slices
+        for j in [0, 512, 1024, 1536, 2048] {
+            damaged.get_mut("dir/b.bin").unwrap()[j] ^= 1;
+        }
+        assert_eq!(parsed.repair(&mut damaged), Err(Errors::TooFewShards));
+
+        // without the second volume
+        let parsed = RecoverySet::parse(&[&index, &first]).unwrap();
+        assert_eq!(parsed.recovery_exponents(), vec![0, 1]);
+        damaged.remove("a.txt");
+        assert_eq!(parsed.repair(&mut damaged), Err(Errors::TooFewShards));
+    }
+
+    #[test]
+    fn repair_singular_exponents() {
+        let mut set = create(2);
+
+        // c^65535 = c^0 and c^65536 = c^1 for every constant, so the first two exponents
+        // give a singular matrix and the third has to stand in
+        let first = set.recovery[&0].clone();
+        let second = set.recovery.remove(&1).unwrap();
+        set.recovery.insert(65535, first);
+        set.recovery.insert(65536, second);
+
+        let mut damaged = files();
+        damaged.remove("c");
+        damaged.get_mut("a.txt").unwrap()[0] ^= 1;
+        assert_eq!(set.repair(&mut damaged), Ok(2));
+        assert_eq!(damaged, files());
+
+        let mut subset = vec![0, 1];
+        let mut subsets = vec![subset.clone()];
+        while next_subset(&mut subset, 4) {
+            subsets.push(subset.clone());
+        }
+        assert_eq!(
+            subsets,
+            [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]].map(Vec::from)
+        );
+    }
+
+    #[test]
+    fn damaged_volumes() {
+        let set = create(2);
+        let mut index = set.index_file();
+        let mut volume = set.volume(0..2);
+
+        // the main packet of the index and the first recovery slice of the volume
+        index[70] ^= 1;
+        volume[100] ^= 1;
+        let mut garbage = vec![0xaa; 37];
+        garbage.extend_from_slice(&volume);
+
+        let parsed = RecoverySet::parse(&[&index, &garbage]).unwrap();
+        assert_eq!(parsed.files(), set.files());
+        assert_eq!(parsed.recovery_exponents(), vec![1]);
+
+        let mut damaged = files();
+        damaged.get_mut("c").unwrap()[0] = b'x';
+        assert_eq!(parsed.repair(&mut damaged), Ok(1));
+        assert_eq!(damaged, files());
+
+        assert_eq!(
+            RecoverySet::parse(&[&index]).unwrap_err(),
+            Errors::InvalidFormat
+        );
+        assert_eq!(
+            RecoverySet::parse(&[&[0; 100]]).unwrap_err(),
+            Errors::InvalidFormat
+        );
+    }
+
+    /// Volumes written by par2cmdline-turbo 1.4.0, see `fixtures/par2/README.md`
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/par2");
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{FIXTURES}/{name}")).unwrap()
+    }
+
+    /// Distinct packets of `data` but the creator, which names the program, as
+    /// par2cmdline repeats packets and orders them its own way
+    fn packet_set(data: &[u8]) -> BTreeSet<(Hash, Vec<u8>, Vec<u8>)> {
+        packets(data)
+            .into_iter()
+            .filter(|p| p.kind != CREATOR)
+            .map(|p| (p.set_id, p.kind.to_vec(), p.body.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn par2cmdline_repair() {
+        let (index, volume) = (
+            fixture("testdata.bin.par2"),
+            fixture("testdata.bin.vol0+1.par2"),
+        );
+        let parsed = RecoverySet::parse(&[&index, &volume]).unwrap();
+        assert_eq!(parsed.slice_size(), 50000);
+        assert_eq!(parsed.recovery_exponents(), vec![0]);
+
+        let original = BTreeMap::from([("testdata.bin".to_string(), fixture("testdata.bin"))]);
+        let mut damaged = original.clone();
+        assert!(parsed.verify(&damaged).is_empty());
+
+        let data = damaged.get_mut("testdata.bin").unwrap();
+        data[100] ^= 1;
+        data[49999] ^= 0x80;
+        assert_eq!(parsed.verify(&damaged), ["testdata.bin"]);
+        assert_eq!(parsed.repair(&mut damaged), Ok(1));
+        assert_eq!(damaged, original);
+    }
+
+    #[test]
+    fn par2cmdline_missing_file() {
+        // a different file, rebuilt from the two recovery slices alone
+        let volumes = [
+            "missing/testdata.bin.par2",
+            "missing/testdata.bin.vol0+1.par2",
+            "missing/testdata.bin.vol1+1.par2",
+        ]
+        .map(fixture);
+        let volumes: Vec<&[u8]> = volumes.iter().map(Vec::as_slice).collect();
+        let parsed = RecoverySet::parse(&volumes).unwrap();
+        assert_eq!(parsed.recovery_exponents(), vec![0, 1]);
+
+        let mut files = BTreeMap::new();
+        assert_eq!(parsed.repair(&mut files), Ok(2));
+        assert_eq!(files["testdata.bin"].len(), 100000);
+        assert!(parsed.verify(&files).is_empty());
+
+        let mut files = BTreeMap::new();
+        assert_eq!(
+            RecoverySet::parse(&volumes[..2])
+                .unwrap()
+                .repair(&mut files),
+            Err(Errors::TooFewShards)
+        );
+    }
+
+    #[test]
+    fn par2cmdline_volumes() {
+        let data = fixture("testdata.bin");
+        let set = RecoverySet::create(&[("testdata.bin", &data)], 50000, 1).unwrap();
+
+        assert_eq!(
+            packet_set(&set.index_file()),
+            packet_set(&fixture("testdata.bin.par2"))
+        );
+        assert_eq!(
+            packet_set(&set.volume(0..1)),
+            packet_set(&fixture("testdata.bin.vol0+1.par2"))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let file: &[u8] = b"data";
+        assert_eq!(
+            RecoverySet::create(&[("f", file)], 6, 1).unwrap_err(),
+            Errors::InvalidShardSize
+        );
+        assert_eq!(
+            RecoverySet::create(&[], 4, 1).unwrap_err(),
+            Errors::InvalidShardCount
+        );
+        assert_eq!(
+            RecoverySet::create(&[("f", &[0; 4 * 40000])], 4, 1).unwrap_err(),
+            Errors::TooManyNumbers
+        );
+    }
+}
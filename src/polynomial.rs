@@ -13,6 +13,9 @@ pub trait PolynomialOps: Sized {
     /// Returns the number of coefficients in the polynomial
     fn len(&self) -> usize;
 
+    /// Returns whether the polynomial has no coefficients
+    fn is_empty(&self) -> bool;
+
     /// Negates a polynomial
     fn neg(&self) -> Self;
 
@@ -85,17 +88,21 @@ impl<F: Fp> PolynomialOps for Polynomial<FieldElement<F>> {
         self.coeffs.len()
     }
 
+    fn is_empty(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// Adds two polynomials, aligning coefficients on the lowest degree term.
     fn add(&self, other: &Self) -> Self {
-        // TODO: what if carry addition?
-        let mut coefficients: Vec<FieldElement<F>> =
-            vec![FieldElement::zero(); std::cmp::max(self.len(), other.len())];
+        let len = std::cmp::max(self.len(), other.len());
+        let mut coefficients: Vec<FieldElement<F>> = vec![FieldElement::zero(); len];
 
-        for i in 0..self.len() - 1 {
-            coefficients[i] += &self.coeffs[i];
+        for (i, x) in self.coeffs.iter().enumerate() {
+            coefficients[i + len - self.len()] += x;
         }
 
-        for i in 0..other.len() - 1 {
-            coefficients[i] += &other.coeffs[i];
+        for (i, x) in other.coeffs.iter().enumerate() {
+            coefficients[i + len - other.len()] += x;
         }
 
         Polynomial {
@@ -103,21 +110,21 @@ impl<F: Fp> PolynomialOps for Polynomial<FieldElement<F>> {
         }
     }
 
-    // TODO: return reference?
     fn neg(&self) -> Self {
-        return self.clone();
+        Polynomial {
+            coeffs: self.coeffs.iter().map(|x| -x).collect(),
+        }
     }
 
     fn sub(&self, other: &Self) -> Self {
-        // TODO: self + -other?
-        return self.add(&other.neg());
+        self.add(&other.neg())
     }
 
     /// Implements naive multiplication algorithm for numbers.
     ///
     /// **Note:** carryless addition as described in the Field.
     fn mul(&self, other: &Self) -> Self {
-        let mut coefficients = vec![FieldElement::zero(); self.len() + other.len()];
+        let mut coefficients = vec![FieldElement::zero(); self.len() + other.len() - 1];
 
         for i in (0..self.len()).rev() {
             if self.coeffs[i] == FieldElement::zero() {
@@ -128,7 +135,7 @@ impl<F: Fp> PolynomialOps for Polynomial<FieldElement<F>> {
                     continue;
                 }
 
-                coefficients[i + j + 1] += &self.coeffs[i] * &other.coeffs[j];
+                coefficients[i + j] += self.coeffs[i] * other.coeffs[j];
             }
         }
 
@@ -138,30 +145,51 @@ impl<F: Fp> PolynomialOps for Polynomial<FieldElement<F>> {
     }
 
     /// Implements Synthetic division method along with some optimisations mentioned [here](https://research.swtch.com/field).
+    ///
+    /// The remainder always has exactly `divisor.degree()` coefficients, so it can be appended
+    /// directly as parity symbols.
     /// TODO: should return Result<Self, Errors>?
     fn div(&self, divisor: &Self) -> (Self, Self) {
+        let rem_len = divisor.len() - 1;
+
+        if self.len() < divisor.len() {
+            let mut remainder = vec![FieldElement::zero(); rem_len - self.len()];
+            remainder.extend_from_slice(&self.coeffs);
+
+            return (
+                Polynomial {
+                    coeffs: vec![FieldElement::zero()],
+                },
+                Polynomial { coeffs: remainder },
+            );
+        }
+
         let mut coefficients = self.coeffs.clone();
+        let lead = divisor.coeffs[0];
 
         for i in 0..(self.len() - divisor.len() + 1) {
-            if self.coeffs[i] == FieldElement::zero() {
+            if coefficients[i] == FieldElement::zero() {
                 continue;
             }
 
+            coefficients[i] /= lead;
+            let coeff = coefficients[i];
+
             for j in 1..(divisor.len()) {
-                coefficients[i + j] += &divisor.coeffs[j] * &self.coeffs[i];
+                coefficients[i + j] -= divisor.coeffs[j] * coeff;
             }
         }
 
-        let (quotient, remainder) = coefficients.split_at(self.len() - (divisor.len() + 1));
+        let (quotient, remainder) = coefficients.split_at(self.len() - rem_len);
 
-        return (
+        (
             Polynomial {
                 coeffs: quotient.to_vec(),
             },
             Polynomial {
                 coeffs: remainder.to_vec(),
             },
-        );
+        )
     }
 
     fn rem(&self, divisor: &Self) -> Self {
@@ -221,10 +249,7 @@ mod tests {
 
         let poly3 = poly1.mul(&poly2);
 
-        assert_eq!(
-            poly3.coeffs,
-            vec![Elem::one(), Elem::new(20), Elem::new(100)]
-        );
+        assert_eq!(poly3.coeffs, vec![Elem::one(), Elem::new(3), Elem::new(2)]);
     }
 
     #[test]
@@ -0,0 +1,311 @@
+use crate::backend::gf256::CcsdsField;
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::reedsolomon::{Decoded, ReedSolomon};
+
+/// Message symbols of a single codeword
+pub const MESSAGE_SIZE: usize = 223;
+
+/// Symbols of a single codeword
+pub const CODEWORD_SIZE: usize = 255;
+
+/// Largest interleaving depth allowed by the standard
+pub const MAX_INTERLEAVE: usize = 8;
+
+/// Rows of the matrix `Tα` converting the conventional representation into Berlekamp's
+/// dual basis, CCSDS 131.0-B section 4.
+const T_ALPHA: [u8; 8] = [0x8d, 0xef, 0xec, 0x86, 0xfa, 0x99, 0xaf, 0x7b];
+
+/// Lookup tables converting conventional symbols to dual basis and back
+const fn dual_basis_tables() -> ([u8; 256], [u8; 256]) {
+    let mut to_dual = [0u8; 256];
+    let mut to_conventional = [0u8; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut z = 0;
+        let mut bit = 0;
+        while bit < 8 {
+            if i & (1 << bit) != 0 {
+                z ^= T_ALPHA[7 - bit];
+            }
+            bit += 1;
+        }
+
+        to_dual[i] = z;
+        to_conventional[z as usize] = i as u8;
+        i += 1;
+    }
+
+    (to_dual, to_conventional)
+}
+
+const DUAL_BASIS: ([u8; 256], [u8; 256]) = dual_basis_tables();
+
+/// Representation of the symbols on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Basis {
+    /// Powers of α, the representation the codec works in
+    Conventional,
+    /// Berlekamp's dual basis mandated by the standard
+    Dual,
+}
+
+/// Converts a symbol from the conventional representation to the dual basis.
+pub fn to_dual(symbol: u8) -> u8 {
+    DUAL_BASIS.0[symbol as usize]
+}
+
+/// Converts a symbol from the dual basis to the conventional representation.
+pub fn to_conventional(symbol: u8) -> u8 {
+    DUAL_BASIS.1[symbol as usize]
+}
+
+/// RS(255, 223) of the CCSDS TM synchronization and channel coding standard
+/// (CCSDS 131.0-B), with `I` codewords interleaved symbol by symbol into a codeblock.
+///
+/// Shorter messages are encoded with virtual fill, i.e. the missing leading symbols of
+/// every codeword are taken to be zero and not transmitted.
+#[derive(Debug)]
+pub struct Ccsds {
+    rs: ReedSolomon<CcsdsField>,
+    interleave: usize,
+    basis: Basis,
+}
+
+impl Ccsds {
+    pub fn new(interleave: usize, basis: Basis) -> Result<Self, Errors> {
+        if interleave == 0 || interleave > MAX_INTERLEAVE {
+            return Err(Errors::InvalidProfile);
+        }
+
+        let rs = ReedSolomon::builder(CODEWORD_SIZE, MESSAGE_SIZE, FE::new(2))
+            .first_root(112)
+            .root_step(11)
+            .field_polynomial(0x187)
+            .build()?;
+
+        Ok(Ccsds {
+            rs,
+            interleave,
+            basis,
+        })
+    }
+
+    pub fn interleave(&self) -> usize {
+        self.interleave
+    }
+
+    pub fn basis(&self) -> Basis {
+        self.basis
+    }
+
+    /// Message bytes of a codeblock without virtual fill
+    pub fn message_size(&self) -> usize {
+        MESSAGE_SIZE * self.interleave
+    }
+
+    /// Bytes of a codeblock without virtual fill
+    pub fn block_size(&self) -> usize {
+        CODEWORD_SIZE * self.interleave
+    }
+
+    /// Parity bytes appended to every codeblock
+    pub fn parity_size(&self) -> usize {
+        (CODEWORD_SIZE - MESSAGE_SIZE) * self.interleave
+    }
+
+    fn to_codec(&self, symbol: u8) -> u8 {
+        match self.basis {
+            Basis::Conventional => symbol,
+            Basis::Dual => to_conventional(symbol),
+        }
+    }
+
+    fn to_wire(&self, symbol: u8) -> u8 {
+        match self.basis {
+            Basis::Conventional => symbol,
+            Basis::Dual => to_dual(symbol),
+        }
+    }
+
+    /// Splits an interleaved block into its codewords, converted to the conventional
+    /// representation.
+    fn deinterleave(&self, block: &[u8]) -> Vec<Vec<u8>> {
+        (0..self.interleave)
+            .map(|i| {
+                block
+                    .iter()
+                    .skip(i)
+                    .step_by(self.interleave)
+                    .map(|&s| self.to_codec(s))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Encodes `message`, a multiple of `I` bytes up to `223·I`, into a codeblock of the
+    /// message followed by `32·I` interleaved parity bytes.
+    pub fn encode(&self, message: &[u8]) -> Result<Vec<u8>, Errors> {
+        if message.is_empty()
+            || message.len() > self.message_size()
+            || !message.len().is_multiple_of(self.interleave)
+        {
+            return Err(Errors::InvalidMessage);
+        }
+
+        let mut block = message.to_vec();
+        block.resize(message.len() + self.parity_size(), 0);
+
+        let codewords = self.deinterleave(message);
+        let parity_start = message.len();
+        for (i, codeword) in codewords.iter().enumerate() {
+            let mut parity = vec![0; CODEWORD_SIZE - MESSAGE_SIZE];
+            self.rs.encode_into(codeword, &mut parity)?;
+
+            for (m, p) in parity.into_iter().enumerate() {
+                block[parity_start + m * self.interleave + i] = self.to_wire(p);
+            }
+        }
+
+        Ok(block)
+    }
+
+    /// Corrects up to 16 symbol errors in each of the `I` codewords of `block` and returns
+    /// the message, along with the corrected positions within the block.
+    pub fn decode(&self, block: &[u8]) -> Result<Decoded, Errors> {
+        if block.len() <= self.parity_size()
+            || block.len() > self.block_size()
+            || !block.len().is_multiple_of(self.interleave)
+        {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        let mut message = block[..block.len() - self.parity_size()].to_vec();
+        let mut errors = vec![];
+
+        for (i, codeword) in self.deinterleave(block).iter().enumerate() {
+            let decoded = self.rs.decode(codeword)?;
+
+            for (m, s) in decoded.message.into_iter().enumerate() {
+                message[m * self.interleave + i] = self.to_wire(s);
+            }
+            errors.extend(decoded.errors.iter().map(|m| m * self.interleave + i));
+        }
+        errors.sort_unstable();
+
+        Ok(Decoded {
+            message,
+            errors,
+            erasures: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::Pow;
+
+    use super::*;
+
+    #[test]
+    fn generator_poly() {
+        // coefficients G_0..G_32 of g(x) as powers of α, CCSDS 131.0-B section 4
+        let exponents = [
+            0, 249, 59, 66, 4, 43, 126, 251, 97, 30, 3, 213, 50, 66, 170, 5, 24, 5, 170, 66, 50,
+            213, 3, 30, 97, 251, 126, 43, 4, 66, 59, 249, 0,
+        ];
+        let ccsds = Ccsds::new(1, Basis::Conventional).unwrap();
+        let alpha = FE::<CcsdsField>::new(2);
+
+        for (c, e) in ccsds.rs.generator_poly().coeffs.iter().zip(exponents) {
+            assert_eq!(*c, alpha.pow(e));
+        }
+    }
+
+    #[test]
+    fn received_codeword() {
+        // a codeword of a Suomi NPP frame, from the tests of the ccsds crate 0.1.0-beta.25
+        const CODEWORD: [u8; 255] = [
+            0x67, 0xc4, 0x6b, 0xa7, 0x3e, 0xbe, 0x4c, 0x33, 0x6c, 0xb2, 0x23, 0x3a, 0x74, 0x06,
+            0x2b, 0x18, 0xab, 0xb8, 0x09, 0xe6, 0x7d, 0xaf, 0x5d, 0xe5, 0xdf, 0x76, 0x25, 0x3f,
+            0xb9, 0x14, 0xee, 0xec, 0xd1, 0xa3, 0x39, 0x5f, 0x38, 0x68, 0xf0, 0x26, 0xa6, 0x8a,
+            0xcb, 0x09, 0xaf, 0x4e, 0xf8, 0x93, 0xf7, 0x45, 0x4b, 0x0d, 0xa9, 0xb8, 0x74, 0x0e,
+            0xf3, 0xc7, 0xed, 0x6e, 0xa3, 0x0f, 0xf6, 0x79, 0x94, 0x16, 0xe2, 0x7f, 0xad, 0x91,
+            0x91, 0x04, 0xac, 0xa4, 0xae, 0xb4, 0x51, 0x76, 0x2f, 0x62, 0x03, 0x5e, 0xa1, 0xe5,
+            0x5c, 0x45, 0xf8, 0x1f, 0x7a, 0x7b, 0xe8, 0x35, 0xd8, 0xcc, 0x51, 0x0e, 0xae, 0x3a,
+            0x2a, 0x64, 0x1d, 0x03, 0x10, 0xcd, 0x18, 0xe6, 0x7f, 0xef, 0xba, 0xd9, 0xe8, 0x98,
+            0x47, 0x82, 0x9c, 0xa1, 0x58, 0x47, 0x25, 0xdf, 0x41, 0xd2, 0x01, 0x62, 0x3c, 0x24,
+            0x88, 0x90, 0xe9, 0xd7, 0x38, 0x1b, 0xa0, 0xa2, 0xb4, 0x23, 0xea, 0x7e, 0x58, 0x0d,
+            0xf4, 0x61, 0x24, 0x14, 0xb0, 0x41, 0x90, 0x0c, 0xb7, 0xbb, 0x5c, 0x59, 0x1b, 0xc6,
+            0x69, 0x24, 0x0f, 0xb6, 0x0e, 0x14, 0xa1, 0xb1, 0x8e, 0x48, 0x0f, 0x17, 0x1d, 0xfb,
+            0x0f, 0x38, 0x42, 0xe3, 0x24, 0x58, 0xab, 0x82, 0xa8, 0xfd, 0xdf, 0xac, 0x68, 0x93,
+            0x3d, 0x0d, 0x8f, 0x50, 0x52, 0x44, 0x6c, 0xba, 0xd3, 0x51, 0x99, 0x9c, 0x3e, 0xad,
+            0xd5, 0xa8, 0xd7, 0x9d, 0xc7, 0x7f, 0x9f, 0xc9, 0x2a, 0xac, 0xe5, 0xc2, 0xcd, 0x9a,
+            0x9b, 0xfa, 0x2d, 0x72, 0xab, 0x6b, 0xa4, 0x6b, 0x8b, 0x7d, 0xfa, 0x6c, 0x83, 0x63,
+            0x77, 0x9f, 0x4e, 0x9a, 0x20, 0x35, 0xd2, 0x91, 0xce, 0xf4, 0x21, 0x1a, 0x97, 0x3c,
+            0x1a, 0x15, 0x9d, 0xfc, 0x98, 0xba, 0x72, 0x1b, 0x9a, 0xa2, 0xe9, 0xc9, 0x46, 0x68,
+            0xce, 0xad, 0x27,
+        ];
+        let ccsds = Ccsds::new(1, Basis::Dual).unwrap();
+
+        assert_eq!(ccsds.encode(&CODEWORD[..223]).unwrap(), CODEWORD);
+        assert!(ccsds.decode(&CODEWORD).unwrap().errors.is_empty());
+    }
+
+    #[test]
+    fn dual_basis() {
+        // the basis vectors α^7..α^0 map onto the rows of Tα
+        for (bit, row) in (0..8).rev().zip(T_ALPHA) {
+            assert_eq!(to_dual(1 << bit), row);
+        }
+        for s in 0..=255 {
+            assert_eq!(to_conventional(to_dual(s)), s);
+        }
+    }
+
+    #[test]
+    fn interleaved() {
+        for interleave in 1..=MAX_INTERLEAVE {
+            for basis in [Basis::Conventional, Basis::Dual] {
+                let ccsds = Ccsds::new(interleave, basis).unwrap();
+                let message: Vec<u8> = (0..ccsds.message_size())
+                    .map(|x| (x * 7 + x / 255) as u8)
+                    .collect();
+                let mut block = ccsds.encode(&message).unwrap();
+                assert_eq!(block.len(), ccsds.block_size());
+                assert_eq!(&block[..message.len()], &message[..]);
+
+                // a burst of 16·I bytes leaves 16 errors in every codeword
+                let burst: Vec<usize> = (100..100 + 16 * interleave).collect();
+                for &j in burst.iter() {
+                    block[j] ^= 0x5a;
+                }
+
+                let decoded = ccsds.decode(&block).unwrap();
+                assert_eq!(decoded.message, message);
+                assert_eq!(decoded.errors, burst);
+            }
+        }
+    }
+
+    #[test]
+    fn virtual_fill() {
+        let ccsds = Ccsds::new(4, Basis::Dual).unwrap();
+        let message = vec![0x42; 400];
+        let block = ccsds.encode(&message).unwrap();
+
+        let mut padded = vec![0; ccsds.message_size() - 400];
+        padded.extend_from_slice(&message);
+        let full = ccsds.encode(&padded).unwrap();
+
+        assert_eq!(block.len(), 400 + 128);
+        assert_eq!(block[400..], full[ccsds.message_size()..]);
+        assert_eq!(ccsds.decode(&block).unwrap().message, message);
+        assert_eq!(ccsds.encode(&message[1..]), Err(Errors::InvalidMessage));
+        assert!(matches!(
+            Ccsds::new(9, Basis::Dual),
+            Err(Errors::InvalidProfile)
+        ));
+    }
+}
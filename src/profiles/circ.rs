@@ -0,0 +1,183 @@
+use crate::backend::gf256::QrField;
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::reedsolomon::ReedSolomon;
+
+/// Data bytes of a frame
+pub const FRAME_SIZE: usize = 24;
+
+/// Bytes of an encoded frame
+pub const ENCODED_FRAME_SIZE: usize = 32;
+
+/// Symbols of the outer C2 codewords
+const C2_SIZE: usize = 28;
+
+/// Frames between consecutive symbols of a C2 codeword
+const DELAY: usize = 4;
+
+/// CD-style cross-interleaved Reed-Solomon code.
+///
+/// Every frame of 24 bytes is encoded with the outer C2 code RS(28, 24), the symbols of
+/// the C2 codeword are spread over 28 frames by delaying symbol `i` by `4i` frames, and
+/// every resulting frame is encoded with the inner C1 code RS(32, 28). Both codes are
+/// shortened from RS(255, 251) over `x^8 + x^4 + x^3 + x^2 + 1` with roots `α^0..α^3`.
+///
+/// On decoding, frames that C1 cannot correct are passed on as erasures, which lets C2
+/// fill in bursts of up to 16 consecutive frames. The audio-specific parts of the
+/// compact disc format, namely the even/odd sample delays, the placement of the C2 parity
+/// in the middle of the frame and the inversion of parity symbols, are left out.
+#[derive(Debug)]
+pub struct Circ {
+    c1: ReedSolomon<QrField>,
+    c2: ReedSolomon<QrField>,
+}
+
+impl Circ {
+    pub fn new() -> Result<Self, Errors> {
+        let code = |len| {
+            ReedSolomon::builder(255, 251, FE::new(2))
+                .first_root(0)
+                .field_polynomial(0x11d)
+                .shortened(len)
+                .build()
+        };
+
+        Ok(Circ {
+            c1: code(ENCODED_FRAME_SIZE)?,
+            c2: code(C2_SIZE)?,
+        })
+    }
+
+    /// Frames needed to flush the interleaver
+    pub fn latency(&self) -> usize {
+        DELAY * (C2_SIZE - 1)
+    }
+
+    /// Encodes `data`, a whole number of frames, into `32` bytes for each of its frames
+    /// plus [`Circ::latency`] trailing frames.
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>, Errors> {
+        if data.is_empty() || !data.len().is_multiple_of(FRAME_SIZE) {
+            return Err(Errors::InvalidMessage);
+        }
+
+        let frames = data.len() / FRAME_SIZE + self.latency();
+        let mut interleaved = vec![0; frames * C2_SIZE];
+        for (t, frame) in data.chunks(FRAME_SIZE).enumerate() {
+            let codeword = self.c2.encode(frame)?;
+            for (i, c) in codeword.into_iter().enumerate() {
+                interleaved[(t + DELAY * i) * C2_SIZE + i] = c;
+            }
+        }
+
+        let mut encoded = Vec::with_capacity(frames * ENCODED_FRAME_SIZE);
+        for frame in interleaved.chunks(C2_SIZE) {
+            encoded.extend_from_slice(&self.c1.encode(frame)?);
+        }
+
+        Ok(encoded)
+    }
+
+    /// Decodes the output of [`Circ::encode`], failing with [`Errors::TooManyErrors`] if
+    /// a C2 codeword cannot be recovered.
+    pub fn decode(&self, encoded: &[u8]) -> Result<Vec<u8>, Errors> {
+        if !encoded.len().is_multiple_of(ENCODED_FRAME_SIZE)
+            || encoded.len() / ENCODED_FRAME_SIZE <= self.latency()
+        {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        // C1 frames, or None where C1 failed
+        let frames: Vec<Option<Vec<u8>>> = encoded
+            .chunks(ENCODED_FRAME_SIZE)
+            .map(|frame| self.c1.decode(frame).ok().map(|d| d.message))
+            .collect();
+
+        let count = frames.len() - self.latency();
+        let mut data = Vec::with_capacity(count * FRAME_SIZE);
+        for t in 0..count {
+            let mut codeword = vec![0; C2_SIZE];
+            let mut erasures = vec![];
+            for (i, c) in codeword.iter_mut().enumerate() {
+                match &frames[t + DELAY * i] {
+                    Some(frame) => *c = frame[i],
+                    None => erasures.push(i),
+                }
+            }
+
+            let decoded = self.c2.decode_with_erasures(&codeword, &erasures)?;
+            data.extend_from_slice(&decoded.message);
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(frames: usize) -> Vec<u8> {
+        (0..frames * FRAME_SIZE)
+            .map(|x| (x * 37 + x / 256) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn encode_decode() {
+        let circ = Circ::new().unwrap();
+        let data = data(50);
+        let encoded = circ.encode(&data).unwrap();
+
+        assert_eq!(encoded.len(), (50 + 108) * ENCODED_FRAME_SIZE);
+        assert_eq!(circ.decode(&encoded).unwrap(), data);
+        assert_eq!(circ.encode(&data[1..]), Err(Errors::InvalidMessage));
+    }
+
+    #[test]
+    fn known_frames() {
+        // C2 codeword of the first frame and the last encoded frame, whose symbols come
+        // from 28 different C2 codewords, with both codes computed by `Encoder::new(4)`
+        // of the reed-solomon crate 0.2.1
+        let c2 = [
+            0x00, 0x25, 0x4a, 0x6f, 0x94, 0xb9, 0xde, 0x03, 0x28, 0x4d, 0x72, 0x97, 0xbc, 0xe1,
+            0x06, 0x2b, 0x50, 0x75, 0x9a, 0xbf, 0xe4, 0x09, 0x2e, 0x53, 0x29, 0x03, 0x40, 0xfa,
+        ];
+        let c1 = [
+            0xaa, 0xee, 0x33, 0x78, 0xbc, 0x01, 0x45, 0x8a, 0xcf, 0x13, 0x58, 0x9d, 0xe1, 0x26,
+            0x6a, 0xaf, 0xf4, 0x38, 0x7d, 0xc2, 0x06, 0x4b, 0x8f, 0xd4, 0xbc, 0x9f, 0x5a, 0xfa,
+            0x9c, 0xf2, 0xdf, 0x31,
+        ];
+        let circ = Circ::new().unwrap();
+        let encoded = circ.encode(&data(109)).unwrap();
+
+        for (i, &c) in c2.iter().enumerate() {
+            assert_eq!(encoded[DELAY * i * ENCODED_FRAME_SIZE + i], c);
+        }
+        assert_eq!(
+            encoded[108 * ENCODED_FRAME_SIZE..109 * ENCODED_FRAME_SIZE],
+            c1
+        );
+    }
+
+    #[test]
+    fn burst() {
+        let circ = Circ::new().unwrap();
+        let data = data(100);
+        let mut encoded = circ.encode(&data).unwrap();
+
+        // 16 consecutive frames garbled, plus scattered errors C1 corrects on its own
+        for (j, byte) in encoded[40 * 32..56 * 32].iter_mut().enumerate() {
+            *byte ^= (j * 13) as u8 | 1;
+        }
+        for frame in [3, 20, 90, 150] {
+            encoded[frame * 32 + 5] ^= 0x0f;
+        }
+        assert_eq!(circ.decode(&encoded).unwrap(), data);
+
+        // one more frame leaves five erasures in some C2 codewords
+        for byte in encoded[56 * 32..57 * 32].iter_mut() {
+            *byte ^= 0xa5;
+        }
+        assert_eq!(circ.decode(&encoded), Err(Errors::TooManyErrors));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::backend::gf256::DataMatrixField;
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::reedsolomon::{Decoded, ReedSolomon};
+
+/// Rows, columns, data codewords, error correction codewords and interleaved blocks of
+/// the ECC 200 symbols, ISO/IEC 16022 table 7
+const SYMBOLS: [(usize, usize, usize, usize, usize); 30] = [
+    (10, 10, 3, 5, 1),
+    (12, 12, 5, 7, 1),
+    (14, 14, 8, 10, 1),
+    (16, 16, 12, 12, 1),
+    (18, 18, 18, 14, 1),
+    (20, 20, 22, 18, 1),
+    (22, 22, 30, 20, 1),
+    (24, 24, 36, 24, 1),
+    (26, 26, 44, 28, 1),
+    (32, 32, 62, 36, 1),
+    (36, 36, 86, 42, 1),
+    (40, 40, 114, 48, 1),
+    (44, 44, 144, 56, 1),
+    (48, 48, 174, 68, 1),
+    (52, 52, 204, 84, 2),
+    (64, 64, 280, 112, 2),
+    (72, 72, 368, 144, 4),
+    (80, 80, 456, 192, 4),
+    (88, 88, 576, 224, 4),
+    (96, 96, 696, 272, 4),
+    (104, 104, 816, 336, 6),
+    (120, 120, 1050, 408, 6),
+    (132, 132, 1304, 496, 8),
+    (144, 144, 1558, 620, 10),
+    (8, 18, 5, 7, 1),
+    (8, 32, 10, 11, 1),
+    (12, 26, 16, 14, 1),
+    (12, 36, 22, 18, 1),
+    (16, 36, 32, 24, 1),
+    (16, 48, 49, 28, 1),
+];
+
+/// Error correction blocks of a Data Matrix ECC 200 symbol of a given size.
+///
+/// Codewords are dealt to the blocks in turn, so block `b` holds every codeword at a
+/// position congruent to `b` modulo the number of blocks, both among the data and the
+/// error correction codewords. Each block is encoded over `x^8 + x^5 + x^3 + x^2 + 1`
+/// with roots `α^1..α^e`.
+#[derive(Debug)]
+pub struct DataMatrixBlocks {
+    rs: ReedSolomon<DataMatrixField>,
+    rows: usize,
+    columns: usize,
+    data_size: usize,
+    blocks: usize,
+}
+
+impl DataMatrixBlocks {
+    pub fn new(rows: usize, columns: usize) -> Result<Self, Errors> {
+        let &(_, _, data_size, ec_total, blocks) = SYMBOLS
+            .iter()
+            .find(|&&(r, c, ..)| (r, c) == (rows, columns))
+            .ok_or(Errors::InvalidProfile)?;
+
+        // only 144x144 has blocks of unequal length, the first ones holding one more
+        let longest = data_size.div_ceil(blocks);
+        let ec_size = ec_total / blocks;
+        let rs = ReedSolomon::builder(longest + ec_size, longest, FE::new(2))
+            .field_polynomial(0x12d)
+            .build()?;
+
+        Ok(DataMatrixBlocks {
+            rs,
+            rows,
+            columns,
+            data_size,
+            blocks,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Number of interleaved blocks
+    pub fn blocks(&self) -> usize {
+        self.blocks
+    }
+
+    /// Error correction codewords of every block
+    pub fn ec_size(&self) -> usize {
+        self.rs.parity_size()
+    }
+
+    /// Data codewords of the symbol
+    pub fn data_size(&self) -> usize {
+        self.data_size
+    }
+
+    /// Codewords of the symbol
+    pub fn total_size(&self) -> usize {
+        self.data_size + self.blocks * self.ec_size()
+    }
+
+    /// Data codewords of block `b`
+    fn block_size(&self, b: usize) -> usize {
+        (self.data_size - b).div_ceil(self.blocks)
+    }
+
+    /// Maps the `m`-th codeword of block `b` to its position in the symbol.
+    fn position(&self, b: usize, m: usize) -> usize {
+        let len = self.block_size(b);
+
+        if m < len {
+            m * self.blocks + b
+        } else {
+            self.data_size + (m - len) * self.blocks + b
+        }
+    }
+
+    /// Encodes exactly [`DataMatrixBlocks::data_size`] data codewords into the codewords
+    /// of the symbol.
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>, Errors> {
+        if data.len() != self.data_size {
+            return Err(Errors::InvalidMessage);
+        }
+
+        let mut symbol = vec![0; self.total_size()];
+        for b in 0..self.blocks {
+            let block: Vec<u8> = data.iter().skip(b).step_by(self.blocks).copied().collect();
+            for (m, c) in self.rs.encode(&block)?.into_iter().enumerate() {
+                symbol[self.position(b, m)] = c;
+            }
+        }
+
+        Ok(symbol)
+    }
+
+    /// Corrects the codewords of a symbol block by block and returns the data codewords,
+    /// along with the corrected positions within the symbol.
+    pub fn decode(&self, symbol: &[u8]) -> Result<Decoded, Errors> {
+        if symbol.len() != self.total_size() {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        let mut message = vec![0; self.data_size];
+        let mut errors = vec![];
+        for b in 0..self.blocks {
+            let codeword: Vec<u8> = (0..self.block_size(b) + self.ec_size())
+                .map(|m| symbol[self.position(b, m)])
+                .collect();
+
+            let decoded = self.rs.decode(&codeword)?;
+            for (m, c) in decoded.message.into_iter().enumerate() {
+                message[self.position(b, m)] = c;
+            }
+            errors.extend(decoded.errors.into_iter().map(|m| self.position(b, m)));
+        }
+        errors.sort_unstable();
+
+        Ok(Decoded {
+            message,
+            errors,
+            erasures: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_structure() {
+        let symbol = DataMatrixBlocks::new(10, 10).unwrap();
+        assert_eq!((symbol.total_size(), symbol.data_size()), (8, 3));
+
+        let symbol = DataMatrixBlocks::new(144, 144).unwrap();
+        assert_eq!(symbol.blocks(), 10);
+        assert_eq!(symbol.ec_size(), 62);
+        assert_eq!(symbol.total_size(), 2178);
+        assert_eq!(symbol.block_size(7), 156);
+        assert_eq!(symbol.block_size(8), 155);
+
+        assert_eq!(DataMatrixBlocks::new(16, 48).unwrap().total_size(), 77);
+        assert!(matches!(
+            DataMatrixBlocks::new(11, 11),
+            Err(Errors::InvalidProfile)
+        ));
+    }
+
+    #[test]
+    fn annex_example() {
+        // "123456" in a 10x10 symbol, ISO/IEC 16022 annex O, whose 5 check codewords come
+        // from the generator polynomial of annex E
+        let symbol = DataMatrixBlocks::new(10, 10).unwrap();
+        let coeffs = [1, 62, 111, 15, 48, 228];
+        for (c, e) in symbol.rs.generator_poly().coeffs.iter().zip(coeffs) {
+            assert_eq!(*c, FE::new(e));
+        }
+
+        let codewords = symbol.encode(&[142, 164, 186]).unwrap();
+        assert_eq!(codewords, [142, 164, 186, 114, 25, 5, 88, 102]);
+    }
+
+    #[test]
+    fn interleaved_blocks() {
+        let symbol = DataMatrixBlocks::new(144, 144).unwrap();
+        let data: Vec<u8> = (0..1558).map(|x| (x * 7) as u8).collect();
+        let mut codewords = symbol.encode(&data).unwrap();
+
+        // the last data row only covers the 8 longer blocks
+        assert_eq!(&codewords[1550..1558], &data[1550..]);
+
+        let damaged = [0, 9, 1557, 1558, 1567, 2177];
+        for &j in damaged.iter() {
+            codewords[j] ^= 0x5a;
+        }
+        let decoded = symbol.decode(&codewords).unwrap();
+        assert_eq!(decoded.message, data);
+        assert_eq!(decoded.errors, damaged);
+    }
+}
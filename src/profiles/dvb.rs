@@ -0,0 +1,76 @@
+use crate::backend::gf256::QrField;
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::reedsolomon::ReedSolomon;
+
+/// Size of an MPEG-2 transport stream packet
+pub const PACKET_SIZE: usize = 188;
+
+/// Size of a packet protected by 16 parity bytes
+pub const CODEWORD_SIZE: usize = 204;
+
+/// Outer code of DVB-S/T/C (ETSI EN 300 421): RS(204, 188) shortened from RS(255, 239)
+/// over `x^8 + x^4 + x^3 + x^2 + 1`, with generator polynomial roots `λ^0..λ^15` for
+/// `λ = 02h`.
+pub fn outer_code() -> Result<ReedSolomon<QrField>, Errors> {
+    ReedSolomon::builder(255, 239, FE::new(2))
+        .first_root(0)
+        .field_polynomial(0x11d)
+        .shortened(CODEWORD_SIZE)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generator_poly() {
+        // g(x) = (x + λ^0)(x + λ^1)...(x + λ^15) of EN 300 421, as tabulated in the tests
+        // of the reed-solomon crate 0.2.1, which implements the same code
+        let coeffs = [
+            1, 59, 13, 104, 189, 68, 209, 30, 8, 163, 65, 41, 229, 98, 50, 36, 59,
+        ];
+        let rs = outer_code().unwrap();
+
+        assert_eq!(rs.total_size(), CODEWORD_SIZE);
+        assert_eq!(rs.parity_size(), 16);
+        for (c, e) in rs.generator_poly().coeffs.iter().zip(coeffs) {
+            assert_eq!(*c, FE::new(e));
+        }
+    }
+
+    #[test]
+    fn null_packet() {
+        // parity of the null packet, PID 0x1fff, as encoded by `Encoder::new(16)` of the
+        // reed-solomon crate 0.2.1
+        let parity = [
+            0x43, 0xbf, 0x42, 0xc1, 0xe1, 0x18, 0xf8, 0x7f, 0x23, 0x90, 0xba, 0x66, 0x7d, 0xa8,
+            0x62, 0x6e,
+        ];
+        let mut packet = vec![0x47, 0x1f, 0xff, 0x10];
+        packet.resize(PACKET_SIZE, 0xff);
+
+        let codeword = outer_code().unwrap().encode(&packet).unwrap();
+        assert_eq!(codeword[PACKET_SIZE..], parity);
+    }
+
+    #[test]
+    fn correct_packet() {
+        let rs = outer_code().unwrap();
+        let mut packet: Vec<u8> = (0..PACKET_SIZE).map(|i| (i * 31) as u8).collect();
+        packet[0] = 0x47;
+        let mut codeword = rs.encode(&packet).unwrap();
+
+        for j in [0, 50, 100, 150, 187, 190, 200, 203] {
+            codeword[j] ^= 0xff;
+        }
+
+        let decoded = rs.decode(&codeword).unwrap();
+        assert_eq!(decoded.message, packet);
+        assert_eq!(decoded.errors.len(), 8);
+
+        codeword[1] ^= 1;
+        assert_eq!(rs.decode(&codeword), Err(Errors::TooManyErrors));
+    }
+}
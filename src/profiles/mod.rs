@@ -0,0 +1,7 @@
+//! Ready-made codes of existing standards, built on [`ReedSolomon`](crate::reedsolomon::ReedSolomon).
+
+pub mod ccsds;
+pub mod circ;
+pub mod datamatrix;
+pub mod dvb;
+pub mod qr;
@@ -0,0 +1,272 @@
+use crate::backend::gf256::QrField;
+use crate::errors::Errors;
+use crate::ff::FieldElement as FE;
+use crate::reedsolomon::{Decoded, ReedSolomon};
+
+/// Error correction level of a QR code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcLevel {
+    L,
+    M,
+    Q,
+    H,
+}
+
+/// Parity codewords per block for versions 1..=40, ISO/IEC 18004 table 9
+const EC_CODEWORDS: [[u8; 40]; 4] = [
+    [
+        7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28, 30,
+        30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+    [
+        13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30, 30,
+        30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24, 30,
+        30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+];
+
+/// Number of error correction blocks for versions 1..=40, ISO/IEC 18004 table 9
+const EC_BLOCKS: [[u8; 40]; 4] = [
+    [
+        1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13,
+        14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+        25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+    [
+        1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29,
+        34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68,
+    ],
+    [
+        1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32, 35,
+        37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81,
+    ],
+];
+
+/// Number of codewords in a symbol of `version`, i.e. its modules not taken by function
+/// patterns or format and version information, divided by 8.
+fn total_codewords(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+
+    if version >= 2 {
+        let alignment = version / 7 + 2;
+        modules -= (25 * alignment - 10) * alignment - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+
+    modules / 8
+}
+
+/// Error correction blocks of a QR code symbol of a given version and level.
+///
+/// The data codewords are split into blocks that are encoded separately with roots
+/// `α^0..α^(e-1)`, and the final sequence interleaves the data codewords of all blocks
+/// followed by their interleaved parity codewords.
+#[derive(Debug)]
+pub struct QrBlocks {
+    rs: ReedSolomon<QrField>,
+    version: usize,
+    level: EcLevel,
+    blocks: Vec<usize>,
+}
+
+impl QrBlocks {
+    pub fn new(version: usize, level: EcLevel) -> Result<Self, Errors> {
+        if !(1..=40).contains(&version) {
+            return Err(Errors::InvalidProfile);
+        }
+
+        let ec_size = EC_CODEWORDS[level as usize][version - 1] as usize;
+        let count = EC_BLOCKS[level as usize][version - 1] as usize;
+        let total = total_codewords(version);
+
+        // blocks differ by at most one data codeword, the longer ones come last
+        let short = total / count - ec_size;
+        let blocks: Vec<usize> = (0..count)
+            .map(|i| short + usize::from(i >= count - total % count))
+            .collect();
+
+        let longest = blocks[count - 1];
+        let rs = ReedSolomon::builder(longest + ec_size, longest, FE::new(2))
+            .first_root(0)
+            .field_polynomial(0x11d)
+            .build()?;
+
+        Ok(QrBlocks {
+            rs,
+            version,
+            level,
+            blocks,
+        })
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn level(&self) -> EcLevel {
+        self.level
+    }
+
+    /// Data codewords of every block, in order
+    pub fn blocks(&self) -> &[usize] {
+        &self.blocks
+    }
+
+    /// Parity codewords of every block
+    pub fn ec_size(&self) -> usize {
+        self.rs.parity_size()
+    }
+
+    /// Data codewords of the symbol
+    pub fn data_size(&self) -> usize {
+        self.blocks.iter().sum()
+    }
+
+    /// Codewords of the symbol
+    pub fn total_size(&self) -> usize {
+        self.data_size() + self.blocks.len() * self.ec_size()
+    }
+
+    /// Maps the `m`-th codeword of block `b` to its position in the final sequence.
+    fn position(&self, b: usize, m: usize) -> usize {
+        let longest = self.blocks[self.blocks.len() - 1];
+
+        if m < self.blocks[b] {
+            // the last data column only has codewords of the long blocks
+            let short = self.blocks.iter().filter(|&&len| len < longest).count();
+            if m < self.blocks[0] {
+                m * self.blocks.len() + b
+            } else {
+                m * self.blocks.len() + b - short
+            }
+        } else {
+            self.data_size() + (m - self.blocks[b]) * self.blocks.len() + b
+        }
+    }
+
+    /// Encodes exactly [`QrBlocks::data_size`] data codewords into the interleaved final
+    /// codeword sequence.
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>, Errors> {
+        if data.len() != self.data_size() {
+            return Err(Errors::InvalidMessage);
+        }
+
+        let mut sequence = vec![0; self.total_size()];
+        let mut start = 0;
+        for (b, &len) in self.blocks.iter().enumerate() {
+            let codeword = self.rs.encode(&data[start..start + len])?;
+            for (m, c) in codeword.into_iter().enumerate() {
+                sequence[self.position(b, m)] = c;
+            }
+            start += len;
+        }
+
+        Ok(sequence)
+    }
+
+    /// Corrects the final codeword sequence block by block and returns the data
+    /// codewords, along with the corrected positions within the sequence.
+    pub fn decode(&self, sequence: &[u8]) -> Result<Decoded, Errors> {
+        if sequence.len() != self.total_size() {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        let mut message = Vec::with_capacity(self.data_size());
+        let mut errors = vec![];
+        for (b, &len) in self.blocks.iter().enumerate() {
+            let codeword: Vec<u8> = (0..len + self.ec_size())
+                .map(|m| sequence[self.position(b, m)])
+                .collect();
+
+            let decoded = self.rs.decode(&codeword)?;
+            message.extend_from_slice(&decoded.message);
+            errors.extend(decoded.errors.into_iter().map(|m| self.position(b, m)));
+        }
+        errors.sort_unstable();
+
+        Ok(Decoded {
+            message,
+            errors,
+            erasures: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_structure() {
+        let blocks = QrBlocks::new(1, EcLevel::M).unwrap();
+        assert_eq!((blocks.total_size(), blocks.data_size()), (26, 16));
+
+        // 5-Q: two blocks of 15 and two blocks of 16 data codewords, 18 parity each
+        let blocks = QrBlocks::new(5, EcLevel::Q).unwrap();
+        assert_eq!(blocks.blocks(), &[15, 15, 16, 16]);
+        assert_eq!(blocks.ec_size(), 18);
+        assert_eq!(blocks.total_size(), 134);
+
+        // data capacity of the largest symbols, ISO/IEC 18004 table 7
+        for (level, data) in [
+            (EcLevel::L, 2956),
+            (EcLevel::M, 2334),
+            (EcLevel::Q, 1666),
+            (EcLevel::H, 1276),
+        ] {
+            let blocks = QrBlocks::new(40, level).unwrap();
+            assert_eq!(blocks.data_size(), data);
+            assert_eq!(blocks.total_size(), 3706);
+        }
+
+        assert!(matches!(
+            QrBlocks::new(41, EcLevel::L),
+            Err(Errors::InvalidProfile)
+        ));
+    }
+
+    #[test]
+    fn numeric_example() {
+        // "01234567" in version 1-M, ISO/IEC 18004 annex I
+        let blocks = QrBlocks::new(1, EcLevel::M).unwrap();
+        let data = [
+            0x10, 0x20, 0x0c, 0x56, 0x61, 0x80, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11,
+            0xec, 0x11,
+        ];
+        let sequence = blocks.encode(&data).unwrap();
+        assert_eq!(
+            &sequence[16..],
+            &[0xa5, 0x24, 0xd4, 0xc1, 0xed, 0x36, 0xc7, 0x87, 0x2c, 0x55]
+        );
+    }
+
+    #[test]
+    fn interleaved_blocks() {
+        let blocks = QrBlocks::new(5, EcLevel::Q).unwrap();
+        let data: Vec<u8> = (0..62).collect();
+        let mut sequence = blocks.encode(&data).unwrap();
+
+        // data columns alternate between blocks, the last one only covers blocks 3 and 4
+        assert_eq!(&sequence[..4], &[0, 15, 30, 46]);
+        assert_eq!(&sequence[60..62], &[45, 61]);
+
+        for j in [0, 1, 2, 3, 61, 62, 100, 133] {
+            sequence[j] ^= 0x99;
+        }
+        let decoded = blocks.decode(&sequence).unwrap();
+        assert_eq!(decoded.message, data);
+        assert_eq!(decoded.errors, vec![0, 1, 2, 3, 61, 62, 100, 133]);
+    }
+}
@@ -0,0 +1,315 @@
+//! RAID-6 P+Q parity over GF(2^8), matching the Linux md raid6 driver.
+//!
+//! For data disks `D_0..D_{n-1}`, P is their XOR and Q is `sum g^i * D_i` with the
+//! generator `g = {02}` of GF(2^8) modulo `0x11d`. Any two of the `n + 2` disks can be
+//! rebuilt from the others.
+
+use crate::backend::gf2_8::GF2k;
+use crate::errors::Errors;
+use crate::ff::Fp;
+
+/// P+Q parity of a stripe of `n` data disks, stored as `n + 2` equal-length buffers with
+/// P at index `n` and Q at index `n + 1`.
+#[derive(Debug, Clone, Copy)]
+pub struct Raid6 {
+    data_disks: usize,
+}
+
+impl Raid6 {
+    /// Stripes over `data_disks` disks, at most 255 as `g^i` must be distinct.
+    pub fn new(data_disks: usize) -> Result<Self, Errors> {
+        if data_disks == 0 || data_disks >= GF2k::ORDER {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        Ok(Raid6 { data_disks })
+    }
+
+    pub fn data_disks(&self) -> usize {
+        self.data_disks
+    }
+
+    pub fn total_disks(&self) -> usize {
+        self.data_disks + 2
+    }
+
+    fn check_sizes<'a, I: Iterator<Item = &'a [u8]>>(mut disks: I) -> Result<usize, Errors> {
+        let len = match disks.next() {
+            Some(disk) => disk.len(),
+            None => return Err(Errors::InvalidShardCount),
+        };
+
+        if len == 0 || disks.any(|disk| disk.len() != len) {
+            return Err(Errors::InvalidShardSize);
+        }
+
+        Ok(len)
+    }
+
+    /// Computes P and Q of `data` into `p` and `q`.
+    pub fn gen_syndrome(&self, data: &[&[u8]], p: &mut [u8], q: &mut [u8]) -> Result<(), Errors> {
+        if data.len() != self.data_disks {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let disks = data.iter().copied().chain([&*p, &*q]);
+        Self::check_sizes(disks)?;
+
+        p.fill(0);
+        q.fill(0);
+        for (i, disk) in data.iter().enumerate() {
+            for (p, d) in p.iter_mut().zip(disk.iter()) {
+                *p ^= d;
+            }
+            GF2k::mul_add_slice(GF2k::exp(2, i), disk, q);
+        }
+
+        Ok(())
+    }
+
+    /// Fills P and Q of `disks` from its data disks.
+    pub fn encode(&self, disks: &mut [Vec<u8>]) -> Result<(), Errors> {
+        if disks.len() != self.total_disks() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let (data, parity) = disks.split_at_mut(self.data_disks);
+        let len = Self::check_sizes(data.iter().map(|d| d.as_slice()))?;
+        let (p, q) = parity.split_at_mut(1);
+        p[0].resize(len, 0);
+        q[0].resize(len, 0);
+
+        let data: Vec<&[u8]> = data.iter().map(|d| d.as_slice()).collect();
+        self.gen_syndrome(&data, &mut p[0], &mut q[0])
+    }
+
+    /// Returns whether P and Q match the data disks.
+    pub fn verify(&self, disks: &[Vec<u8>]) -> Result<bool, Errors> {
+        if disks.len() != self.total_disks() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let len = Self::check_sizes(disks.iter().map(|d| d.as_slice()))?;
+        let (mut p, mut q) = (vec![0; len], vec![0; len]);
+        let data: Vec<&[u8]> = disks[..self.data_disks]
+            .iter()
+            .map(|d| d.as_slice())
+            .collect();
+        self.gen_syndrome(&data, &mut p, &mut q)?;
+
+        Ok(p == disks[self.data_disks] && q == disks[self.data_disks + 1])
+    }
+
+    /// Rebuilds up to two missing (`None`) disks of a stripe.
+    pub fn reconstruct(&self, disks: &mut [Option<Vec<u8>>]) -> Result<(), Errors> {
+        if disks.len() != self.total_disks() {
+            return Err(Errors::InvalidShardCount);
+        }
+
+        let missing: Vec<usize> = (0..disks.len()).filter(|&i| disks[i].is_none()).collect();
+        if missing.len() > 2 {
+            return Err(Errors::TooFewShards);
+        }
+
+        let len = Self::check_sizes(disks.iter().flatten().map(|d| d.as_slice()))?;
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        // syndromes of the data disks at hand, the missing ones taken as zero
+        let zero = vec![0; len];
+        let data: Vec<&[u8]> = disks[..self.data_disks]
+            .iter()
+            .map(|d| d.as_deref().unwrap_or(&zero))
+            .collect();
+        let (mut pxy, mut qxy) = (vec![0; len], vec![0; len]);
+        self.gen_syndrome(&data, &mut pxy, &mut qxy)?;
+
+        let (p_index, q_index) = (self.data_disks, self.data_disks + 1);
+        let p = disks[p_index].as_deref();
+        let q = disks[q_index].as_deref();
+
+        match *missing.as_slice() {
+            // only parity is missing
+            [x] | [x, _] if x >= p_index => {
+                disks[p_index].get_or_insert(pxy);
+                disks[q_index].get_or_insert(qxy);
+            }
+            [x] => disks[x] = Some(xor(p.unwrap(), &pxy)),
+            // a data disk and Q: the data comes back from P
+            [x, y] if y == q_index => {
+                let dx = xor(p.unwrap(), &pxy);
+                GF2k::mul_add_slice(GF2k::exp(2, x), &dx, &mut qxy);
+
+                disks[x] = Some(dx);
+                disks[q_index] = Some(qxy);
+            }
+            // a data disk and P: the data comes back from Q
+            [x, y] if y == p_index => {
+                let scale = GF2k::inverse(GF2k::exp(2, x)).unwrap();
+                let mut dx = vec![0; len];
+                GF2k::mul_slice(scale, &xor(q.unwrap(), &qxy), &mut dx);
+
+                disks[p_index] = Some(xor(&pxy, &dx));
+                disks[x] = Some(dx);
+            }
+            // two data disks, solving `D_x + D_y = P + Pxy` and
+            // `g^x D_x + g^y D_y = Q + Qxy`
+            [x, y] => {
+                let gyx = GF2k::exp(2, y - x);
+                let denom = GF2k::inverse(gyx ^ 1).unwrap();
+                let a = GF2k::mul(gyx, denom);
+                let b = GF2k::mul(GF2k::inverse(GF2k::exp(2, x)).unwrap(), denom);
+
+                let px = xor(p.unwrap(), &pxy);
+                let qx = xor(q.unwrap(), &qxy);
+                let mut dx = vec![0; len];
+                GF2k::mul_slice(a, &px, &mut dx);
+                GF2k::mul_add_slice(b, &qx, &mut dx);
+
+                disks[y] = Some(xor(&px, &dx));
+                disks[x] = Some(dx);
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    /// Read-modify-write update of P and Q for data disk `disk` changing from `old` to
+    /// `new`, without reading the other data disks.
+    pub fn update(
+        &self,
+        disk: usize,
+        old: &[u8],
+        new: &[u8],
+        p: &mut [u8],
+        q: &mut [u8],
+    ) -> Result<(), Errors> {
+        if disk >= self.data_disks {
+            return Err(Errors::InvalidShardIndex);
+        }
+
+        Self::check_sizes([old, new, &*p, &*q].into_iter())?;
+
+        let delta = xor(old, new);
+        for (p, d) in p.iter_mut().zip(delta.iter()) {
+            *p ^= d;
+        }
+        GF2k::mul_add_slice(GF2k::exp(2, disk), &delta, q);
+
+        Ok(())
+    }
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b.iter()).map(|(a, b)| a ^ b).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stripe(data: usize, len: usize) -> Vec<Vec<u8>> {
+        let mut disks: Vec<Vec<u8>> = (0..data)
+            .map(|i| (0..len).map(|j| (i * 29 + j * 11 + i * j) as u8).collect())
+            .collect();
+        disks.resize(data + 2, vec![]);
+        disks
+    }
+
+    #[test]
+    fn syndromes() {
+        let raid = Raid6::new(3).unwrap();
+        let (mut p, mut q) = ([0], [0]);
+
+        raid.gen_syndrome(&[&[1], &[1], &[1]], &mut p, &mut q)
+            .unwrap();
+        assert_eq!((p, q), ([1], [1 ^ 2 ^ 4]));
+
+        // {02} * {80} reduces by 0x11d
+        raid.gen_syndrome(&[&[0], &[0x80], &[0]], &mut p, &mut q)
+            .unwrap();
+        assert_eq!((p, q), ([0x80], [0x1d]));
+
+        let raid = Raid6::new(20).unwrap();
+        let mut disks = stripe(20, 50);
+        raid.encode(&mut disks).unwrap();
+        let q: Vec<u8> = (0..50)
+            .map(|j| (0..20).fold(0, |acc, i| acc ^ GF2k::mul(GF2k::exp(2, i), disks[i][j])))
+            .collect();
+        assert_eq!(disks[21], q);
+        assert!(raid.verify(&disks).unwrap());
+
+        disks[3][7] ^= 1;
+        assert!(!raid.verify(&disks).unwrap());
+    }
+
+    #[test]
+    fn reconstruct() {
+        let raid = Raid6::new(6).unwrap();
+        let mut original = stripe(6, 40);
+        raid.encode(&mut original).unwrap();
+
+        for x in 0..8 {
+            for y in x..8 {
+                let mut damaged: Vec<Option<Vec<u8>>> =
+                    original.iter().cloned().map(Some).collect();
+                damaged[x] = None;
+                damaged[y] = None;
+
+                raid.reconstruct(&mut damaged).unwrap();
+                let rebuilt: Vec<Vec<u8>> = damaged.into_iter().map(Option::unwrap).collect();
+                assert_eq!(rebuilt, original, "disks {x} and {y}");
+            }
+        }
+
+        let mut damaged: Vec<Option<Vec<u8>>> = original.into_iter().map(Some).collect();
+        for i in [0, 4, 7] {
+            damaged[i] = None;
+        }
+        assert_eq!(raid.reconstruct(&mut damaged), Err(Errors::TooFewShards));
+    }
+
+    #[test]
+    fn update() {
+        let raid = Raid6::new(5).unwrap();
+        let mut disks = stripe(5, 16);
+        raid.encode(&mut disks).unwrap();
+
+        let new = vec![0xa5; 16];
+        let (mut p, mut q) = (disks[5].clone(), disks[6].clone());
+        raid.update(3, &disks[3], &new, &mut p, &mut q).unwrap();
+
+        disks[3] = new;
+        raid.encode(&mut disks).unwrap();
+        assert_eq!((p, q), (disks[5].clone(), disks[6].clone()));
+
+        let (mut p, mut q) = (disks[5].clone(), disks[6].clone());
+        assert_eq!(
+            raid.update(5, &disks[0], &disks[1], &mut p, &mut q),
+            Err(Errors::InvalidShardIndex)
+        );
+        assert_eq!(
+            raid.update(0, &disks[0][1..], &disks[1], &mut p, &mut q),
+            Err(Errors::InvalidShardSize)
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Raid6::new(0).is_err());
+        assert!(Raid6::new(256).is_err());
+        assert!(Raid6::new(255).is_ok());
+
+        let raid = Raid6::new(3).unwrap();
+        assert_eq!(
+            raid.encode(&mut stripe(4, 8)),
+            Err(Errors::InvalidShardCount)
+        );
+
+        let mut uneven = stripe(3, 8);
+        uneven[2].pop();
+        assert_eq!(raid.encode(&mut uneven), Err(Errors::InvalidShardSize));
+    }
+}
@@ -1,3 +1,4 @@
+use std::sync::OnceLock;
 use std::vec;
 
 use num_traits::{One, Pow, Zero};
@@ -5,6 +6,8 @@ use num_traits::{One, Pow, Zero};
 use crate::errors::Errors;
 
 use crate::ff::FieldElement as FE;
+use crate::fft;
+use crate::matrix::Matrix;
 use crate::polynomial::PolynomialOps;
 use crate::{ff::Fp, polynomial::Polynomial};
 
@@ -13,30 +16,210 @@ pub struct ReedSolomon<F: Fp> {
     total_size: usize,
     parity_size: usize,
     generator: FE<F>,
+    first_root: usize,
     generator_poly: Polynomial<FE<F>>,
+    encoding: Encoding,
+    punctured: Vec<usize>,
+    /// Parity of every unit message, built on the first parity update
+    parity_columns: OnceLock<Vec<Vec<FE<F>>>>,
+    /// Every byte times the generator coefficients below the leading one, a row per
+    /// byte, built on the first encode over a one byte field
+    generator_products: OnceLock<Vec<u8>>,
 }
 
-fn create_generator_poly<F: Fp>(n: usize, k: usize, gen: &FE<F>) -> Polynomial<FE<F>> {
+/// Configures a systematic [`ReedSolomon`] code beyond the defaults of
+/// [`ReedSolomon::new`], to match the conventions of existing standards.
+///
+/// The generator polynomial has roots `β^(fcr+i)` for `i` in `0..n-k`, where
+/// `β = gen^step`. Encoding, checking and every decoder use the same roots, so the
+/// defaults `fcr = 1` and `step = 1` give the same code as [`ReedSolomon::new`].
+#[derive(Debug, Clone)]
+pub struct ReedSolomonBuilder<F: Fp> {
+    total_size: usize,
+    message_size: usize,
+    generator: FE<F>,
+    first_root: usize,
+    root_step: usize,
+    field_poly: Option<usize>,
+    shortened: Option<usize>,
+    punctured: Vec<usize>,
+}
+
+/// How messages are mapped onto codewords
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// The message followed by the remainder of its division by the generator polynomial
+    Systematic,
+    /// Evaluations of the message polynomial at `gen^0..gen^(n-1)`, computed with an NTT
+    Evaluation,
+}
+
+/// Result of a successful decode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// Corrected message symbols, without parity
+    pub message: Vec<u8>,
+    /// Positions in the codeword whose symbols were corrected
+    pub errors: Vec<usize>,
+    /// Positions the caller reported as erased, sorted and deduplicated
+    pub erasures: Vec<usize>,
+}
+
+/// Returns `Π (x - gen^(fcr+i))` for `i` in `0..parity`.
+fn create_generator_poly<F: Fp>(parity: usize, fcr: usize, gen: &FE<F>) -> Polynomial<FE<F>> {
     let mut p = Polynomial {
         coeffs: vec![FE::one(); 1],
     };
 
-    for i in 1..=(n - k) {
+    for i in 0..parity {
         p = p.mul(&Polynomial {
-            coeffs: vec![FE::one(), gen.pow(i)],
+            coeffs: vec![FE::one(), -gen.pow(fcr + i)],
         })
     }
 
     p
 }
 
-impl<F: Fp> ReedSolomon<F> {
-    pub fn new(n: usize, k: usize, gen: FE<F>) -> Result<ReedSolomon<F>, Errors> {
+/// Multiplies a field element by an integer, i.e. adds it to itself `n` times.
+fn scale<F: Fp>(x: FE<F>, mut n: usize) -> FE<F> {
+    let mut result = FE::zero();
+    let mut acc = x;
+
+    while n > 0 {
+        if n & 1 == 1 {
+            result += acc;
+        }
+        acc = acc + acc;
+        n >>= 1;
+    }
+
+    result
+}
+
+/// Evaluates a polynomial stored lowest degree first.
+fn evaluate_low<F: Fp>(coeffs: &[FE<F>], at: &FE<F>) -> FE<F> {
+    coeffs
+        .iter()
+        .rev()
+        .fold(FE::zero(), |acc, x| acc * at + x.to_owned())
+}
+
+/// Runs Berlekamp-Massey over the syndromes and returns the errata locator polynomial
+/// (lowest degree first) along with its linear complexity.
+///
+/// The iteration is seeded with `erasure_locator`, whose degree is the number of known
+/// erasures, so only the remaining syndromes are spent on locating unknown errors.
+fn berlekamp_massey<F: Fp>(
+    syndromes: &[FE<F>],
+    erasure_locator: Vec<FE<F>>,
+) -> (Vec<FE<F>>, usize) {
+    let erasures = erasure_locator.len() - 1;
+    let mut locator = erasure_locator.clone();
+    let mut prev = erasure_locator;
+    let mut prev_discrepancy = FE::<F>::one();
+    let mut len = erasures;
+    let mut shift = 1;
+
+    for r in erasures..syndromes.len() {
+        let mut discrepancy = syndromes[r];
+        for i in 1..=len.min(r) {
+            if let Some(c) = locator.get(i) {
+                discrepancy += *c * syndromes[r - i];
+            }
+        }
+
+        if discrepancy.is_zero() {
+            shift += 1;
+            continue;
+        }
+
+        let factor = discrepancy / prev_discrepancy;
+        let mut next = locator.clone();
+        if next.len() < prev.len() + shift {
+            next.resize(prev.len() + shift, FE::zero());
+        }
+        for (i, b) in prev.iter().enumerate() {
+            next[i + shift] -= factor * b;
+        }
+
+        if 2 * len <= r + erasures {
+            prev = locator;
+            prev_discrepancy = discrepancy;
+            len = r + 1 + erasures - len;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+
+        locator = next;
+    }
+
+    while locator.len() > 1 && locator.last().unwrap().is_zero() {
+        locator.pop();
+    }
+
+    (locator, len)
+}
+
+impl<F: Fp> ReedSolomonBuilder<F> {
+    /// Starts from an `(n, k)` code over the powers of the primitive element `gen`.
+    pub fn new(n: usize, k: usize, gen: FE<F>) -> Self {
+        ReedSolomonBuilder {
+            total_size: n,
+            message_size: k,
+            generator: gen,
+            first_root: 1,
+            root_step: 1,
+            field_poly: None,
+            shortened: None,
+            punctured: vec![],
+        }
+    }
+
+    /// Sets the exponent of the first consecutive root, e.g. 0 for QR codes and DVB or
+    /// 112 for CCSDS.
+    pub fn first_root(mut self, fcr: usize) -> Self {
+        self.first_root = fcr;
+        self
+    }
+
+    /// Sets the power of `gen` whose consecutive powers are the roots, e.g. 11 for CCSDS.
+    pub fn root_step(mut self, step: usize) -> Self {
+        self.root_step = step;
+        self
+    }
+
+    /// Requires the field to be defined by the prime polynomial `poly`. The field itself
+    /// is chosen by `F`, e.g. [`GF256`](crate::backend::gf256::GF256), so this only guards
+    /// against building a standard's code over the wrong representation.
+    pub fn field_polynomial(mut self, poly: usize) -> Self {
+        self.field_poly = Some(poly);
+        self
+    }
+
+    /// Shortens the code to `len` symbols by leaving out `n - len` message symbols that
+    /// are implicitly zero, e.g. RS(204, 188) from RS(255, 239).
+    pub fn shortened(mut self, len: usize) -> Self {
+        self.shortened = Some(len);
+        self
+    }
+
+    /// Leaves the parity symbols at `positions` out of every codeword, see
+    /// [`ReedSolomon::puncture`].
+    pub fn punctured(mut self, positions: &[usize]) -> Self {
+        self.punctured = positions.to_vec();
+        self
+    }
+
+    pub fn build(self) -> Result<ReedSolomon<F>, Errors> {
+        let n = self.total_size;
+        let k = self.message_size;
+
         if n == 0 {
             return Err(Errors::TooFewNumbers);
         }
 
-        if k == 0 {
+        if k == 0 || k >= n {
             return Err(Errors::TooFewParity);
         }
 
@@ -44,59 +227,1414 @@ impl<F: Fp> ReedSolomon<F> {
             return Err(Errors::TooManyNumbers);
         }
 
-        Ok(ReedSolomon {
-            total_size: n,
-            parity_size: k,
-            generator: gen.clone(),
-            generator_poly: create_generator_poly(n, k, &gen),
+        if self.field_poly.is_some_and(|poly| poly != F::prime_poly()) {
+            return Err(Errors::FieldMismatch);
+        }
+
+        let parity_size = n - k;
+        let total_size = match self.shortened {
+            Some(len) if len > n => return Err(Errors::TooManyNumbers),
+            Some(len) if len <= parity_size => return Err(Errors::InvalidShortening),
+            Some(len) => len,
+            None => n,
+        };
+
+        // positions are told apart by their locators β^i, which must not repeat
+        let root = self.generator.pow(self.root_step);
+        let mut power = root;
+        for _ in 1..n {
+            if power == FE::one() || power.is_zero() {
+                return Err(Errors::InvalidRoot);
+            }
+            power *= root;
+        }
+
+        ReedSolomon {
+            total_size,
+            parity_size,
+            generator: root,
+            first_root: self.first_root,
+            generator_poly: create_generator_poly(parity_size, self.first_root, &root),
+            encoding: Encoding::Systematic,
+            punctured: vec![],
+            parity_columns: OnceLock::new(),
+            generator_products: OnceLock::new(),
+        }
+        .puncture(&self.punctured)
+    }
+}
+
+impl<F: Fp> ReedSolomon<F> {
+    /// Creates a systematic `(n, k)` code whose generator polynomial has roots
+    /// `gen^1..gen^(n-k)`.
+    pub fn new(n: usize, k: usize, gen: FE<F>) -> Result<ReedSolomon<F>, Errors> {
+        ReedSolomonBuilder::new(n, k, gen).build()
+    }
+
+    /// Creates an `(n, k)` code shortened by `s` symbols, i.e. an `(n-s, k-s)` code whose
+    /// first `s` message symbols are implicitly zero and never transmitted.
+    pub fn shortened(n: usize, k: usize, s: usize, gen: FE<F>) -> Result<ReedSolomon<F>, Errors> {
+        if s >= k {
+            return Err(Errors::InvalidShortening);
+        }
+
+        ReedSolomonBuilder::new(n, k, gen).shortened(n - s).build()
+    }
+
+    /// Punctures the code, i.e. stops transmitting the parity symbols at `positions`,
+    /// counted from the first parity symbol. The decoder treats them as erasures, so at
+    /// least one parity symbol has to remain.
+    pub fn puncture(mut self, positions: &[usize]) -> Result<ReedSolomon<F>, Errors> {
+        if positions.is_empty() {
+            return Ok(self);
+        }
+
+        if self.encoding != Encoding::Systematic {
+            return Err(Errors::NotSystematic);
+        }
+
+        if positions.iter().any(|&q| q >= self.parity_size) {
+            return Err(Errors::InvalidParity);
+        }
+
+        self.punctured.extend_from_slice(positions);
+        self.punctured.sort_unstable();
+        self.punctured.dedup();
+        if self.punctured.len() >= self.parity_size {
+            return Err(Errors::TooFewParity);
+        }
+
+        Ok(self)
+    }
+
+    /// Returns a [`ReedSolomonBuilder`] for an `(n, k)` code over the powers of `gen`.
+    pub fn builder(n: usize, k: usize, gen: FE<F>) -> ReedSolomonBuilder<F> {
+        ReedSolomonBuilder::new(n, k, gen)
+    }
+
+    /// Creates a non-systematic code whose codewords are the evaluations of the message
+    /// polynomial over the subgroup generated by `root`, a primitive `n`-th root of unity
+    /// for a power of two `n`.
+    ///
+    /// Encoding and erasure recovery run in `O(n log n)` with radix-2 NTTs. Read backwards,
+    /// every codeword is also divisible by the generator polynomial with roots
+    /// `root^1..root^(n-k)`, so the same Berlekamp-Massey decoder corrects errors.
+    pub fn new_evaluation(n: usize, k: usize, root: FE<F>) -> Result<ReedSolomon<F>, Errors> {
+        fft::check_root(n, &root)?;
+
+        let mut rs = Self::new(n, k, root)?;
+        rs.encoding = Encoding::Evaluation;
+
+        Ok(rs)
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Number of symbols in a codeword, without the punctured ones
+    pub fn total_size(&self) -> usize {
+        self.total_size - self.punctured.len()
+    }
+
+    /// Number of parity symbols of the code, including the punctured ones
+    pub fn parity_size(&self) -> usize {
+        self.parity_size
+    }
+
+    /// Positions of the parity symbols left out of every codeword
+    pub fn punctured(&self) -> &[usize] {
+        &self.punctured
+    }
+
+    /// Positions of the transmitted parity symbols, counted from the first parity symbol
+    fn kept_parity(&self) -> Vec<usize> {
+        (0..self.parity_size)
+            .filter(|q| self.punctured.binary_search(q).is_err())
+            .collect()
+    }
+
+    /// Exponent of the first consecutive root of the generator polynomial
+    pub fn first_root(&self) -> usize {
+        self.first_root
+    }
+
+    /// Generator polynomial, highest degree first
+    pub fn generator_poly(&self) -> &Polynomial<FE<F>> {
+        &self.generator_poly
+    }
+
+    /// Number of message symbols in a codeword
+    pub fn message_size(&self) -> usize {
+        self.total_size - self.parity_size
+    }
+
+    /// Encodes `message` and returns the systematic codeword, i.e. the message followed
+    /// by the `n-k` parity symbols that are not punctured. Evaluation codes take exactly
+    /// `k` message symbols and return their `n` evaluations instead.
+    ///
+    /// Symbols are `F::SYMBOL_SIZE` bytes wide, so `message` must hold a whole number of
+    /// symbols.
+    pub fn encode(&self, message: &[u8]) -> Result<Vec<u8>, Errors> {
+        if self.encoding == Encoding::Evaluation {
+            let mut symbols = Self::to_symbols(message)?;
+            if symbols.len() != self.message_size() {
+                return Err(Errors::InvalidMessage);
+            }
+
+            symbols.resize(self.total_size, FE::zero());
+            fft::ntt(&mut symbols, &self.generator)?;
+
+            return Ok(FE::to_byte_vec(&symbols));
+        }
+
+        let mut encoded = message.to_vec();
+        let parity_size = self.parity_size - self.punctured.len();
+        encoded.resize(message.len() + parity_size * F::SYMBOL_SIZE, 0);
+
+        let (message, parity) = encoded.split_at_mut(message.len());
+        self.encode_into(message, parity)?;
+
+        Ok(encoded)
+    }
+
+    /// Computes the parity symbols of `message` into `parity`, which must hold exactly
+    /// the `n-k` symbols minus the punctured ones.
+    pub fn encode_into(&self, message: &[u8], parity: &mut [u8]) -> Result<(), Errors> {
+        if self.encoding != Encoding::Systematic {
+            return Err(Errors::NotSystematic);
+        }
+
+        if parity.len() != (self.parity_size - self.punctured.len()) * F::SYMBOL_SIZE {
+            return Err(Errors::InvalidParity);
+        }
+
+        let symbols = Self::to_symbols(message)?;
+        if symbols.len() > self.message_size() {
+            return Err(Errors::InvalidMessage);
+        }
+
+        // LFSR division by the generator polynomial, keeping the negated remainder, so
+        // that each message symbol adds a multiple of the generator to the whole register.
+        // Over one byte fields the multiples are looked up rather than multiplied, as the
+        // register is too short to pay for building slice tables per symbol.
+        let size = F::SYMBOL_SIZE;
+        let generator = FE::to_byte_vec(&self.generator_poly.coeffs[1..]);
+        let mut register = vec![0; self.parity_size * size];
+        for symbol in symbols {
+            let feedback = symbol - FE::new(F::from_bytes(&register[..size]));
+
+            register.copy_within(size.., 0);
+            let len = register.len();
+            register[len - size..].fill(0);
+
+            if feedback.is_zero() {
+                continue;
+            }
+
+            if size == 1 {
+                let start = F::to_u8(feedback.value()) as usize * self.parity_size;
+                let multiple = &self.generator_products()[start..start + self.parity_size];
+                for (r, m) in register.iter_mut().zip(multiple) {
+                    *r = F::to_u8(F::add(F::from_u8(*r), F::from_u8(*m)));
+                }
+            } else {
+                F::mul_add_slice(feedback.value(), &generator, &mut register);
+            }
+        }
+
+        for (p, q) in parity.chunks_mut(size).zip(self.kept_parity()) {
+            p.copy_from_slice(&register[q * size..(q + 1) * size]);
+        }
+
+        Ok(())
+    }
+
+    /// Encodes `depth` messages of exactly `k` symbols, stored one after the other, into
+    /// their codewords interleaved symbol by symbol, i.e. with symbol `m` of codeword `i`
+    /// at `m·depth + i`.
+    ///
+    /// The codewords are divided by the generator polynomial together, so every step
+    /// multiplies a row of `depth` symbols with [`Fp::mul_add_slice`].
+    pub fn encode_interleaved(&self, messages: &[u8], depth: usize) -> Result<Vec<u8>, Errors> {
+        if self.encoding != Encoding::Systematic {
+            return Err(Errors::NotSystematic);
+        }
+
+        let size = F::SYMBOL_SIZE;
+        let k = self.message_size();
+        let row = depth * size;
+        if depth == 0 || messages.len() != k * row || !messages.chunks(size).all(F::is_canonical) {
+            return Err(Errors::InvalidMessage);
+        }
+
+        let kept = self.kept_parity();
+        let mut encoded = vec![0; (k + kept.len()) * row];
+        for (i, message) in messages.chunks(k * size).enumerate() {
+            for (m, symbol) in message.chunks(size).enumerate() {
+                let start = m * row + i * size;
+                encoded[start..start + size].copy_from_slice(symbol);
+            }
+        }
+
+        // same LFSR as `encode_into`, with a row of the register per parity symbol
+        let mut register = vec![0; self.parity_size * row];
+        let mut feedback = vec![0; row];
+        for m in 0..k {
+            let symbols = encoded[m * row..(m + 1) * row].chunks(size);
+            for ((f, s), r) in feedback
+                .chunks_mut(size)
+                .zip(symbols)
+                .zip(register.chunks(size))
+            {
+                F::to_bytes(F::sub(F::from_bytes(s), F::from_bytes(r)), f);
+            }
+
+            register.copy_within(row.., 0);
+            let len = register.len();
+            register[len - row..].fill(0);
+
+            for (c, r) in self.generator_poly.coeffs[1..]
+                .iter()
+                .zip(register.chunks_mut(row))
+            {
+                F::mul_add_slice(c.value(), &feedback, r);
+            }
+        }
+
+        for (c, q) in kept.into_iter().enumerate() {
+            encoded[(k + c) * row..(k + c + 1) * row]
+                .copy_from_slice(&register[q * row..(q + 1) * row]);
+        }
+
+        Ok(encoded)
+    }
+
+    /// `f·g` for every byte `f`, with `g` the generator coefficients below the leading
+    /// one, for fields of one byte symbols.
+    fn generator_products(&self) -> &[u8] {
+        self.generator_products.get_or_init(|| {
+            let generator = FE::to_byte_vec(&self.generator_poly.coeffs[1..]);
+            let mut products = vec![0; 256 * generator.len()];
+            for (f, row) in products.chunks_mut(generator.len()).enumerate() {
+                F::mul_slice(F::from_u8(f as u8), &generator, row);
+            }
+
+            products
+        })
+    }
+
+    /// Parity symbols of the message with a one at position `j` and zeros elsewhere,
+    /// i.e. `-(x^(n-1-j) mod g)`, for every `j` in `0..k`.
+    fn parity_columns(&self) -> &[Vec<FE<F>>] {
+        self.parity_columns.get_or_init(|| {
+            let g = &self.generator_poly.coeffs;
+
+            // x^(n-k) mod g, then one more power of x per column
+            let mut remainder: Vec<FE<F>> = g[1..].iter().map(|c| -*c).collect();
+            let mut columns = Vec::with_capacity(self.message_size());
+            for _ in 0..self.message_size() {
+                columns.push(remainder.iter().map(|r| -*r).collect());
+
+                let lead = remainder[0];
+                remainder.rotate_left(1);
+                *remainder.last_mut().unwrap() = FE::zero();
+                for (r, c) in remainder.iter_mut().zip(g[1..].iter()) {
+                    *r -= lead * *c;
+                }
+            }
+
+            columns.reverse();
+            columns
         })
     }
 
-    pub fn encode(&self, message: &str) -> Result<String, Errors> {
-        if message.len() == 0 {
+    /// Updates `parity`, as computed by [`ReedSolomon::encode_into`], for the message
+    /// symbols starting at `index` changing from `old` to `new`, without the rest of the
+    /// message.
+    ///
+    /// Positions count from the start of a full `k` symbol message, shorter messages
+    /// being padded with leading zeros. Only the delta times the generator matrix columns
+    /// of the changed symbols is added, so an update costs `O((n-k)·len)`.
+    pub fn update_parity(
+        &self,
+        index: usize,
+        old: &[u8],
+        new: &[u8],
+        parity: &mut [u8],
+    ) -> Result<(), Errors> {
+        if self.encoding != Encoding::Systematic {
+            return Err(Errors::NotSystematic);
+        }
+
+        let kept = self.kept_parity();
+        if parity.len() != kept.len() * F::SYMBOL_SIZE {
+            return Err(Errors::InvalidParity);
+        }
+
+        let old = Self::to_symbols(old)?;
+        let new = Self::to_symbols(new)?;
+        if old.len() != new.len() || index + old.len() > self.message_size() {
             return Err(Errors::InvalidMessage);
         }
 
-        let mut coeffs: Vec<FE<F>> = message.as_bytes().iter().map(|x| FE::from(*x)).collect();
+        let columns = &self.parity_columns()[index..];
+        for ((o, n), column) in old.iter().zip(new.iter()).zip(columns) {
+            let delta = *n - *o;
+            if delta.is_zero() {
+                continue;
+            }
+
+            let column: Vec<FE<F>> = kept.iter().map(|&q| column[q]).collect();
+            F::mul_add_slice(delta.value(), &FE::to_byte_vec(&column), parity);
+        }
+
+        Ok(())
+    }
+
+    /// Generator matrix of the code in systematic form `[I_k | P]`, with a column per
+    /// transmitted symbol, so that row `j` is the codeword of the `j`-th unit message.
+    ///
+    /// Evaluation codes get the systematic basis of the same code, which differs from the
+    /// message to codeword map of their encoder.
+    pub fn generator_matrix(&self) -> Matrix<F> {
+        let k = self.message_size();
+
+        if self.encoding == Encoding::Evaluation {
+            let xs: Vec<FE<F>> = (0..self.total_size)
+                .map(|j| self.generator.pow(j))
+                .collect();
+            let mut g = Matrix::vandermonde(&xs, k).transpose();
+            g.row_reduce();
+
+            return g;
+        }
 
-        coeffs.append(vec![FE::zero(); self.parity_size].as_mut());
+        let kept = self.kept_parity();
+        let mut g = Matrix::new(k, k + kept.len());
+        for (j, column) in self.parity_columns().iter().enumerate() {
+            g[(j, j)] = FE::one();
+            for (c, &q) in kept.iter().enumerate() {
+                g[(j, k + c)] = column[q];
+            }
+        }
+
+        g
+    }
+
+    /// Parity-check matrix `[-P^T | I]` matching [`ReedSolomon::generator_matrix`], so
+    /// that `H·c` is zero exactly for codewords.
+    pub fn parity_check_matrix(&self) -> Matrix<F> {
+        let g = self.generator_matrix();
+        let (k, n) = (g.rows(), g.cols());
+
+        let mut h = Matrix::new(n - k, n);
+        for r in 0..n - k {
+            for j in 0..k {
+                h[(r, j)] = -g[(j, k + r)];
+            }
+            h[(r, k + r)] = FE::one();
+        }
+
+        h
+    }
+
+    /// Returns the dual `(n, n-k)` code, whose codewords are orthogonal to every codeword
+    /// of this one.
+    ///
+    /// Only full-length systematic codes, where `β^n = 1`, are cyclic and have a
+    /// Reed-Solomon dual: the one with roots `β^(1-fcr)..β^(k-fcr)`.
+    pub fn dual_code(&self) -> Result<ReedSolomon<F>, Errors> {
+        if self.encoding != Encoding::Systematic {
+            return Err(Errors::NotSystematic);
+        }
+
+        let n = self.total_size;
+        if !self.punctured.is_empty() || self.generator.pow(n) != FE::one() {
+            return Err(Errors::NotCyclic);
+        }
+
+        let first_root = (n + 1 - self.first_root % n) % n;
+        ReedSolomon::builder(n, self.parity_size, self.generator)
+            .first_root(first_root)
+            .build()
+    }
+
+    /// Returns whether `codeword` is a valid codeword, i.e. divisible by the generator
+    /// polynomial. Punctured codewords are valid if some punctured symbols complete them.
+    pub fn check(&self, codeword: &[u8]) -> Result<bool, Errors> {
+        if Self::non_canonical(codeword).next().is_some() {
+            return Ok(false);
+        }
+
+        let coeffs = Self::to_symbols(codeword)?;
+
+        if !self.punctured.is_empty() {
+            return match self.decode(codeword) {
+                Ok(decoded) => Ok(decoded.errors.is_empty()),
+                Err(Errors::TooManyErrors) => Ok(false),
+                Err(e) => Err(e),
+            };
+        }
+
+        if self.encoding == Encoding::Evaluation {
+            return Ok(self.interpolate(coeffs)?.is_some());
+        }
+
+        // TODO: implement fast check
+        let codeword_poly = Polynomial { coeffs };
+
+        let remainder = Polynomial::rem(&codeword_poly, &self.generator_poly);
+
+        Ok(remainder.is_zero())
+    }
+
+    /// Reads the symbols of a message or codeword, rejecting any that `F` would reduce.
+    fn to_symbols(bytes: &[u8]) -> Result<Vec<FE<F>>, Errors> {
+        if !bytes.chunks(F::SYMBOL_SIZE).all(F::is_canonical) {
+            return Err(Errors::InvalidMessage);
+        }
+
+        match FE::from_byte_slice(bytes) {
+            Some(symbols) if !symbols.is_empty() => Ok(symbols),
+            _ => Err(Errors::InvalidMessage),
+        }
+    }
+
+    /// Positions of the symbols of `codeword` that `F` would reduce, which no codeword
+    /// contains. A trailing partial symbol is left to the callers' length checks.
+    fn non_canonical(codeword: &[u8]) -> impl Iterator<Item = usize> + '_ {
+        codeword
+            .chunks_exact(F::SYMBOL_SIZE)
+            .enumerate()
+            .filter(|(_, symbol)| !F::is_canonical(symbol))
+            .map(|(j, _)| j)
+    }
+
+    /// Computes the syndromes `S_i = c(gen^(fcr+i))` for `i` in `0..n-k`, lowest first.
+    fn syndromes(&self, codeword: &Polynomial<FE<F>>) -> Vec<FE<F>> {
+        (0..self.parity_size)
+            .map(|i| codeword.evaluate(&self.generator.pow(self.first_root + i)))
+            .collect()
+    }
 
-        let message_poly = Polynomial {
-            coeffs: coeffs.clone(),
+    /// Returns `gen^-(len-1-j)`, the inverse locator of position `j` in a codeword of
+    /// `len` symbols, as position `j` holds the coefficient of `x^(len-1-j)`.
+    fn inverse_locator(&self, len: usize, j: usize) -> FE<F> {
+        FE::one() / self.generator.pow(len - 1 - j)
+    }
+
+    /// Corrects up to `(n-k)/2` symbol errors in `codeword` and returns the message
+    /// along with the corrected positions.
+    ///
+    /// Syndromes are fed into Berlekamp-Massey to find the error locator, Chien search
+    /// finds its roots and Forney's algorithm computes the error magnitudes.
+    pub fn decode(&self, codeword: &[u8]) -> Result<Decoded, Errors> {
+        self.decode_with_erasures(codeword, &[])
+    }
+
+    /// Decodes `codeword` given the positions of symbols known to be bad.
+    ///
+    /// Recovers up to `n-k` erasures, or any mix of `e` errors and `s` erasures with
+    /// `2e + s <= n-k`. The returned [`Decoded`] reports the erasures separately from the
+    /// errors found by the decoder. Symbols `F` would reduce are erasures as well.
+    pub fn decode_with_erasures(
+        &self,
+        codeword: &[u8],
+        erasure_positions: &[usize],
+    ) -> Result<Decoded, Errors> {
+        let mut received = FE::from_byte_slice(codeword).ok_or(Errors::InvalidCodeword)?;
+
+        let mut erasures = erasure_positions.to_vec();
+        erasures.extend(Self::non_canonical(codeword));
+        erasures.sort_unstable();
+        erasures.dedup();
+
+        if self.encoding == Encoding::Evaluation {
+            if received.len() != self.total_size {
+                return Err(Errors::InvalidCodeword);
+            }
+
+            // position j holds the coefficient of x^j, the reverse of the systematic layout
+            let n = self.total_size;
+            let flipped: Vec<usize> = erasures.iter().rev().map(|j| n - 1 - j).collect();
+            received.reverse();
+            let mut errors: Vec<usize> = self
+                .correct(&mut received, &flipped)?
+                .into_iter()
+                .map(|j| n - 1 - j)
+                .collect();
+            errors.sort_unstable();
+            received.reverse();
+
+            let message = self.interpolate(received)?.ok_or(Errors::TooManyErrors)?;
+
+            return Ok(Decoded {
+                message: FE::to_byte_vec(&message),
+                errors,
+                erasures,
+            });
+        }
+
+        if !self.punctured.is_empty() {
+            return self.decode_punctured(received, erasures);
+        }
+
+        let errors = self.correct(&mut received, &erasures)?;
+        let message_size = received.len() - self.parity_size;
+
+        Ok(Decoded {
+            message: FE::to_byte_vec(&received[..message_size]),
+            errors,
+            erasures,
+        })
+    }
+
+    /// Fills the punctured symbols of `received` in as erasures before correcting it, and
+    /// maps the positions found back to the transmitted codeword.
+    fn decode_punctured(
+        &self,
+        received: Vec<FE<F>>,
+        erasures: Vec<usize>,
+    ) -> Result<Decoded, Errors> {
+        let kept = self.kept_parity();
+        if received.len() <= kept.len() {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        if erasures.iter().any(|&j| j >= received.len()) {
+            return Err(Errors::InvalidErasure);
+        }
+
+        let message_size = received.len() - kept.len();
+        let mut full = received[..message_size].to_vec();
+        full.resize(message_size + self.parity_size, FE::zero());
+        for (w, &q) in kept.iter().enumerate() {
+            full[message_size + q] = received[message_size + w];
+        }
+
+        let to_full = |j: usize| match j.checked_sub(message_size) {
+            Some(w) => message_size + kept[w],
+            None => j,
+        };
+        let mut full_erasures: Vec<usize> = erasures.iter().map(|&j| to_full(j)).collect();
+        full_erasures.extend(self.punctured.iter().map(|q| message_size + q));
+        full_erasures.sort_unstable();
+
+        let errors = self
+            .correct(&mut full, &full_erasures)?
+            .into_iter()
+            .map(|j| match j.checked_sub(message_size) {
+                // errors are never reported at punctured positions, which are erasures
+                Some(q) => message_size + kept.binary_search(&q).unwrap(),
+                None => j,
+            })
+            .collect();
+
+        Ok(Decoded {
+            message: FE::to_byte_vec(&full[..message_size]),
+            errors,
+            erasures,
+        })
+    }
+
+    /// Recovers the message from a codeword whose only damage is at `erasure_positions`.
+    ///
+    /// Evaluation codes interpolate around the erasures with inverse NTTs in `O(n log n)`,
+    /// systematic codes fall back to [`ReedSolomon::decode_with_erasures`].
+    pub fn recover_erasures(
+        &self,
+        codeword: &[u8],
+        erasure_positions: &[usize],
+    ) -> Result<Decoded, Errors> {
+        if self.encoding == Encoding::Systematic {
+            return self.decode_with_erasures(codeword, erasure_positions);
+        }
+
+        let received = FE::from_byte_slice(codeword).ok_or(Errors::InvalidCodeword)?;
+        if received.len() != self.total_size {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        let mut erasures = erasure_positions.to_vec();
+        erasures.extend(Self::non_canonical(codeword));
+        erasures.sort_unstable();
+        erasures.dedup();
+
+        let message =
+            fft::recover_erasures(&received, &erasures, self.message_size(), &self.generator)?;
+
+        Ok(Decoded {
+            message: FE::to_byte_vec(&message),
+            errors: vec![],
+            erasures,
+        })
+    }
+
+    /// Interpolates the evaluations in `values` and returns the message coefficients, or
+    /// `None` if the polynomial has degree `k` or more.
+    fn interpolate(&self, mut values: Vec<FE<F>>) -> Result<Option<Vec<FE<F>>>, Errors> {
+        if values.len() != self.total_size {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        fft::intt(&mut values, &self.generator)?;
+        if values[self.message_size()..].iter().any(|x| !x.is_zero()) {
+            return Ok(None);
+        }
+
+        values.truncate(self.message_size());
+        Ok(Some(values))
+    }
+
+    /// Corrects `received` in place given sorted, deduplicated erasure positions and
+    /// returns the positions of the errors found outside of the erasures.
+    fn correct(&self, received: &mut [FE<F>], erasures: &[usize]) -> Result<Vec<usize>, Errors> {
+        if received.len() <= self.parity_size
+            || received.len() > self.total_size
+            || received.len() >= F::ORDER
+        {
+            return Err(Errors::InvalidCodeword);
+        }
+
+        if erasures.iter().any(|&j| j >= received.len()) {
+            return Err(Errors::InvalidErasure);
+        }
+
+        if erasures.len() > self.parity_size {
+            return Err(Errors::TooManyErrors);
+        }
+
+        let mut received_poly = Polynomial {
+            coeffs: received.to_vec(),
         };
 
-        let remainder = Polynomial::rem(&message_poly, &self.generator_poly);
+        let syndromes = self.syndromes(&received_poly);
+        if syndromes.iter().all(|s| s.is_zero()) {
+            return Ok(vec![]);
+        }
+
+        // Γ(x) = Π (1 - X_i x) over the erasure locators X_i
+        let mut erasure_locator = vec![FE::<F>::one()];
+        for &j in erasures.iter() {
+            let x = self.generator.pow(received.len() - 1 - j);
+            erasure_locator.push(FE::zero());
+            for i in (1..erasure_locator.len()).rev() {
+                let term = erasure_locator[i - 1] * x;
+                erasure_locator[i] -= term;
+            }
+        }
+
+        let (locator, errata) = berlekamp_massey(&syndromes, erasure_locator);
+        if errata < erasures.len()
+            || 2 * errata - erasures.len() > self.parity_size
+            || locator.len() != errata + 1
+        {
+            return Err(Errors::TooManyErrors);
+        }
 
-        let encoded_poly = Polynomial::sub(&message_poly, &remainder);
+        // Chien search over every position of the received word
+        let positions: Vec<usize> = (0..received.len())
+            .filter(|&j| evaluate_low(&locator, &self.inverse_locator(received.len(), j)).is_zero())
+            .collect();
 
-        let encoded = encoded_poly
-            .coeffs
+        if positions.len() != errata {
+            return Err(Errors::TooManyErrors);
+        }
+
+        // Forney: Ω(x) = S(x)Λ(x) mod x^(n-k), e_j = -X_j^(1-fcr) Ω(X_j^-1) / Λ'(X_j^-1)
+        let omega: Vec<FE<F>> = (0..self.parity_size)
+            .map(|i| {
+                (0..=i)
+                    .filter_map(|j| locator.get(j).map(|l| *l * syndromes[i - j]))
+                    .fold(FE::zero(), |acc, x| acc + x)
+            })
+            .collect();
+        let derivative: Vec<FE<F>> = locator
             .iter()
-            .map(|x| x.to_owned().into())
-            .collect::<Vec<u8>>()
+            .enumerate()
+            .skip(1)
+            .map(|(i, l)| scale(*l, i))
+            .collect();
+
+        for &j in positions.iter() {
+            let x_inv = self.inverse_locator(received.len(), j);
+            let denominator = evaluate_low(&derivative, &x_inv);
+            if denominator.is_zero() {
+                return Err(Errors::TooManyErrors);
+            }
+
+            let x = FE::one() / x_inv;
+            let magnitude =
+                -(x * x_inv.pow(self.first_root) * evaluate_low(&omega, &x_inv) / denominator);
+            received_poly.coeffs[j] -= magnitude;
+        }
+
+        if self.syndromes(&received_poly).iter().any(|s| !s.is_zero()) {
+            return Err(Errors::TooManyErrors);
+        }
+
+        received.copy_from_slice(&received_poly.coeffs);
+
+        Ok(positions
             .into_iter()
-            .map(|x| x as char)
-            .collect::<String>();
+            .filter(|j| erasures.binary_search(j).is_err())
+            .collect())
+    }
+}
 
-        Ok(encoded)
+#[cfg(test)]
+mod tests {
+    use num_traits::{One, Pow, Zero};
+
+    use super::{Decoded, Encoding, ReedSolomon};
+    use crate::backend::gf256::{CcsdsField, QrField};
+    use crate::backend::{gf2_16::GF2_16, gf2_8::GF2k, prime::Mod998244353};
+    use crate::errors::Errors;
+    use crate::ff::{FieldElement, Fp};
+    use crate::matrix::Matrix;
+
+    fn codec() -> ReedSolomon<GF2k> {
+        ReedSolomon::new(32, 22, FieldElement::new(2)).unwrap()
     }
 
-    // TODO: implement fast check
-    pub fn check(&self, message: &str) -> Result<bool, Errors> {
-        if message.len() == 0 {
-            return Err(Errors::InvalidMessage);
+    fn encode(rs: &ReedSolomon<GF2k>, message: &str) -> Vec<u8> {
+        rs.encode(message.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn encode_check() {
+        let rs = codec();
+        let encoded = rs.encode(b"hello world").unwrap();
+
+        assert_eq!(encoded.len(), 21);
+        assert_eq!(&encoded[..11], b"hello world");
+        assert!(rs.check(&encoded).unwrap());
+        assert!(!rs.check(b"hello world").unwrap());
+        assert_eq!(rs.encode(b""), Err(Errors::InvalidMessage));
+    }
+
+    #[test]
+    fn encode_binary() {
+        let rs = codec();
+        let message: Vec<u8> = (0..22).map(|x| 0xff - x * 7).collect();
+        let encoded = rs.encode(&message).unwrap();
+
+        assert_eq!(encoded.len(), 32);
+        assert!(rs.check(&encoded).unwrap());
+        assert_eq!(rs.decode(&encoded).unwrap().message, message);
+    }
+
+    #[test]
+    fn encode_into() {
+        let rs = codec();
+        let encoded = rs.encode(b"hello world").unwrap();
+        let mut parity = [0u8; 10];
+
+        rs.encode_into(b"hello world", &mut parity).unwrap();
+        assert_eq!(&encoded[11..], &parity);
+        assert_eq!(
+            rs.encode_into(b"hello world", &mut [0u8; 9]),
+            Err(Errors::InvalidParity)
+        );
+    }
+
+    #[test]
+    fn encode_slice_tables() {
+        use crate::backend::slice::slice_bytes;
+
+        // the lookup rows are built once per code instead of tables per symbol
+        let rs = codec();
+        rs.encode(b"hello world").unwrap();
+        let before = slice_bytes();
+        rs.encode(b"hello again").unwrap();
+        assert_eq!(slice_bytes(), before);
+
+        // every step multiplies one row of 5 symbols per generator coefficient
+        let messages: Vec<u8> = (0..22 * 5).map(|x| x as u8).collect();
+        rs.encode_interleaved(&messages, 5).unwrap();
+        assert_eq!(slice_bytes() - before, 22 * 10 * 5);
+    }
+
+    fn check_interleaved<F: Fp>(rs: &ReedSolomon<F>, messages: &[u8], depth: usize) {
+        let size = F::SYMBOL_SIZE;
+        let codewords: Vec<Vec<u8>> = messages
+            .chunks(messages.len() / depth)
+            .map(|message| rs.encode(message).unwrap())
+            .collect();
+
+        let encoded = rs.encode_interleaved(messages, depth).unwrap();
+        for (i, codeword) in codewords.iter().enumerate() {
+            for (m, symbol) in codeword.chunks(size).enumerate() {
+                let start = (m * depth + i) * size;
+                assert_eq!(&encoded[start..start + size], symbol);
+            }
         }
+    }
 
-        let mut coeffs: Vec<FE<F>> = message.as_bytes().iter().map(|x| FE::from(*x)).collect();
+    #[test]
+    fn encode_interleaved() {
+        let messages: Vec<u8> = (0..22 * 5).map(|x| (x * 31 + 7) as u8).collect();
+        check_interleaved(&codec(), &messages, 5);
+        check_interleaved(&codec().puncture(&[0, 4]).unwrap(), &messages, 5);
 
-        coeffs.append(vec![FE::zero(); self.parity_size].as_mut());
+        let rs = ReedSolomon::<GF2_16>::new(1000, 960, FieldElement::new(2)).unwrap();
+        let messages: Vec<u8> = (0..960 * 2 * 3).map(|x| (x * 13 + x / 7) as u8).collect();
+        check_interleaved(&rs, &messages, 3);
 
-        let message_poly = Polynomial {
-            coeffs: coeffs.clone(),
-        };
+        let rs = ReedSolomon::new(12, 8, FieldElement::<Mod998244353>::from(3)).unwrap();
+        let messages: Vec<u8> = (0..8 * 4 * 7)
+            .map(|x| if x % 4 == 0 { 0 } else { x as u8 })
+            .collect();
+        check_interleaved(&rs, &messages, 7);
 
-        let remainder = Polynomial::rem(&message_poly, &self.generator_poly);
+        assert_eq!(
+            codec().encode_interleaved(&messages[..22 * 4], 5),
+            Err(Errors::InvalidMessage)
+        );
+        assert_eq!(
+            codec().encode_interleaved(&[], 0),
+            Err(Errors::InvalidMessage)
+        );
+    }
 
-        Ok(remainder.is_zero())
+    #[test]
+    fn decode_clean() {
+        let rs = codec();
+        let codeword = encode(&rs, "hello world");
+
+        assert_eq!(
+            rs.decode(&codeword).unwrap(),
+            Decoded {
+                message: b"hello world".to_vec(),
+                errors: vec![],
+                erasures: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn decode_errors() {
+        let rs = codec();
+        let mut codeword = encode(&rs, "hello world");
+
+        for (pos, val) in [(0, 0), (4, 0xff), (11, 7), (15, 1), (20, 0x80)] {
+            codeword[pos] ^= val ^ 0x5a;
+        }
+
+        let decoded = rs.decode(&codeword).unwrap();
+        assert_eq!(decoded.message, b"hello world".to_vec());
+        assert_eq!(decoded.errors, vec![0, 4, 11, 15, 20]);
+    }
+
+    #[test]
+    fn decode_too_many_errors() {
+        let rs = codec();
+        let mut codeword = encode(&rs, "hello world");
+
+        for byte in codeword.iter_mut().take(6) {
+            *byte ^= 0xa5;
+        }
+
+        assert_eq!(rs.decode(&codeword), Err(Errors::TooManyErrors));
+    }
+
+    #[test]
+    fn decode_invalid_length() {
+        let rs = codec();
+
+        assert_eq!(rs.decode(&[0; 10]), Err(Errors::InvalidCodeword));
+        assert_eq!(rs.decode(&[0; 33]), Err(Errors::InvalidCodeword));
+    }
+
+    #[test]
+    fn decode_erasures_only() {
+        let rs = codec();
+        let mut codeword = encode(&rs, "hello world");
+        let erased = [1, 2, 3, 5, 8, 13, 17, 18, 19, 20];
+
+        for &pos in erased.iter() {
+            codeword[pos] = 0;
+        }
+
+        let decoded = rs.decode_with_erasures(&codeword, &erased).unwrap();
+        assert_eq!(decoded.message, b"hello world".to_vec());
+        assert_eq!(decoded.errors, vec![]);
+        assert_eq!(decoded.erasures, erased.to_vec());
+    }
+
+    #[test]
+    fn decode_errors_and_erasures() {
+        let rs = codec();
+        let mut codeword = encode(&rs, "hello world");
+
+        // 2 * 3 errors + 4 erasures == n - k
+        for pos in [0, 7, 19] {
+            codeword[pos] ^= 0x33;
+        }
+        for pos in [2, 4, 12, 16] {
+            codeword[pos] = 0;
+        }
+
+        let decoded = rs.decode_with_erasures(&codeword, &[16, 2, 12, 4]).unwrap();
+        assert_eq!(decoded.message, b"hello world".to_vec());
+        assert_eq!(decoded.errors, vec![0, 7, 19]);
+        assert_eq!(decoded.erasures, vec![2, 4, 12, 16]);
+    }
+
+    #[test]
+    fn decode_too_many_erasures() {
+        let rs = codec();
+        let codeword = encode(&rs, "hello world");
+        let erased: Vec<usize> = (0..11).collect();
+
+        assert_eq!(
+            rs.decode_with_erasures(&codeword, &erased),
+            Err(Errors::TooManyErrors)
+        );
+        assert_eq!(
+            rs.decode_with_erasures(&codeword, &[21]),
+            Err(Errors::InvalidErasure)
+        );
+    }
+
+    #[test]
+    fn decode_gf2_16() {
+        let rs = ReedSolomon::<GF2_16>::new(1000, 960, FieldElement::new(2)).unwrap();
+        let message: Vec<u8> = (0..1920).map(|x| (x * 13 + x / 256) as u8).collect();
+        let mut codeword = rs.encode(&message).unwrap();
+
+        assert_eq!(codeword.len(), 2000);
+        assert!(rs.check(&codeword).unwrap());
+
+        // symbols 3, 500 and 999 are corrupted, symbols 10 and 700 are erased
+        codeword[6] ^= 0x80;
+        codeword[1001] ^= 0x01;
+        codeword[1998] = 0xff;
+        codeword[20..22].copy_from_slice(&[0, 0]);
+        codeword[1400..1402].copy_from_slice(&[0, 0]);
+
+        let decoded = rs.decode_with_erasures(&codeword, &[10, 700]).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, vec![3, 500, 999]);
+        assert_eq!(rs.encode(&message[1..]), Err(Errors::InvalidMessage));
+    }
+
+    #[test]
+    fn evaluation_encoding() {
+        let root = FieldElement::<Mod998244353>::from(3).pow((998_244_352 / 32) as usize);
+        let rs = ReedSolomon::new_evaluation(32, 20, root).unwrap();
+        let message: Vec<u8> = (0..80)
+            .map(|i| if i % 4 == 0 { 0 } else { (i * 11) as u8 })
+            .collect();
+        let codeword = rs.encode(&message).unwrap();
+
+        assert_eq!(rs.encoding(), Encoding::Evaluation);
+        assert_eq!(codeword.len(), 128);
+        assert!(rs.check(&codeword).unwrap());
+        assert_eq!(rs.encode(&message[4..]), Err(Errors::InvalidMessage));
+        assert_eq!(
+            rs.encode_into(&message, &mut [0; 48]),
+            Err(Errors::NotSystematic)
+        );
+
+        let mut damaged = codeword.clone();
+        damaged[9] ^= 0x04;
+        damaged[70] ^= 0x20;
+        damaged[127] ^= 0x01;
+        damaged[40..44].copy_from_slice(&[0; 4]);
+        assert!(!rs.check(&damaged).unwrap());
+
+        let decoded = rs.decode_with_erasures(&damaged, &[10]).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, vec![2, 17, 31]);
+
+        let mut erased = codeword.clone();
+        let positions: Vec<usize> = (0..12).map(|i| i * 2 + 1).collect();
+        for &j in positions.iter() {
+            erased[j * 4..j * 4 + 4].copy_from_slice(&[0; 4]);
+        }
+        assert_eq!(
+            rs.recover_erasures(&erased, &positions).unwrap().message,
+            message
+        );
+    }
+
+    #[test]
+    fn evaluation_invalid_root() {
+        let root = FieldElement::<Mod998244353>::from(3).pow((998_244_352 / 16) as usize);
+
+        assert!(matches!(
+            ReedSolomon::new_evaluation(32, 20, root),
+            Err(Errors::InvalidRoot)
+        ));
+        assert!(matches!(
+            ReedSolomon::new_evaluation(16, 8, FieldElement::<GF2k>::new(2)),
+            Err(Errors::InvalidRoot)
+        ));
+    }
+
+    #[test]
+    fn builder_defaults() {
+        let rs = ReedSolomon::builder(32, 22, FieldElement::<GF2k>::new(2))
+            .build()
+            .unwrap();
+
+        assert_eq!(rs.first_root(), 1);
+        assert_eq!(rs.encode(b"hello world"), codec().encode(b"hello world"));
+    }
+
+    #[test]
+    fn builder_qr_code() {
+        // version 1-M "HELLO WORLD" from ISO/IEC 18004, roots α^0..α^9
+        let rs = ReedSolomon::builder(26, 16, FieldElement::<QrField>::new(2))
+            .first_root(0)
+            .field_polynomial(0x11d)
+            .build()
+            .unwrap();
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        let codeword = rs.encode(&data).unwrap();
+
+        assert_eq!(
+            &codeword[16..],
+            &[196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+
+        let mut damaged = codeword.clone();
+        damaged[1] = 0;
+        damaged[12] ^= 0x11;
+        damaged[25] = 0;
+        let decoded = rs.decode_with_erasures(&damaged, &[25]).unwrap();
+        assert_eq!(decoded.message, data.to_vec());
+        assert_eq!(decoded.errors, vec![1, 12]);
+    }
+
+    #[test]
+    fn builder_ccsds_roots() {
+        // roots β^112..β^143 with β = α^11 lie symmetrically around β^127.5, so the
+        // generator polynomial is a palindrome
+        let rs = ReedSolomon::builder(255, 223, FieldElement::<CcsdsField>::new(2))
+            .first_root(112)
+            .root_step(11)
+            .field_polynomial(0x187)
+            .build()
+            .unwrap();
+        let coeffs = &rs.generator_poly().coeffs;
+        assert_eq!(coeffs.len(), 33);
+        assert!(coeffs.iter().eq(coeffs.iter().rev()));
+
+        let message: Vec<u8> = (0..223).map(|i| (i * 7 + 3) as u8).collect();
+        let mut codeword = rs.encode(&message).unwrap();
+        assert!(rs.check(&codeword).unwrap());
+
+        let errors: Vec<usize> = (0..16).map(|i| i * 15 + 2).collect();
+        for &j in errors.iter() {
+            codeword[j] ^= 0xa5;
+        }
+        let decoded = rs.decode(&codeword).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.errors, errors);
+    }
+
+    #[test]
+    fn builder_shortened() {
+        let rs = ReedSolomon::builder(255, 239, FieldElement::<QrField>::new(2))
+            .first_root(0)
+            .shortened(204)
+            .build()
+            .unwrap();
+        let full = ReedSolomon::builder(255, 239, FieldElement::<QrField>::new(2))
+            .first_root(0)
+            .build()
+            .unwrap();
+        let message: Vec<u8> = (0..188).map(|i| i as u8).collect();
+        let codeword = rs.encode(&message).unwrap();
+
+        assert_eq!(rs.total_size(), 204);
+        assert_eq!(rs.parity_size(), 16);
+        assert_eq!(codeword.len(), 204);
+        assert_eq!(rs.encode(&[0; 189]), Err(Errors::InvalidMessage));
+        assert_eq!(rs.decode(&[0; 205]), Err(Errors::InvalidCodeword));
+
+        let mut padded = vec![0; 51];
+        padded.extend_from_slice(&message);
+        assert_eq!(full.encode(&padded).unwrap()[51..], codeword[..]);
+    }
+
+    #[test]
+    fn builder_invalid() {
+        let gen = FieldElement::<GF2k>::new(2);
+
+        assert!(matches!(
+            ReedSolomon::builder(32, 22, gen)
+                .field_polynomial(0x11b)
+                .build(),
+            Err(Errors::FieldMismatch)
+        ));
+        assert!(matches!(
+            ReedSolomon::builder(32, 22, gen).shortened(10).build(),
+            Err(Errors::InvalidShortening)
+        ));
+        assert!(matches!(
+            ReedSolomon::builder(32, 22, gen).shortened(33).build(),
+            Err(Errors::TooManyNumbers)
+        ));
+        // α^51 only has order 5
+        assert!(matches!(
+            ReedSolomon::builder(32, 22, gen).root_step(51).build(),
+            Err(Errors::InvalidRoot)
+        ));
+    }
+
+    #[test]
+    fn shortened() {
+        let rs = ReedSolomon::shortened(255, 223, 200, FieldElement::<GF2k>::new(2)).unwrap();
+        let full = ReedSolomon::new(255, 223, FieldElement::<GF2k>::new(2)).unwrap();
+        let message = b"odd-sized packet";
+        let mut codeword = rs.encode(message).unwrap();
+
+        assert_eq!(rs.total_size(), 55);
+        assert_eq!(codeword.len(), 48);
+        assert_eq!(codeword, full.encode(message).unwrap());
+        assert_eq!(rs.encode(&[1; 24]), Err(Errors::InvalidMessage));
+
+        for j in [0, 10, 20, 30, 40] {
+            codeword[j] ^= 0x77;
+        }
+        assert_eq!(rs.decode(&codeword).unwrap().message, message.to_vec());
+        assert!(matches!(
+            ReedSolomon::shortened(32, 22, 22, FieldElement::<GF2k>::new(2)),
+            Err(Errors::InvalidShortening)
+        ));
+    }
+
+    #[test]
+    fn punctured() {
+        let rs = codec().puncture(&[0, 4, 9]).unwrap();
+        let full = codec().encode(b"hello world").unwrap();
+        let mut codeword = encode(&rs, "hello world");
+
+        assert_eq!(rs.total_size(), 29);
+        assert_eq!(rs.punctured(), &[0, 4, 9]);
+        assert_eq!(codeword.len(), 18);
+        assert_eq!(
+            &codeword[11..],
+            &[12, 13, 14, 16, 17, 18, 19].map(|j| full[j])
+        );
+        assert!(rs.check(&codeword).unwrap());
+
+        // punctured symbols count as erasures: 2 * 2 errors + 3 <= n - k
+        codeword[3] ^= 0x01;
+        codeword[16] ^= 0x10;
+        assert!(!rs.check(&codeword).unwrap());
+        let decoded = rs.decode(&codeword).unwrap();
+        assert_eq!(decoded.message, b"hello world".to_vec());
+        assert_eq!(decoded.errors, vec![3, 16]);
+
+        codeword[17] = 0;
+        let decoded = rs.decode_with_erasures(&codeword, &[17]).unwrap();
+        assert_eq!(decoded.message, b"hello world".to_vec());
+        assert_eq!(decoded.erasures, vec![17]);
+
+        // 2 * 4 errors + 1 erasure + 3 > n - k
+        codeword[5] ^= 0x01;
+        codeword[7] ^= 0x01;
+        assert_eq!(
+            rs.decode_with_erasures(&codeword, &[17]),
+            Err(Errors::TooManyErrors)
+        );
+        assert_eq!(rs.decode(&codeword[..7]), Err(Errors::InvalidCodeword));
+    }
+
+    #[test]
+    fn puncture_invalid() {
+        assert!(matches!(
+            codec().puncture(&[10]),
+            Err(Errors::InvalidParity)
+        ));
+        assert!(matches!(
+            codec().puncture(&(0..10).collect::<Vec<_>>()),
+            Err(Errors::TooFewParity)
+        ));
+        assert!(matches!(
+            ReedSolomon::builder(32, 22, FieldElement::<GF2k>::new(2))
+                .punctured(&[1, 1, 2])
+                .build()
+                .map(|rs| rs.punctured().to_vec()),
+            Ok(positions) if positions == vec![1, 2]
+        ));
+
+        let root = FieldElement::<Mod998244353>::from(3).pow((998_244_352 / 32) as usize);
+        let rs = ReedSolomon::new_evaluation(32, 20, root).unwrap();
+        assert!(matches!(rs.puncture(&[0]), Err(Errors::NotSystematic)));
+    }
+
+    #[test]
+    fn update_parity() {
+        let rs = codec();
+        let mut message: Vec<u8> = (0..22).map(|x| x * 9).collect();
+        let mut parity = rs.encode(&message).unwrap()[22..].to_vec();
+
+        rs.update_parity(5, &message[5..8], b"new", &mut parity)
+            .unwrap();
+        message[5..8].copy_from_slice(b"new");
+        assert_eq!(parity, rs.encode(&message).unwrap()[22..]);
+
+        // shorter messages count from the start of the full message
+        let mut parity = encode(&rs, "hello world")[11..].to_vec();
+        rs.update_parity(11, b"h", b"j", &mut parity).unwrap();
+        assert_eq!(parity, encode(&rs, "jello world")[11..]);
+
+        let rs = codec().puncture(&[0, 4, 9]).unwrap();
+        let mut parity = encode(&rs, "hello world")[11..].to_vec();
+        rs.update_parity(21, b"d", b"D", &mut parity).unwrap();
+        assert_eq!(parity, encode(&rs, "hello worlD")[11..]);
+
+        // the signs matter outside characteristic 2
+        let rs = ReedSolomon::new(12, 8, FieldElement::<Mod998244353>::from(3)).unwrap();
+        let message: Vec<u8> = (0..32).collect();
+        let mut parity = rs.encode(&message).unwrap()[32..].to_vec();
+        let mut changed = message.clone();
+        changed[4..8].copy_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+        rs.update_parity(1, &message[4..8], &changed[4..8], &mut parity)
+            .unwrap();
+        assert_eq!(parity, rs.encode(&changed).unwrap()[32..]);
+
+        let rs = codec();
+        let mut parity = vec![0; 10];
+        assert_eq!(
+            rs.update_parity(21, b"ab", b"cd", &mut parity),
+            Err(Errors::InvalidMessage)
+        );
+        assert_eq!(
+            rs.update_parity(0, b"ab", b"c", &mut parity),
+            Err(Errors::InvalidMessage)
+        );
+        assert_eq!(
+            rs.update_parity(0, b"a", b"b", &mut parity[1..]),
+            Err(Errors::InvalidParity)
+        );
+    }
+
+    #[test]
+    fn non_canonical_symbols() {
+        let rs = ReedSolomon::new(12, 8, FieldElement::<Mod998244353>::from(3)).unwrap();
+        let message: Vec<u8> = (0..32).collect();
+        let mut codeword = rs.encode(&message).unwrap();
+
+        // as many symbols at or above the modulus as there are parity symbols, which
+        // only decode as erasures
+        for j in [1, 5, 9, 10] {
+            codeword[4 * j..4 * j + 4].fill(0xff);
+        }
+
+        assert_eq!(rs.check(&codeword), Ok(false));
+        let decoded = rs.decode(&codeword).unwrap();
+        assert_eq!(decoded.message, message);
+        assert!(decoded.errors.is_empty());
+        assert_eq!(decoded.erasures, vec![1, 5, 9, 10]);
+    }
+
+    /// Checks `G·H^T = 0` and that every row of `G` is a codeword.
+    fn check_matrices<F: Fp + std::fmt::Debug>(rs: &ReedSolomon<F>) {
+        let g = rs.generator_matrix();
+        let h = rs.parity_check_matrix();
+        let (k, n) = (rs.message_size(), rs.total_size());
+
+        assert_eq!((g.rows(), g.cols()), (k, n));
+        assert_eq!((h.rows(), h.cols()), (n - k, n));
+        assert_eq!(g.multiply(&h.transpose()).unwrap(), Matrix::new(k, n - k));
+        assert_eq!(
+            g.select_cols(&(0..k).collect::<Vec<_>>()),
+            Matrix::identity(k)
+        );
+
+        for j in 0..k {
+            assert!(rs.check(&FieldElement::to_byte_vec(g.row(j))).unwrap());
+        }
+    }
+
+    #[test]
+    fn generator_matrix() {
+        let rs = codec();
+        check_matrices(&rs);
+
+        // encoding multiplies by G, and H·c vanishes on codewords only
+        let message = b"generator matrix test!";
+        let codeword = FieldElement::from_byte_slice(&rs.encode(message).unwrap()).unwrap();
+        let row = Matrix::from_rows(vec![FieldElement::from_byte_slice(message).unwrap()])
+            .unwrap()
+            .multiply(&rs.generator_matrix())
+            .unwrap();
+        assert_eq!(row.row(0), &codeword[..]);
+
+        let h = rs.parity_check_matrix();
+        assert!(h
+            .multiply_vec(&codeword)
+            .unwrap()
+            .iter()
+            .all(|s| s.is_zero()));
+        let mut received = codeword.clone();
+        received[3] += FieldElement::one();
+        assert!(!h
+            .multiply_vec(&received)
+            .unwrap()
+            .iter()
+            .all(|s| s.is_zero()));
+
+        check_matrices(&codec().puncture(&[0, 4, 9]).unwrap());
+        check_matrices(
+            &ReedSolomon::<GF2k>::shortened(255, 223, 200, FieldElement::new(2)).unwrap(),
+        );
+
+        let root = FieldElement::<Mod998244353>::from(3).pow((998_244_352 / 32) as usize);
+        check_matrices(&ReedSolomon::new_evaluation(32, 20, root).unwrap());
+    }
+
+    #[test]
+    fn dual_code() {
+        // β = 2^17 has order 15
+        let rs = ReedSolomon::builder(15, 11, FieldElement::<GF2k>::new(2))
+            .root_step(17)
+            .build()
+            .unwrap();
+        let dual = rs.dual_code().unwrap();
+        assert_eq!((dual.total_size(), dual.message_size()), (15, 4));
+        assert_eq!(dual.first_root(), 0);
+        assert_eq!(
+            rs.generator_matrix()
+                .multiply(&dual.generator_matrix().transpose())
+                .unwrap(),
+            Matrix::new(11, 4)
+        );
+        assert_eq!(
+            dual.dual_code().unwrap().generator_matrix(),
+            rs.generator_matrix()
+        );
+
+        let ccsds = ReedSolomon::builder(255, 223, FieldElement::<CcsdsField>::new(2))
+            .first_root(112)
+            .root_step(11)
+            .build()
+            .unwrap();
+        let dual = ccsds.dual_code().unwrap();
+        assert_eq!(dual.first_root(), 144);
+        assert_eq!(
+            ccsds
+                .generator_matrix()
+                .multiply(&dual.generator_matrix().transpose())
+                .unwrap(),
+            Matrix::new(223, 32)
+        );
+
+        assert!(matches!(codec().dual_code(), Err(Errors::NotCyclic)));
+        assert!(matches!(
+            ReedSolomon::<GF2k>::shortened(255, 223, 100, FieldElement::new(2))
+                .unwrap()
+                .dual_code(),
+            Err(Errors::NotCyclic)
+        ));
     }
 }
@@ -0,0 +1,455 @@
+//! Encoding and decoding of byte streams of unknown length, one interleaving group of a
+//! [`BlockCodec`] at a time.
+//!
+//! The stream is a sequence of encoded groups, the last payload group zero padded,
+//! followed by a trailer holding the payload length as a big-endian `u64`, zero padded to
+//! whole groups.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+use crate::block::{BlockCodec, HEADER_SIZE};
+use crate::errors::Errors;
+use crate::ff::Fp;
+
+fn invalid_data(e: Errors) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Number of groups taken by the trailer
+fn trailer_groups<F: Fp>(codec: &BlockCodec<F>) -> usize {
+    HEADER_SIZE.div_ceil(codec.group_size())
+}
+
+/// Encodes everything written to it into `inner`.
+///
+/// [`RsWriter::finish`] must be called to write the trailer, dropping the writer does so
+/// on a best-effort basis and ignores errors.
+#[derive(Debug)]
+pub struct RsWriter<W: Write, F: Fp> {
+    inner: Option<W>,
+    codec: BlockCodec<F>,
+    buffer: Vec<u8>,
+    written: u64,
+}
+
+impl<W: Write, F: Fp> RsWriter<W, F> {
+    pub fn new(inner: W, codec: BlockCodec<F>) -> Self {
+        let buffer = Vec::with_capacity(codec.group_size());
+
+        RsWriter {
+            inner: Some(inner),
+            codec,
+            buffer,
+            written: 0,
+        }
+    }
+
+    /// Number of payload bytes written so far
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Encodes `data` into `inner`, zero padded to a whole number of groups.
+    fn write_groups(codec: &BlockCodec<F>, inner: &mut W, data: &[u8]) -> io::Result<()> {
+        for chunk in data.chunks(codec.group_size()) {
+            let mut group = chunk.to_vec();
+            group.resize(codec.group_size(), 0);
+
+            let encoded = codec.encode_group(&group).map_err(invalid_data)?;
+            inner.write_all(&encoded)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the last group and the trailer. The underlying writer is taken first, so
+    /// that a failed trailer is never retried and appended twice on drop.
+    fn write_trailer(&mut self) -> io::Result<W> {
+        let mut inner = self.inner.take().unwrap();
+        Self::write_groups(&self.codec, &mut inner, &self.buffer)?;
+        Self::write_groups(&self.codec, &mut inner, &self.written.to_be_bytes())?;
+        inner.flush()?;
+
+        Ok(inner)
+    }
+
+    /// Writes the last group and the trailer, and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_trailer()
+    }
+}
+
+impl<W: Write, F: Fp> Write for RsWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let group_size = self.codec.group_size();
+        let len = buf.len().min(group_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+
+        if self.buffer.len() == group_size {
+            let inner = self.inner.as_mut().unwrap();
+            if let Err(e) = Self::write_groups(&self.codec, inner, &self.buffer) {
+                // none of `buf` is taken, so the write can be retried
+                self.buffer.truncate(group_size - len);
+                return Err(e);
+            }
+            self.buffer.clear();
+        }
+
+        self.written += len as u64;
+        Ok(len)
+    }
+
+    /// Flushes the underlying writer. Buffered bytes short of a whole group stay
+    /// buffered, as padding them would corrupt the stream.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write, F: Fp> Drop for RsWriter<W, F> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_trailer();
+        }
+    }
+}
+
+/// Correction statistics of a group that needed repair
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockStats {
+    /// Index of the group in the stream
+    pub index: u64,
+    /// Number of symbols corrected in the group
+    pub errors: usize,
+}
+
+/// Correction statistics of a stream
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// Groups decoded so far, including the trailer
+    pub blocks: u64,
+    /// Symbols corrected so far
+    pub errors: u64,
+    /// Groups that needed correction, in stream order
+    pub corrected: Vec<BlockStats>,
+}
+
+/// Decodes a stream written by [`RsWriter`], correcting every group on the fly.
+///
+/// Uncorrectable groups and malformed streams surface as [`io::ErrorKind::InvalidData`]
+/// errors wrapping [`Errors`].
+#[derive(Debug)]
+pub struct RsReader<R: Read, F: Fp> {
+    inner: R,
+    codec: BlockCodec<F>,
+    /// Decoded groups held back until it is known whether they are payload or trailer
+    pending: VecDeque<Vec<u8>>,
+    buffer: Vec<u8>,
+    pos: usize,
+    read: u64,
+    done: bool,
+    stats: StreamStats,
+}
+
+impl<R: Read, F: Fp> RsReader<R, F> {
+    pub fn new(inner: R, codec: BlockCodec<F>) -> Self {
+        let pending = VecDeque::with_capacity(trailer_groups(&codec) + 2);
+
+        RsReader {
+            inner,
+            codec,
+            pending,
+            buffer: vec![],
+            pos: 0,
+            read: 0,
+            done: false,
+            stats: StreamStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> &StreamStats {
+        &self.stats
+    }
+
+    /// Reads a whole encoded group, or returns `None` at the end of the stream.
+    fn read_group(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut group = vec![0; self.codec.encoded_group_size()];
+        let mut filled = 0;
+
+        while filled < group.len() {
+            match self.inner.read(&mut group[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        match filled {
+            0 => Ok(None),
+            n if n == group.len() => Ok(Some(group)),
+            _ => Err(invalid_data(Errors::InvalidCodeword)),
+        }
+    }
+
+    /// Refills the buffer with the next payload group.
+    fn fill(&mut self) -> io::Result<()> {
+        while !self.done && self.pos == self.buffer.len() {
+            let Some(group) = self.read_group()? else {
+                return self.finish();
+            };
+
+            let decoded = self.codec.decode_group(&group).map_err(invalid_data)?;
+            if !decoded.errors.is_empty() {
+                self.stats.corrected.push(BlockStats {
+                    index: self.stats.blocks,
+                    errors: decoded.errors.len(),
+                });
+                self.stats.errors += decoded.errors.len() as u64;
+            }
+            self.stats.blocks += 1;
+
+            self.pending.push_back(decoded.message);
+            if self.pending.len() > trailer_groups(&self.codec) + 1 {
+                self.buffer = self.pending.pop_front().unwrap();
+                self.pos = 0;
+                self.read += self.buffer.len() as u64;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Strips the padding off the last payload group using the length in the trailer.
+    fn finish(&mut self) -> io::Result<()> {
+        self.done = true;
+
+        let groups = trailer_groups(&self.codec);
+        if self.pending.len() < groups {
+            return Err(invalid_data(Errors::InvalidCodeword));
+        }
+
+        let trailer: Vec<u8> = self
+            .pending
+            .drain(self.pending.len() - groups..)
+            .flatten()
+            .collect();
+        let len = u64::from_be_bytes(trailer[..HEADER_SIZE].try_into().unwrap());
+
+        let mut last = self.pending.pop_front().unwrap_or_default();
+        let remaining = len
+            .checked_sub(self.read)
+            .filter(|&r| r <= last.len() as u64)
+            .ok_or_else(|| invalid_data(Errors::InvalidCodeword))?;
+        last.truncate(remaining as usize);
+
+        self.buffer = last;
+        self.pos = 0;
+        self.read = len;
+
+        Ok(())
+    }
+}
+
+impl<R: Read, F: Fp> Read for RsReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill()?;
+
+        let len = buf.len().min(self.buffer.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::backend::gf2_8::GF2k;
+    use crate::ff::FieldElement;
+    use crate::reedsolomon::ReedSolomon;
+
+    fn codec() -> BlockCodec<GF2k> {
+        let rs = ReedSolomon::new(32, 24, FieldElement::new(2)).unwrap();
+        BlockCodec::new(rs).interleaved(2).unwrap()
+    }
+
+    fn encode(data: &[u8]) -> Vec<u8> {
+        let mut writer = RsWriter::new(vec![], codec());
+        // uneven writes to cross group boundaries
+        for chunk in data.chunks(13) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        for len in [0usize, 1, 47, 48, 49, 96, 1000] {
+            let data: Vec<u8> = (0..len).map(|x| (x * 3) as u8).collect();
+            let encoded = encode(&data);
+            assert_eq!(encoded.len(), (len.div_ceil(48) + 1) * 64);
+
+            let mut reader = RsReader::new(&encoded[..], codec());
+            let mut decoded = vec![];
+            reader.read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+            assert_eq!(reader.stats().blocks, len.div_ceil(48) as u64 + 1);
+            assert!(reader.stats().corrected.is_empty());
+        }
+    }
+
+    #[test]
+    fn drop_writes_trailer() {
+        let mut encoded = vec![];
+        {
+            let mut writer = RsWriter::new(&mut encoded, codec());
+            writer.write_all(b"dropped without finish").unwrap();
+        }
+
+        let mut decoded = String::new();
+        RsReader::new(&encoded[..], codec())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "dropped without finish");
+    }
+
+    /// Writer into a shared buffer failing its first `fail_writes` writes, and every
+    /// flush if `fail_flush`
+    struct Flaky {
+        out: Rc<RefCell<Vec<u8>>>,
+        fail_writes: usize,
+        fail_flush: bool,
+    }
+
+    impl Write for Flaky {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.fail_writes > 0 {
+                self.fail_writes -= 1;
+                return Err(io::Error::other("write failed"));
+            }
+
+            self.out.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            if self.fail_flush {
+                return Err(io::Error::other("flush failed"));
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_writes() {
+        let data: Vec<u8> = (0..100).collect();
+
+        // the trailer is written once even though finish failed before drop
+        let out = Rc::new(RefCell::new(vec![]));
+        let flaky = Flaky {
+            out: out.clone(),
+            fail_writes: 0,
+            fail_flush: true,
+        };
+        let mut writer = RsWriter::new(flaky, codec());
+        writer.write_all(&data).unwrap();
+        assert!(writer.finish().is_err());
+        assert_eq!(*out.borrow(), encode(&data));
+
+        // a group that failed to write is kept for the retry
+        let out = Rc::new(RefCell::new(vec![]));
+        let flaky = Flaky {
+            out: out.clone(),
+            fail_writes: 1,
+            fail_flush: false,
+        };
+        let mut writer = RsWriter::new(flaky, codec());
+        writer.write_all(&data[..40]).unwrap();
+        assert!(writer.write_all(&data[40..]).is_err());
+        assert_eq!(writer.written(), 40);
+        writer.write_all(&data[40..]).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(*out.borrow(), encode(&data));
+    }
+
+    #[test]
+    fn corrections() {
+        let data = vec![0x55; 500];
+        let mut encoded = encode(&data);
+        for j in [3, 200, 201, 202, 203, 700] {
+            encoded[j] ^= 0xf0;
+        }
+
+        let mut reader = RsReader::new(&encoded[..], codec());
+        let mut decoded = vec![];
+        reader.read_to_end(&mut decoded).unwrap();
+
+        assert_eq!(decoded, data);
+        assert_eq!(reader.stats().errors, 6);
+        assert_eq!(
+            reader.stats().corrected,
+            vec![
+                BlockStats {
+                    index: 0,
+                    errors: 1
+                },
+                BlockStats {
+                    index: 3,
+                    errors: 4
+                },
+                BlockStats {
+                    index: 10,
+                    errors: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn uncorrectable() {
+        let mut encoded = encode(&[7; 100]);
+        for byte in encoded[64..84].iter_mut() {
+            *byte = !*byte;
+        }
+
+        let err = RsReader::new(&encoded[..], codec())
+            .read_to_end(&mut vec![])
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.into_inner().unwrap().downcast::<Errors>().unwrap(),
+            Box::new(Errors::TooManyErrors)
+        );
+
+        let err = RsReader::new(&encoded[..100], codec())
+            .read_to_end(&mut vec![])
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn small_groups() {
+        // 3 byte groups need 3 groups for the trailer
+        let rs = ReedSolomon::<GF2k>::new(7, 3, FieldElement::new(2)).unwrap();
+        let data = b"tiny blocks".to_vec();
+
+        let mut writer = RsWriter::new(vec![], BlockCodec::new(rs));
+        writer.write_all(&data).unwrap();
+        let encoded = writer.finish().unwrap();
+        assert_eq!(encoded.len(), (4 + 3) * 7);
+
+        let rs = ReedSolomon::<GF2k>::new(7, 3, FieldElement::new(2)).unwrap();
+        let mut decoded = vec![];
+        RsReader::new(&encoded[..], BlockCodec::new(rs))
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, data);
+    }
+}